@@ -204,6 +204,8 @@ fn dynamic_linking(major: usize, minor: usize) {
     println!("cargo:rustc-link-lib=dylib=nccl");
     #[cfg(feature = "nvrtc")]
     println!("cargo:rustc-link-lib=dylib=nvrtc");
+    #[cfg(feature = "nvjitlink")]
+    println!("cargo:rustc-link-lib=dylib=nvJitLink");
     #[cfg(feature = "curand")]
     println!("cargo:rustc-link-lib=dylib=curand");
     #[cfg(feature = "cublas")]
@@ -277,6 +279,8 @@ fn static_linking(major: usize, minor: usize) {
         println!("cargo:rustc-link-lib=static:+whole-archive=nvptxcompiler_static");
         println!("cargo:rustc-link-lib=static:+whole-archive=nvrtc-builtins_static");
     }
+    #[cfg(feature = "nvjitlink")]
+    println!("cargo:rustc-link-lib=static:+whole-archive=nvJitLink_static");
     #[cfg(any(
         feature = "curand",
         feature = "cublas",
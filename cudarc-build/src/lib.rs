@@ -0,0 +1,1062 @@
+//! Compiles `.cu` files with nvcc from a build script, and generates a Rust module that embeds
+//! the results for [cudarc](https://docs.rs/cudarc).
+//!
+//! ```no_run
+//! // In build.rs's `main()`:
+//! cudarc_build::Build::new()
+//!     .dir("src/kernels")
+//!     .arch("sm_80")
+//!     .arch("sm_90")
+//!     .compile()
+//!     .unwrap();
+//! ```
+//!
+//! Each kernel file becomes a constant and a function returning a `cudarc::nvrtc::Ptx`,
+//! named after the file and architecture:
+//!
+//! ```ignore
+//! mod kernels {
+//!     include!(concat!(env!("OUT_DIR"), "/kernels.rs"));
+//! }
+//!
+//! let module = ctx.load_module(kernels::my_kernel_sm_80())?;
+//! ```
+//!
+//! Files are only recompiled when they, a header they include, or the nvcc command line
+//! changed.
+//!
+//! nvcc is searched for in the `NVCC` environment variable, then as `bin/nvcc` under the
+//! same roots cudarc's own build script searches for the CUDA libraries: `CUDA_HOME`,
+//! `CUDA_PATH`, `CUDA_ROOT`, `CUDA_TOOLKIT_ROOT_DIR` or `CUDNN_LIB`, or if none of those is set
+//! the typical install locations in the same order (`/usr` first). Under each root,
+//! versioned installs like `CUDA/v12.4` (the layout of
+//! `C:/Program Files/NVIDIA GPU Computing Toolkit`) are searched too, newest first.
+//! Finally nvcc is looked up on `PATH`.
+//!
+//! If `CUDARC_CUDA_VERSION` is set, e.g. to `12040`, the first nvcc of that version is used,
+//! so kernels are compiled with the toolkit cudarc's bindings were selected for.
+
+use std::{
+    ffi::OsString,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    time::SystemTime,
+};
+
+/// Environment variables that point to the CUDA toolkit, in the order they are searched.
+/// The same as in cudarc's `build.rs`.
+const CUDA_PATH_ENV_VARS: [&str; 5] = [
+    "CUDA_HOME",
+    "CUDA_PATH",
+    "CUDA_ROOT",
+    "CUDA_TOOLKIT_ROOT_DIR",
+    "CUDNN_LIB",
+];
+
+/// Where CUDA is usually installed, searched if none of [CUDA_PATH_ENV_VARS] is set. The
+/// same as in cudarc's `build.rs`, apart from cuDNN's own install directory.
+const TYPICAL_LOCATIONS: &[&str] = &[
+    "/usr",
+    "/usr/local/cuda",
+    "/opt/cuda",
+    "/usr/lib/cuda",
+    "C:/Program Files/NVIDIA GPU Computing Toolkit",
+    "C:/Program Files/NVIDIA",
+    "C:/CUDA",
+];
+
+/// Selects the CUDA version, like in cudarc's `build.rs`.
+const CUDA_VERSION_ENV_VAR: &str = "CUDARC_CUDA_VERSION";
+
+const NVCC: &str = if cfg!(windows) { "nvcc.exe" } else { "nvcc" };
+
+/// An error from [Nvcc::find()] or [Build::compile()].
+#[derive(Debug)]
+pub enum Error {
+    /// No nvcc was found, see the [crate] docs for where it is searched.
+    NvccNotFound,
+    /// `nvcc --version` printed something unexpected.
+    UnknownVersion(String),
+    /// `CUDARC_CUDA_VERSION` is not a version like `12040`.
+    InvalidCudaVersion(String),
+    /// nvcc failed to compile `source`.
+    NvccFailed {
+        source: PathBuf,
+        command: String,
+        stderr: String,
+    },
+    /// An architecture that isn't `sm_XX`, or `compute_XX` for PTX.
+    InvalidArch(String),
+    /// [Build::compile()] was called without any architecture.
+    NoArch,
+    /// Neither [Build::out_dir()] nor `OUT_DIR` was set.
+    NoOutDir,
+    /// Two sources would write the same output files and generate the same items, e.g.
+    /// `a/k.cu` and `b/k.cu`, or `my-k.cu` and `my_k.cu`.
+    NameCollision {
+        name: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NvccNotFound => write!(
+                f,
+                "nvcc (of the {CUDA_VERSION_ENV_VAR} version, if set) not found. Set NVCC, or one of {CUDA_PATH_ENV_VARS:?} to the CUDA toolkit"
+            ),
+            Self::UnknownVersion(output) => {
+                write!(f, "unexpected `nvcc --version` output:\n{output}")
+            }
+            Self::InvalidCudaVersion(version) => {
+                write!(f, "invalid {CUDA_VERSION_ENV_VAR} `{version}`, expected e.g. `12040`")
+            }
+            Self::NvccFailed {
+                source,
+                command,
+                stderr,
+            } => write!(
+                f,
+                "nvcc failed to compile {}\n{command}\n{}",
+                source.display(),
+                stderr.trim_end()
+            ),
+            Self::InvalidArch(arch) => write!(f, "invalid architecture `{arch}`"),
+            Self::NoArch => write!(f, "no architecture to compile for"),
+            Self::NoOutDir => write!(f, "OUT_DIR is not set"),
+            Self::NameCollision {
+                name,
+                first,
+                second,
+            } => write!(
+                f,
+                "{} and {} both generate `{name}`, rename one of them",
+                first.display(),
+                second.display()
+            ),
+            Self::Io(path, e) => write!(f, "{}: {e}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn io_err(path: &Path) -> impl FnOnce(io::Error) -> Error + '_ {
+    move |e| Error::Io(path.to_path_buf(), e)
+}
+
+/// An nvcc executable and its version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nvcc {
+    path: PathBuf,
+    version: (usize, usize),
+}
+
+impl Nvcc {
+    /// Finds nvcc, see the [crate] docs for where.
+    pub fn find() -> Result<Self, Error> {
+        let env = |var: &str| std::env::var(var).ok();
+        let wanted = env(CUDA_VERSION_ENV_VAR)
+            .map(|version| parse_cuda_version(&version))
+            .transpose()?;
+        nvcc_candidates(env)
+            .into_iter()
+            .filter_map(|path| Self::at(path).ok())
+            .find(|nvcc| wanted.is_none() || wanted == Some(nvcc.version))
+            .ok_or(Error::NvccNotFound)
+    }
+
+    /// The nvcc at `path`, which is run to get its version.
+    pub fn at(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let output = Command::new(&path)
+            .arg("--version")
+            .output()
+            .map_err(io_err(&path))?;
+        if !output.status.success() {
+            return Err(Error::NvccNotFound);
+        }
+        let version = parse_version(&String::from_utf8_lossy(&output.stdout))?;
+        Ok(Self { path, version })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The CUDA toolkit version, e.g. `(12, 4)`.
+    pub fn version(&self) -> (usize, usize) {
+        self.version
+    }
+
+    /// The toolkit's `include` directory, e.g. for bindgen, if nvcc is in a `bin` directory
+    /// next to it.
+    pub fn include_dir(&self) -> Option<PathBuf> {
+        let root = self.path.parent()?.parent()?;
+        Some(root.join("include")).filter(|dir| dir.is_dir())
+    }
+}
+
+/// Where nvcc may be, in the order to try them.
+fn nvcc_candidates(env: impl Fn(&str) -> Option<String>) -> Vec<PathBuf> {
+    if let Some(nvcc) = env("NVCC") {
+        return vec![nvcc.into()];
+    }
+    let roots: Vec<String> = CUDA_PATH_ENV_VARS
+        .iter()
+        .filter_map(|var| env(var))
+        .collect();
+    let roots = if roots.is_empty() {
+        TYPICAL_LOCATIONS.iter().map(|&root| root.into()).collect()
+    } else {
+        roots
+    };
+    let mut candidates = Vec::new();
+    for root in roots.into_iter().map(PathBuf::from) {
+        candidates.push(root.join("bin").join(NVCC));
+        for install in versioned_installs(&root) {
+            candidates.push(install.join("bin").join(NVCC));
+        }
+    }
+    // Whatever is on `PATH`.
+    candidates.push(NVCC.into());
+    candidates
+}
+
+/// The `CUDA/vX.Y` directories under `root`, newest first.
+fn versioned_installs(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root.join("CUDA")) else {
+        return Vec::new();
+    };
+    let mut installs: Vec<((usize, usize), PathBuf)> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let (major, minor) = name.strip_prefix('v')?.split_once('.')?;
+            let version = (major.parse().ok()?, minor.parse().ok()?);
+            Some((version, path))
+        })
+        .collect();
+    installs.sort_by_key(|(version, _)| std::cmp::Reverse(*version));
+    installs.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Parses a version like `12040` as cudarc's `build.rs` reads `CUDARC_CUDA_VERSION`.
+fn parse_cuda_version(version: &str) -> Result<(usize, usize), Error> {
+    let invalid = || Error::InvalidCudaVersion(version.into());
+    let code: usize = version.parse().map_err(|_| invalid())?;
+    if version.len() != 5 || !code.is_multiple_of(10) {
+        return Err(invalid());
+    }
+    Ok((code / 1000, code % 1000 / 10))
+}
+
+/// Parses the `release` of `nvcc --version`, e.g. `(12, 4)` from
+/// `Cuda compilation tools, release 12.4, V12.4.131`.
+fn parse_version(stdout: &str) -> Result<(usize, usize), Error> {
+    stdout
+        .lines()
+        .find_map(|line| line.split_once("release ")?.1.split(',').next())
+        .and_then(|release| release.trim().split_once('.'))
+        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+        .ok_or_else(|| Error::UnknownVersion(stdout.into()))
+}
+
+/// What [Build] compiles to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Output {
+    /// One PTX file per source and architecture, embedded as `&str`.
+    #[default]
+    Ptx,
+    /// One cubin per source and architecture, embedded as `&[u8]`. Requires `sm_XX`
+    /// architectures.
+    Cubin,
+    /// One fatbin per source with every architecture, plus the PTX of each so newer GPUs
+    /// can JIT it, embedded as `&[u8]`. Requires `sm_XX` architectures.
+    Fatbin,
+}
+
+impl Output {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Ptx => "ptx",
+            Self::Cubin => "cubin",
+            Self::Fatbin => "fatbin",
+        }
+    }
+}
+
+/// One compiled file, from [Build::compile()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub source: PathBuf,
+    /// The architecture, or `None` for a [Output::Fatbin] of all of them.
+    pub arch: Option<String>,
+    pub path: PathBuf,
+    /// Whether nvcc ran, rather than the previous output being up to date.
+    pub rebuilt: bool,
+}
+
+/// Compiles `.cu` files with nvcc and writes a Rust module embedding the results. See the
+/// [crate] docs.
+#[derive(Debug, Clone)]
+pub struct Build {
+    nvcc: Option<Nvcc>,
+    files: Vec<PathBuf>,
+    dirs: Vec<PathBuf>,
+    arches: Vec<String>,
+    output: Output,
+    includes: Vec<PathBuf>,
+    defines: Vec<(String, Option<String>)>,
+    flags: Vec<String>,
+    out_dir: Option<PathBuf>,
+    module: String,
+    cargo_metadata: bool,
+}
+
+impl Default for Build {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Build {
+    pub fn new() -> Self {
+        Self {
+            nvcc: None,
+            files: Vec::new(),
+            dirs: Vec::new(),
+            arches: Vec::new(),
+            output: Output::Ptx,
+            includes: Vec::new(),
+            defines: Vec::new(),
+            flags: Vec::new(),
+            out_dir: None,
+            module: "kernels".into(),
+            cargo_metadata: true,
+        }
+    }
+
+    /// Uses this nvcc instead of [Nvcc::find()].
+    pub fn nvcc(mut self, nvcc: Nvcc) -> Self {
+        self.nvcc = Some(nvcc);
+        self
+    }
+
+    /// Compiles a `.cu` file.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(path.into());
+        self
+    }
+
+    /// Compiles every `.cu` file directly in `dir`. Files in subdirectories, like shared
+    /// headers, are not compiled themselves.
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dirs.push(dir.into());
+        self
+    }
+
+    /// Adds an architecture, e.g. `sm_80`. PTX can also be compiled for `compute_80`.
+    pub fn arch(mut self, arch: impl Into<String>) -> Self {
+        self.arches.push(arch.into());
+        self
+    }
+
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Adds an include directory, `-I`.
+    pub fn include(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.includes.push(dir.into());
+        self
+    }
+
+    /// Defines a macro, `-D<name>` or `-D<name>=<value>`.
+    pub fn define(mut self, name: impl Into<String>, value: Option<&str>) -> Self {
+        self.defines.push((name.into(), value.map(Into::into)));
+        self
+    }
+
+    /// Passes another flag to nvcc, e.g. `-O3` or `--use_fast_math`.
+    pub fn flag(mut self, flag: impl Into<String>) -> Self {
+        self.flags.push(flag.into());
+        self
+    }
+
+    /// Where to write outputs and the module. Defaults to `OUT_DIR`.
+    pub fn out_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(dir.into());
+        self
+    }
+
+    /// The generated module is written to `<name>.rs`. Defaults to `kernels`.
+    pub fn module_name(mut self, name: impl Into<String>) -> Self {
+        self.module = name.into();
+        self
+    }
+
+    /// Whether to print `cargo:rerun-if-changed` lines for sources, headers and directories.
+    /// Defaults to `true`.
+    pub fn cargo_metadata(mut self, enabled: bool) -> Self {
+        self.cargo_metadata = enabled;
+        self
+    }
+
+    /// Compiles every source that is out of date, then writes the module.
+    pub fn compile(self) -> Result<Vec<Artifact>, Error> {
+        if self.arches.is_empty() {
+            return Err(Error::NoArch);
+        }
+        for arch in self.arches.iter() {
+            let valid = arch.starts_with("sm_")
+                || (self.output == Output::Ptx && arch.starts_with("compute_"));
+            if !valid {
+                return Err(Error::InvalidArch(arch.clone()));
+            }
+        }
+        let out_dir = match self.out_dir.clone() {
+            Some(dir) => dir,
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(Error::NoOutDir)?,
+        };
+        fs::create_dir_all(&out_dir).map_err(io_err(&out_dir))?;
+        let nvcc = match self.nvcc.clone() {
+            Some(nvcc) => nvcc,
+            None => Nvcc::find()?,
+        };
+        if self.cargo_metadata {
+            println!("cargo:rerun-if-env-changed=NVCC");
+            println!("cargo:rerun-if-env-changed={CUDA_VERSION_ENV_VAR}");
+            for var in CUDA_PATH_ENV_VARS {
+                println!("cargo:rerun-if-env-changed={var}");
+            }
+            // New or removed files.
+            for dir in self.dirs.iter() {
+                println!("cargo:rerun-if-changed={}", dir.display());
+            }
+        }
+
+        let sources = self.sources()?;
+        self.check_names(&sources)?;
+        let mut artifacts = Vec::new();
+        for source in sources.iter() {
+            for arch in self.targets() {
+                artifacts.push(self.compile_one(&nvcc, source, arch, &out_dir)?);
+            }
+        }
+
+        let module = out_dir.join(format!("{}.rs", self.module));
+        let contents = self.module_source(&artifacts);
+        // Rewriting an unchanged module would make cargo rebuild the crate.
+        if fs::read_to_string(&module).ok().as_deref() != Some(contents.as_str()) {
+            fs::write(&module, contents).map_err(io_err(&module))?;
+        }
+        Ok(artifacts)
+    }
+
+    /// The files to compile, sorted within each directory.
+    fn sources(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut sources = self.files.clone();
+        for dir in self.dirs.iter() {
+            let mut found = Vec::new();
+            for entry in fs::read_dir(dir).map_err(io_err(dir))? {
+                let path = entry.map_err(io_err(dir))?.path();
+                if path.is_file() && path.extension().is_some_and(|ext| ext == "cu") {
+                    found.push(path);
+                }
+            }
+            found.sort();
+            sources.extend(found);
+        }
+        Ok(sources)
+    }
+
+    /// The architectures each source is compiled for, `None` meaning all for a fatbin.
+    fn targets(&self) -> Vec<Option<&str>> {
+        match self.output {
+            Output::Fatbin => vec![None],
+            _ => self.arches.iter().map(|a| Some(a.as_str())).collect(),
+        }
+    }
+
+    /// The name of the items generated for `source` compiled for `arch`.
+    fn item_name(source: &Path, arch: Option<&str>) -> String {
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        match arch {
+            Some(arch) => identifier(&format!("{stem}_{arch}")),
+            None => identifier(&stem),
+        }
+    }
+
+    /// Fails if two sources would overwrite each other's outputs or generate the same
+    /// items. Outputs are named after the file stem, so sharing an item name covers both.
+    fn check_names(&self, sources: &[PathBuf]) -> Result<(), Error> {
+        let mut seen: Vec<(String, &PathBuf)> = Vec::new();
+        for source in sources {
+            for arch in self.targets() {
+                let name = Self::item_name(source, arch);
+                if let Some((_, first)) = seen.iter().find(|(n, _)| *n == name) {
+                    return Err(Error::NameCollision {
+                        name,
+                        first: first.to_path_buf(),
+                        second: source.clone(),
+                    });
+                }
+                seen.push((name, source));
+            }
+        }
+        Ok(())
+    }
+
+    /// The file `source` compiles to for `arch`, `None` meaning all for a fatbin.
+    fn output_path(&self, out_dir: &Path, source: &Path, arch: Option<&str>) -> PathBuf {
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = self.output.extension();
+        match arch {
+            Some(arch) => out_dir.join(format!("{stem}.{arch}.{ext}")),
+            None => out_dir.join(format!("{stem}.{ext}")),
+        }
+    }
+
+    /// The nvcc arguments that compile `source` to `out`, writing its dependencies to
+    /// `depfile`.
+    fn args(&self, source: &Path, arch: Option<&str>, out: &Path, depfile: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![format!("-{}", self.output.extension()).into()];
+        match arch {
+            Some(arch) => args.push(format!("-arch={arch}").into()),
+            None => {
+                for arch in self.arches.iter() {
+                    let virt = arch.replacen("sm_", "compute_", 1);
+                    args.push(format!("--generate-code=arch={virt},code=[{virt},{arch}]").into());
+                }
+            }
+        }
+        for dir in self.includes.iter() {
+            let mut include = OsString::from("-I");
+            include.push(dir);
+            args.push(include);
+        }
+        for (name, value) in self.defines.iter() {
+            match value {
+                Some(value) => args.push(format!("-D{name}={value}").into()),
+                None => args.push(format!("-D{name}").into()),
+            }
+        }
+        args.extend(self.flags.iter().map(Into::into));
+        args.extend(["-MD".into(), "-MF".into(), depfile.into()]);
+        args.extend(["-o".into(), out.into(), source.into()]);
+        args
+    }
+
+    fn compile_one(
+        &self,
+        nvcc: &Nvcc,
+        source: &Path,
+        arch: Option<&str>,
+        out_dir: &Path,
+    ) -> Result<Artifact, Error> {
+        let path = self.output_path(out_dir, source, arch);
+        let depfile = with_suffix(&path, ".d");
+        let stamp = with_suffix(&path, ".cmd");
+        let args = self.args(source, arch, &path, &depfile);
+        let command = command_line(nvcc.path(), &args);
+
+        let deps = fs::read_to_string(&depfile)
+            .map(|d| parse_depfile(&d))
+            .unwrap_or_default();
+        let up_to_date = fs::read_to_string(&stamp).ok().as_deref() == Some(command.as_str())
+            && is_newer_than(&path, deps.iter().map(PathBuf::as_path).chain([source]));
+
+        if !up_to_date {
+            let output = Command::new(nvcc.path())
+                .args(&args)
+                .output()
+                .map_err(io_err(nvcc.path()))?;
+            if !output.status.success() {
+                // So the next build doesn't think the old output is current.
+                let _ = fs::remove_file(&stamp);
+                return Err(Error::NvccFailed {
+                    source: source.to_path_buf(),
+                    command,
+                    stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                });
+            }
+            fs::write(&stamp, &command).map_err(io_err(&stamp))?;
+        }
+
+        if self.cargo_metadata {
+            println!("cargo:rerun-if-changed={}", source.display());
+            let deps = fs::read_to_string(&depfile)
+                .map(|d| parse_depfile(&d))
+                .unwrap_or_default();
+            for dep in deps.iter().filter(|dep| dep.as_path() != source) {
+                println!("cargo:rerun-if-changed={}", dep.display());
+            }
+        }
+
+        Ok(Artifact {
+            source: source.to_path_buf(),
+            arch: arch.map(Into::into),
+            path,
+            rebuilt: !up_to_date,
+        })
+    }
+
+    /// A constant and a `Ptx` function for each artifact.
+    fn module_source(&self, artifacts: &[Artifact]) -> String {
+        let mut src = String::from("// Generated by cudarc-build. Do not edit.\n");
+        for artifact in artifacts {
+            let name = Self::item_name(&artifact.source, artifact.arch.as_deref());
+            let konst = name.to_uppercase();
+            let path = format!("{:?}", artifact.path.display().to_string());
+            let (ty, include, ptx) = match self.output {
+                Output::Ptx => ("&str", "include_str", format!("from_src({konst})")),
+                _ => (
+                    "&[u8]",
+                    "include_bytes",
+                    format!("from_binary({konst}.to_vec())"),
+                ),
+            };
+            let target = match artifact.arch.as_ref() {
+                Some(arch) => format!("`{arch}`"),
+                None => format!("`{}`", self.arches.join("`, `")),
+            };
+            src += &format!(
+                "\n/// `{}` compiled for {target}.\n\
+                pub const {konst}: {ty} = {include}!({path});\n\
+                \n\
+                /// [{konst}] as a `Ptx`, for `CudaContext::load_module()`.\n\
+                pub fn {name}() -> ::cudarc::nvrtc::Ptx {{\n    \
+                    ::cudarc::nvrtc::Ptx::{ptx}\n\
+                }}\n",
+                artifact.source.display(),
+            );
+        }
+        src
+    }
+}
+
+/// `path` with `suffix` appended to the file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
+
+/// Whether `path` exists and was modified after each of `inputs`.
+fn is_newer_than<'a>(path: &Path, mut inputs: impl Iterator<Item = &'a Path>) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    let Some(output) = modified(path) else {
+        return false;
+    };
+    inputs.all(|input| modified(input).is_some_and(|input: SystemTime| input <= output))
+}
+
+/// The command line for error messages and the stamp file.
+fn command_line(nvcc: &Path, args: &[OsString]) -> String {
+    let mut line = nvcc.display().to_string();
+    for arg in args {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    line
+}
+
+/// The prerequisites of a Makefile rule like nvcc's `-MF` writes.
+fn parse_depfile(depfile: &str) -> Vec<PathBuf> {
+    let joined = depfile.replace("\\\r\n", " ").replace("\\\n", " ");
+    let Some((_, prerequisites)) = joined.split_once(": ") else {
+        return Vec::new();
+    };
+    let mut deps = Vec::new();
+    let mut current = String::new();
+    let mut chars = prerequisites.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&' ') => {
+                current.push(' ');
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    deps.push(PathBuf::from(std::mem::take(&mut current)));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        deps.push(current.into());
+    }
+    deps
+}
+
+/// `name` as a Rust identifier: lowercase, with anything but letters and digits as `_`.
+fn identifier(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION: &str = "nvcc: NVIDIA (R) Cuda compiler driver
+Copyright (c) 2005-2024 NVIDIA Corporation
+Built on Thu_Mar_28_02:18:24_PDT_2024
+Cuda compilation tools, release 12.4, V12.4.131
+Build cuda_12.4.r12.4/compiler.34097967_0
+";
+
+    /// A fresh directory in the system temp dir.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cudarc-build-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn args(build: &Build, arch: Option<&str>) -> Vec<String> {
+        build
+            .args(
+                Path::new("k.cu"),
+                arch,
+                Path::new("k.out"),
+                Path::new("k.d"),
+            )
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version(VERSION).unwrap(), (12, 4));
+        assert_eq!(
+            parse_version("Cuda compilation tools, release 11.8, V11.8.89").unwrap(),
+            (11, 8)
+        );
+        assert!(matches!(
+            parse_version("nvcc: command not found"),
+            Err(Error::UnknownVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_nvcc_candidates() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |var: &str| {
+                vars.iter()
+                    .find(|(name, _)| *name == var)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let bin = |root: &str| Path::new(root).join("bin").join(NVCC);
+
+        assert_eq!(
+            nvcc_candidates(env(&[("NVCC", "/x/nvcc"), ("CUDA_HOME", "/cuda")])),
+            [PathBuf::from("/x/nvcc")]
+        );
+        assert_eq!(
+            nvcc_candidates(env(&[("CUDA_PATH", "/b"), ("CUDA_HOME", "/a")])),
+            [bin("/a"), bin("/b"), NVCC.into()]
+        );
+        let typical = nvcc_candidates(env(&[]));
+        assert_eq!(typical[0], bin("/usr"));
+        assert_eq!(typical[1], bin("/usr/local/cuda"));
+        assert!(typical.contains(&bin("C:/Program Files/NVIDIA GPU Computing Toolkit")));
+        assert_eq!(typical.last().unwrap(), Path::new(NVCC));
+
+        let dir = temp_dir("versioned");
+        for version in ["v11.8", "v12.10", "v12.4", "notes"] {
+            fs::create_dir_all(dir.join("CUDA").join(version)).unwrap();
+        }
+        assert_eq!(
+            versioned_installs(&dir),
+            [
+                dir.join("CUDA/v12.10"),
+                dir.join("CUDA/v12.4"),
+                dir.join("CUDA/v11.8")
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_cuda_version() {
+        assert_eq!(parse_cuda_version("12040").unwrap(), (12, 4));
+        assert_eq!(parse_cuda_version("13020").unwrap(), (13, 2));
+        assert_eq!(parse_cuda_version("11080").unwrap(), (11, 8));
+        for invalid in ["12.4", "1204", "12041", ""] {
+            assert!(matches!(
+                parse_cuda_version(invalid),
+                Err(Error::InvalidCudaVersion(v)) if v == invalid
+            ));
+        }
+    }
+
+    #[test]
+    fn test_name_collisions() {
+        let build = Build::new().arch("sm_80");
+        let sources = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert!(build
+            .check_names(&sources(&["a/k.cu", "a/sin.cu", "b/k2.cu"]))
+            .is_ok());
+        assert!(matches!(
+            build.check_names(&sources(&["a/k.cu", "b/k.cu"])),
+            Err(Error::NameCollision { name, first, second })
+                if name == "k_sm_80" && first == Path::new("a/k.cu") && second == Path::new("b/k.cu")
+        ));
+        assert!(matches!(
+            build.check_names(&sources(&["my-k.cu", "my_k.cu"])),
+            Err(Error::NameCollision { name, .. }) if name == "my_k_sm_80"
+        ));
+        let fatbin = build.output(Output::Fatbin);
+        assert!(matches!(
+            fatbin.check_names(&sources(&["K.cu", "k.cu"])),
+            Err(Error::NameCollision { name, .. }) if name == "k"
+        ));
+    }
+
+    #[test]
+    fn test_args() {
+        let build = Build::new()
+            .arch("sm_80")
+            .arch("sm_90a")
+            .include("inc")
+            .define("N", Some("4"))
+            .define("FAST", None)
+            .flag("-O3");
+        assert_eq!(
+            args(&build, Some("sm_80")),
+            [
+                "-ptx",
+                "-arch=sm_80",
+                "-Iinc",
+                "-DN=4",
+                "-DFAST",
+                "-O3",
+                "-MD",
+                "-MF",
+                "k.d",
+                "-o",
+                "k.out",
+                "k.cu"
+            ]
+        );
+
+        let build = build.output(Output::Fatbin);
+        let fatbin = args(&build, None);
+        assert_eq!(fatbin[0], "-fatbin");
+        assert_eq!(
+            fatbin[1..3],
+            [
+                "--generate-code=arch=compute_80,code=[compute_80,sm_80]",
+                "--generate-code=arch=compute_90a,code=[compute_90a,sm_90a]"
+            ]
+        );
+
+        let build = Build::new().output(Output::Cubin);
+        assert_eq!(args(&build, Some("sm_75"))[..2], ["-cubin", "-arch=sm_75"]);
+        assert_eq!(
+            build.output_path(Path::new("out"), Path::new("src/sin.cu"), Some("sm_75")),
+            Path::new("out").join("sin.sm_75.cubin")
+        );
+    }
+
+    #[test]
+    fn test_parse_depfile() {
+        let deps = parse_depfile(
+            "out/k.ptx : \\\n  src/k.cu \\\n  src/inc/common.cuh /usr/local/cuda/include/cuda_runtime.h \\\n  my\\ dir/a.h\n",
+        );
+        assert_eq!(
+            deps,
+            [
+                PathBuf::from("src/k.cu"),
+                "src/inc/common.cuh".into(),
+                "/usr/local/cuda/include/cuda_runtime.h".into(),
+                "my dir/a.h".into(),
+            ]
+        );
+        assert!(parse_depfile("").is_empty());
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier("my-kernel_sm_80"), "my_kernel_sm_80");
+        assert_eq!(identifier("2d.conv"), "_2d_conv");
+        assert_eq!(identifier("Sin"), "sin");
+    }
+
+    #[test]
+    fn test_invalid_builds() {
+        assert!(matches!(Build::new().compile(), Err(Error::NoArch)));
+        assert!(matches!(
+            Build::new().arch("compute_80").output(Output::Cubin).compile(),
+            Err(Error::InvalidArch(arch)) if arch == "compute_80"
+        ));
+        assert!(matches!(
+            Build::new().arch("80").compile(),
+            Err(Error::InvalidArch(_))
+        ));
+    }
+
+    /// A shell script that answers `--version` and "compiles" by copying the source and
+    /// writing a depfile that lists `common.cuh`, logging each compile.
+    #[cfg(unix)]
+    fn fake_nvcc(dir: &Path) -> Nvcc {
+        use std::os::unix::fs::PermissionsExt;
+
+        let log = dir.join("nvcc.log");
+        let script = format!(
+            r#"#!/bin/sh
+if [ "$1" = "--version" ]; then
+    cat <<'EOF'
+{VERSION}EOF
+    exit 0
+fi
+while [ $# -gt 0 ]; do
+    case "$1" in
+        -o) out="$2"; shift ;;
+        -MF) dep="$2"; shift ;;
+        *.cu) src="$1" ;;
+    esac
+    shift
+done
+echo "$src" >> "{log}"
+if grep -q syntax_error "$src"; then
+    echo "$src(1): error: expected a declaration" >&2
+    exit 1
+fi
+cp "$src" "$out"
+echo "$out: $src $(dirname "$src")/common.cuh" > "$dep"
+"#,
+            log = log.display()
+        );
+        let path = dir.join("nvcc");
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        Nvcc::at(path).unwrap()
+    }
+
+    #[cfg(unix)]
+    fn compiles(dir: &Path) -> usize {
+        fs::read_to_string(dir.join("nvcc.log"))
+            .unwrap_or_default()
+            .lines()
+            .count()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compile_with_fake_nvcc() {
+        let dir = temp_dir("compile");
+        let nvcc = fake_nvcc(&dir);
+        assert_eq!(nvcc.version(), (12, 4));
+
+        let src = dir.join("kernels");
+        let out = dir.join("out");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("sin.cu"), "// sin").unwrap();
+        fs::write(src.join("my-cos.cu"), "// cos").unwrap();
+        fs::write(src.join("common.cuh"), "// common").unwrap();
+        fs::write(src.join("notes.txt"), "").unwrap();
+        let build = Build::new()
+            .nvcc(nvcc)
+            .dir(&src)
+            .arch("sm_80")
+            .arch("sm_90")
+            .out_dir(&out)
+            .cargo_metadata(false);
+
+        let artifacts = build.clone().compile().unwrap();
+        let names: Vec<_> = artifacts
+            .iter()
+            .map(|a| a.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "my-cos.sm_80.ptx",
+                "my-cos.sm_90.ptx",
+                "sin.sm_80.ptx",
+                "sin.sm_90.ptx"
+            ]
+        );
+        assert!(artifacts.iter().all(|a| a.rebuilt));
+        assert_eq!(
+            fs::read_to_string(out.join("sin.sm_90.ptx")).unwrap(),
+            "// sin"
+        );
+        assert_eq!(compiles(&dir), 4);
+
+        let module = fs::read_to_string(out.join("kernels.rs")).unwrap();
+        assert!(module.contains("pub const MY_COS_SM_80: &str = include_str!("));
+        assert!(module.contains("pub fn sin_sm_90() -> ::cudarc::nvrtc::Ptx {"));
+        assert!(module.contains("::cudarc::nvrtc::Ptx::from_src(SIN_SM_90)"));
+
+        // Nothing changed.
+        let artifacts = build.clone().compile().unwrap();
+        assert!(artifacts.iter().all(|a| !a.rebuilt));
+        assert_eq!(compiles(&dir), 4);
+
+        // A header from the depfile changed.
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(src.join("common.cuh"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let artifacts = build.clone().compile().unwrap();
+        assert!(artifacts.iter().all(|a| a.rebuilt));
+        assert_eq!(compiles(&dir), 8);
+        fs::File::options()
+            .write(true)
+            .open(src.join("common.cuh"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        // The command line changed.
+        let artifacts = build.clone().flag("-O3").compile().unwrap();
+        assert!(artifacts.iter().all(|a| a.rebuilt));
+        assert_eq!(compiles(&dir), 12);
+
+        // Fatbins hold every architecture.
+        let artifacts = build.clone().output(Output::Fatbin).compile().unwrap();
+        assert_eq!(artifacts.len(), 2);
+        assert_eq!(artifacts[1].path, out.join("sin.fatbin"));
+        assert_eq!(artifacts[1].arch, None);
+        let module = fs::read_to_string(out.join("kernels.rs")).unwrap();
+        assert!(module.contains("pub const SIN: &[u8] = include_bytes!("));
+        assert!(module.contains("from_binary(SIN.to_vec())"));
+
+        fs::write(src.join("bad.cu"), "syntax_error").unwrap();
+        match build.compile() {
+            Err(Error::NvccFailed { source, stderr, .. }) => {
+                assert_eq!(source, src.join("bad.cu"));
+                assert!(stderr.contains("expected a declaration"));
+            }
+            r => panic!("{r:?}"),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
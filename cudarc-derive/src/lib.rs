@@ -0,0 +1,214 @@
+//! Derive macros for [cudarc](https://docs.rs/cudarc). Enable cudarc's `derive` feature and use
+//! them through `cudarc::driver::{DeviceRepr, ValidAsZeroBits}` and `cudarc::types::CudaTypeName`:
+//!
+//! ```ignore
+//! use cudarc::{driver::{DeviceRepr, ValidAsZeroBits}, types::CudaTypeName};
+//!
+//! #[derive(Clone, Copy, DeviceRepr, ValidAsZeroBits, CudaTypeName)]
+//! #[repr(C)]
+//! struct Particle {
+//!     pos: [f32; 3],
+//!     mass: f64,
+//! }
+//!
+//! // Prepend the matching cuda struct to your kernel source.
+//! let src = format!("{}{KERNEL_SRC}", Particle::definition());
+//! ```
+//!
+//! All three derives only accept `#[repr(C)]` (or `#[repr(transparent)]`) structs that aren't
+//! `#[repr(packed)]`, and require every field to implement the derived trait.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, parse_quote_spanned, spanned::Spanned, Data, DeriveInput, Expr,
+    Fields, Type,
+};
+
+/// Implements `cudarc::driver::DeviceRepr` for a `#[repr(C)]` struct whose fields are all `DeviceRepr`.
+#[proc_macro_derive(DeviceRepr)]
+pub fn derive_device_repr(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_marker(&input, "DeviceRepr", quote!(::cudarc::driver::DeviceRepr))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `cudarc::driver::ValidAsZeroBits` for a `#[repr(C)]` struct whose fields are all `ValidAsZeroBits`.
+#[proc_macro_derive(ValidAsZeroBits)]
+pub fn derive_valid_as_zero_bits(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_marker(
+        &input,
+        "ValidAsZeroBits",
+        quote!(::cudarc::driver::ValidAsZeroBits),
+    )
+    .unwrap_or_else(syn::Error::into_compile_error)
+    .into()
+}
+
+/// Implements `cudarc::types::CudaTypeName` for a `#[repr(C)]` struct, naming it after the rust
+/// struct and generating a matching cuda struct definition with `static_assert`s on its size and
+/// alignment, and those of each field.
+#[proc_macro_derive(CudaTypeName)]
+pub fn derive_cuda_type_name(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_cuda_type_name(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_marker(
+    input: &DeriveInput,
+    trait_name: &str,
+    trait_path: TokenStream2,
+) -> syn::Result<TokenStream2> {
+    check_repr(input, trait_name)?;
+    let fields = struct_fields(input, trait_name)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+    for field in fields.iter() {
+        let ty = &field.ty;
+        where_clause
+            .predicates
+            .push(parse_quote_spanned!(ty.span()=> #ty: #trait_path));
+    }
+
+    Ok(quote! {
+        unsafe impl #impl_generics #trait_path for #name #ty_generics #where_clause {}
+    })
+}
+
+fn expand_cuda_type_name(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    check_repr(input, "CudaTypeName")?;
+    let fields = struct_fields(input, "CudaTypeName")?;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "#[derive(CudaTypeName)] does not support generic structs",
+        ));
+    }
+    if fields.is_empty() {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "#[derive(CudaTypeName)] requires at least one field, empty structs have different sizes in rust and c++",
+        ));
+    }
+
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let type_name = quote!(::cudarc::types::CudaTypeName);
+
+    let mut bounds = Vec::new();
+    let mut members = Vec::new();
+    let mut asserts = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let member = match field.ident.as_ref() {
+            Some(ident) => ident.to_string(),
+            None => format!("_{i}"),
+        };
+        let (elem, dims) = split_array(&field.ty);
+        let bound: syn::WherePredicate = parse_quote_spanned!(elem.span()=> #elem: #type_name);
+        bounds.push(bound);
+
+        let fmt = format!("    {{}} {member}{};", "[{}]".repeat(dims.len()));
+        members.push(quote! {
+            ::core::writeln!(f, #fmt, <#elem as #type_name>::NAME #(, #dims)*)?;
+        });
+
+        let ty = &field.ty;
+        let size_fmt = format!(
+            "static_assert(sizeof({name_str}::{member}) == {{}}, \"{name_str}::{member} has a different size in rust\");"
+        );
+        let align_fmt = format!(
+            "static_assert(alignof(decltype({name_str}::{member})) == {{}}, \"{name_str}::{member} has a different alignment in rust\");"
+        );
+        asserts.push(quote! {
+            ::core::writeln!(f, #size_fmt, ::core::mem::size_of::<#ty>())?;
+            ::core::writeln!(f, #align_fmt, ::core::mem::align_of::<#ty>())?;
+        });
+    }
+
+    let open = format!("struct {name_str} {{{{");
+    let size_fmt = format!(
+        "static_assert(sizeof({name_str}) == {{}}, \"{name_str} has a different size in rust\");"
+    );
+    let align_fmt = format!(
+        "static_assert(alignof({name_str}) == {{}}, \"{name_str} has a different alignment in rust\");"
+    );
+
+    Ok(quote! {
+        impl #type_name for #name where #(#bounds,)* {
+            const NAME: &'static str = #name_str;
+
+            fn write_definition(f: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
+                ::core::writeln!(f, #open)?;
+                #(#members)*
+                ::core::writeln!(f, "}};")?;
+                ::core::writeln!(f, #size_fmt, ::core::mem::size_of::<Self>())?;
+                ::core::writeln!(f, #align_fmt, ::core::mem::align_of::<Self>())?;
+                #(#asserts)*
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Only `#[repr(C)]` and `#[repr(transparent)]` have a layout that is stable and matches c++.
+/// `#[repr(packed)]` is rejected, since the cuda struct would not be packed.
+fn check_repr(input: &DeriveInput, trait_name: &str) -> syn::Result<()> {
+    let mut has_repr = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") || meta.path.is_ident("transparent") {
+                has_repr = true;
+            } else if meta.path.is_ident("packed") {
+                return Err(meta.error(format!(
+                    "#[derive({trait_name})] does not support #[repr(packed)]"
+                )));
+            } else if meta.input.peek(syn::token::Paren) {
+                // e.g. `align(8)`
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream2>()?;
+            }
+            Ok(())
+        })?;
+    }
+    if has_repr {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            input.ident.span(),
+            format!("#[derive({trait_name})] requires #[repr(C)] or #[repr(transparent)]"),
+        ))
+    }
+}
+
+fn struct_fields<'a>(input: &'a DeriveInput, trait_name: &str) -> syn::Result<Vec<&'a syn::Field>> {
+    match &input.data {
+        Data::Struct(data) => Ok(match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => Vec::new(),
+        }),
+        _ => Err(syn::Error::new(
+            input.ident.span(),
+            format!("#[derive({trait_name})] is only supported on structs"),
+        )),
+    }
+}
+
+/// Splits `[[T; N]; M]` into `T` and `[M, N]`.
+fn split_array(ty: &Type) -> (&Type, Vec<&Expr>) {
+    let mut dims = Vec::new();
+    let mut ty = ty;
+    while let Type::Array(array) = ty {
+        dims.push(&array.len);
+        ty = &array.elem;
+    }
+    (ty, dims)
+}
@@ -1,9 +1,9 @@
 use cudarc::{
-    driver::{CudaContext, DriverError, LaunchConfig, PushKernelArg},
+    driver::{CudaContext, GlobalError, LaunchConfig, PushKernelArg},
     nvrtc::compile_ptx,
 };
 
-fn main() -> Result<(), DriverError> {
+fn main() -> Result<(), GlobalError> {
     let ctx = CudaContext::new(0)?;
     let stream = ctx.default_stream();
 
@@ -11,19 +11,19 @@ fn main() -> Result<(), DriverError> {
     let ptx = compile_ptx(include_str!("./constant_memory.cu")).expect("compile failure");
     let module = ctx.load_module(ptx)?;
 
-    // Get the constant memory symbol as a CudaSlice<u8>
-    let mut coefficients_symbol = module.get_global("coefficients", &stream)?;
+    // Get the constant memory symbol as a typed `[f32; 4]`. This fails if the size
+    // of the symbol doesn't match.
+    let mut coefficients_symbol = module.global::<[f32; 4]>("coefficients")?;
     println!(
         "Constant memory symbol 'coefficients' has {} bytes",
-        coefficients_symbol.len()
+        coefficients_symbol.num_bytes()
     );
 
     // Set up polynomial coefficients: 1.0 + 2.0*x + 3.0*x^2 + 4.0*x^3
     let coefficients = [1.0f32, 2.0, 3.0, 4.0];
 
-    // Transmute the symbol to f32 and copy coefficients to constant memory
-    let mut symbol_f32 = unsafe { coefficients_symbol.transmute_mut::<f32>(4).unwrap() };
-    stream.memcpy_htod(&coefficients, &mut symbol_f32)?;
+    // Copy coefficients to constant memory
+    coefficients_symbol.write(&stream, &coefficients)?;
 
     // Load the kernel function
     let polynomial_kernel = module.load_function("polynomial_kernel")?;
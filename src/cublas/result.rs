@@ -1,4 +1,5 @@
 use super::sys::{self};
+use crate::ErrorKind;
 use core::ffi::{c_int, c_longlong, c_void};
 use core::mem::MaybeUninit;
 
@@ -24,6 +25,21 @@ impl std::fmt::Display for CublasError {
 #[cfg(feature = "std")]
 impl std::error::Error for CublasError {}
 
+impl CublasError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cublasStatus_t::CUBLAS_STATUS_ALLOC_FAILED => ErrorKind::OutOfMemory,
+            sys::cublasStatus_t::CUBLAS_STATUS_INVALID_VALUE => ErrorKind::InvalidValue,
+            sys::cublasStatus_t::CUBLAS_STATUS_NOT_SUPPORTED
+            | sys::cublasStatus_t::CUBLAS_STATUS_ARCH_MISMATCH => ErrorKind::NotSupported,
+            sys::cublasStatus_t::CUBLAS_STATUS_NOT_INITIALIZED => ErrorKind::NotInitialized,
+            sys::cublasStatus_t::CUBLAS_STATUS_EXECUTION_FAILED => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// Creates a handle to the cuBLAS library. See
 /// [nvidia docs](https://docs.nvidia.com/cuda/cublas/index.html#cublascreate)
 pub fn create_handle() -> Result<sys::cublasHandle_t, CublasError> {
@@ -1,5 +1,6 @@
 use super::sys::{self};
 use crate::cublaslt::sys::cublasLtMatmulAlgo_t;
+use crate::ErrorKind;
 use core::ffi::c_void;
 use core::mem::MaybeUninit;
 
@@ -25,6 +26,21 @@ impl std::fmt::Display for CublasError {
 #[cfg(feature = "std")]
 impl std::error::Error for CublasError {}
 
+impl CublasError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cublasStatus_t::CUBLAS_STATUS_ALLOC_FAILED => ErrorKind::OutOfMemory,
+            sys::cublasStatus_t::CUBLAS_STATUS_INVALID_VALUE => ErrorKind::InvalidValue,
+            sys::cublasStatus_t::CUBLAS_STATUS_NOT_SUPPORTED
+            | sys::cublasStatus_t::CUBLAS_STATUS_ARCH_MISMATCH => ErrorKind::NotSupported,
+            sys::cublasStatus_t::CUBLAS_STATUS_NOT_INITIALIZED => ErrorKind::NotInitialized,
+            sys::cublasStatus_t::CUBLAS_STATUS_EXECUTION_FAILED => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// Creates a handle to the cuBLASLT library. See
 /// [nvidia docs](https://docs.nvidia.com/cuda/cublas/index.html#cublasltcreate)
 pub fn create_handle() -> Result<sys::cublasLtHandle_t, CublasError> {
@@ -3,6 +3,7 @@
 use std::mem::MaybeUninit;
 
 use super::sys::{self};
+use crate::ErrorKind;
 
 pub type CudnnResult<T> = Result<T, CudnnError>;
 
@@ -29,6 +30,20 @@ impl std::fmt::Display for CudnnError {
 #[cfg(feature = "std")]
 impl std::error::Error for CudnnError {}
 
+impl CudnnError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cudnnStatus_t::CUDNN_STATUS_BAD_PARAM
+            | sys::cudnnStatus_t::CUDNN_STATUS_INVALID_VALUE => ErrorKind::InvalidValue,
+            sys::cudnnStatus_t::CUDNN_STATUS_NOT_SUPPORTED => ErrorKind::NotSupported,
+            sys::cudnnStatus_t::CUDNN_STATUS_NOT_INITIALIZED => ErrorKind::NotInitialized,
+            sys::cudnnStatus_t::CUDNN_STATUS_EXECUTION_FAILED => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// This function returns the version number of the cuDNN library. It returns the CUDNN_VERSION defined present in the cudnn.h header file.
 ///
 /// See [nvidia docs](https://docs.nvidia.com/deeplearning/cudnn/api/index.html#cudnnGetVersion)
@@ -2,6 +2,7 @@ use core::ffi::{c_int, c_longlong, c_void};
 use core::mem::MaybeUninit;
 
 use super::sys;
+use crate::ErrorKind;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct CufftError(pub sys::cufftResult);
@@ -26,6 +27,26 @@ impl std::fmt::Display for CufftError {
 #[cfg(feature = "std")]
 impl std::error::Error for CufftError {}
 
+impl CufftError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cufftResult::CUFFT_ALLOC_FAILED => ErrorKind::OutOfMemory,
+            sys::cufftResult::CUFFT_INVALID_VALUE
+            | sys::cufftResult::CUFFT_INVALID_SIZE
+            | sys::cufftResult::CUFFT_UNALIGNED_DATA
+            | sys::cufftResult::CUFFT_INVALID_DEVICE => ErrorKind::InvalidValue,
+            sys::cufftResult::CUFFT_NOT_SUPPORTED | sys::cufftResult::CUFFT_NOT_IMPLEMENTED => {
+                ErrorKind::NotSupported
+            }
+            sys::cufftResult::CUFFT_SETUP_FAILED => ErrorKind::NotInitialized,
+            sys::cufftResult::CUFFT_INVALID_PLAN => ErrorKind::InvalidHandle,
+            sys::cufftResult::CUFFT_EXEC_FAILED => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// See [cuda docs](https://docs.nvidia.com/cuda/cufft/#cufftcreate)
 pub fn create() -> Result<sys::cufftHandle, CufftError> {
     let mut handle = MaybeUninit::uninit();
@@ -1,6 +1,7 @@
 use std::mem::MaybeUninit;
 
 use super::sys;
+use crate::ErrorKind;
 
 /// Wrapper around [sys::CUfileOpError] and [std::io::Error]
 #[derive(Debug)]
@@ -37,6 +38,29 @@ impl std::fmt::Display for CufileError {
 #[cfg(feature = "std")]
 impl std::error::Error for CufileError {}
 
+impl CufileError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::IO(_) => ErrorKind::Io,
+            #[cfg(any(feature = "cuda-13000", feature = "cuda-13010", feature = "cuda-13020"))]
+            Self::Cufile(sys::CUfileOpError::CU_FILE_NOMEM_ERROR) => ErrorKind::OutOfMemory,
+            Self::Cufile(sys::CUfileOpError::CU_FILE_INVALID_VALUE) => ErrorKind::InvalidValue,
+            Self::Cufile(sys::CUfileOpError::CU_FILE_DRIVER_NOT_INITIALIZED) => {
+                ErrorKind::NotInitialized
+            }
+            Self::Cufile(
+                sys::CUfileOpError::CU_FILE_PLATFORM_NOT_SUPPORTED
+                | sys::CUfileOpError::CU_FILE_IO_NOT_SUPPORTED
+                | sys::CUfileOpError::CU_FILE_DEVICE_NOT_SUPPORTED,
+            ) => ErrorKind::NotSupported,
+            #[cfg(any(feature = "cuda-13000", feature = "cuda-13010", feature = "cuda-13020"))]
+            Self::Cufile(sys::CUfileOpError::CU_FILE_IO_ERROR) => ErrorKind::Io,
+            Self::Cufile(_) => ErrorKind::Other,
+        }
+    }
+}
+
 /// See [cuda docs](https://docs.nvidia.com/gpudirect-storage/api-reference-guide/index.html#cufiledriveropen)
 pub fn driver_open() -> Result<(), CufileError> {
     unsafe { sys::cuFileDriverOpen() }.result()
@@ -2,6 +2,7 @@ use core::ffi::CStr;
 
 use super::sys;
 use crate::driver;
+use crate::ErrorKind;
 
 pub mod activity;
 
@@ -53,6 +54,25 @@ impl std::fmt::Display for CuptiError {
 #[cfg(feature = "std")]
 impl std::error::Error for CuptiError {}
 
+impl CuptiError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::CUptiResult::CUPTI_ERROR_OUT_OF_MEMORY => ErrorKind::OutOfMemory,
+            sys::CUptiResult::CUPTI_ERROR_INVALID_PARAMETER
+            | sys::CUptiResult::CUPTI_ERROR_INVALID_KIND
+            | sys::CUptiResult::CUPTI_ERROR_INVALID_DEVICE => ErrorKind::InvalidValue,
+            sys::CUptiResult::CUPTI_ERROR_NOT_SUPPORTED
+            | sys::CUptiResult::CUPTI_ERROR_API_NOT_IMPLEMENTED => ErrorKind::NotSupported,
+            sys::CUptiResult::CUPTI_ERROR_NOT_INITIALIZED => ErrorKind::NotInitialized,
+            sys::CUptiResult::CUPTI_ERROR_INVALID_HANDLE
+            | sys::CUptiResult::CUPTI_ERROR_INVALID_CONTEXT
+            | sys::CUptiResult::CUPTI_ERROR_INVALID_STREAM => ErrorKind::InvalidHandle,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// Check support for a compute capability.
 ///
 /// See [cuptiComputeCapabilitySupported()](https://docs.nvidia.com/cupti/api/group__CUPTI__ACTIVITY__API.html#group__cupti__activity__api_1ga22c5ce610ffbf5940b7c05be54fc813d).
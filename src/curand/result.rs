@@ -5,6 +5,7 @@
 //! 2. Generic: See [UniformFill], [NormalFill], and [LogNormalFill] for generic generation functions.
 
 use super::sys::{self};
+use crate::ErrorKind;
 use std::mem::MaybeUninit;
 
 /// Wrapper around [sys::curandStatus_t].
@@ -32,6 +33,26 @@ impl std::fmt::Display for CurandError {
 #[cfg(feature = "std")]
 impl std::error::Error for CurandError {}
 
+impl CurandError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::curandStatus_t::CURAND_STATUS_ALLOCATION_FAILED => ErrorKind::OutOfMemory,
+            sys::curandStatus_t::CURAND_STATUS_OUT_OF_RANGE
+            | sys::curandStatus_t::CURAND_STATUS_LENGTH_NOT_MULTIPLE => ErrorKind::InvalidValue,
+            sys::curandStatus_t::CURAND_STATUS_ARCH_MISMATCH
+            | sys::curandStatus_t::CURAND_STATUS_DOUBLE_PRECISION_REQUIRED => {
+                ErrorKind::NotSupported
+            }
+            sys::curandStatus_t::CURAND_STATUS_NOT_INITIALIZED
+            | sys::curandStatus_t::CURAND_STATUS_INITIALIZATION_FAILED => ErrorKind::NotInitialized,
+            sys::curandStatus_t::CURAND_STATUS_LAUNCH_FAILURE
+            | sys::curandStatus_t::CURAND_STATUS_PREEXISTING_FAILURE => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// Create new random number generator with the default pseudo rng type.
 ///
 /// See [cuRAND docs](https://docs.nvidia.com/cuda/curand/group__HOST.html#group__HOST_1g56ff2b3cf7e28849f73a1e22022bcbfd).
@@ -1,6 +1,7 @@
 use std::mem::MaybeUninit;
 
 use super::sys;
+use crate::ErrorKind;
 
 /// Wrapper around [sys::cusolverStatus_t]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -25,6 +26,22 @@ impl std::fmt::Display for CusolverError {
 #[cfg(feature = "std")]
 impl std::error::Error for CusolverError {}
 
+impl CusolverError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cusolverStatus_t::CUSOLVER_STATUS_ALLOC_FAILED => ErrorKind::OutOfMemory,
+            sys::cusolverStatus_t::CUSOLVER_STATUS_INVALID_VALUE
+            | sys::cusolverStatus_t::CUSOLVER_STATUS_INVALID_WORKSPACE => ErrorKind::InvalidValue,
+            sys::cusolverStatus_t::CUSOLVER_STATUS_NOT_SUPPORTED
+            | sys::cusolverStatus_t::CUSOLVER_STATUS_ARCH_MISMATCH => ErrorKind::NotSupported,
+            sys::cusolverStatus_t::CUSOLVER_STATUS_NOT_INITIALIZED => ErrorKind::NotInitialized,
+            sys::cusolverStatus_t::CUSOLVER_STATUS_EXECUTION_FAILED => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// See [cuda docs](https://docs.nvidia.com/cuda/cusolver/index.html#cusolverdncreate)
 pub fn dn_create() -> Result<sys::cusolverDnHandle_t, CusolverError> {
     let mut handle = MaybeUninit::uninit();
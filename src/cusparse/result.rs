@@ -1,6 +1,7 @@
 use std::mem::MaybeUninit;
 
 use super::sys;
+use crate::ErrorKind;
 
 /// Wrapper around [sys::CUresult]. See
 /// nvidia's [CUresult docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__TYPES.html#group__CUDA__TYPES_1gc6c391505e117393cc2558fff6bfc2e9)
@@ -17,6 +18,34 @@ impl sys::cusparseStatus_t {
     }
 }
 
+#[cfg(feature = "std")]
+impl std::fmt::Display for CusparseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CusparseError {}
+
+impl CusparseError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cusparseStatus_t::CUSPARSE_STATUS_ALLOC_FAILED
+            | sys::cusparseStatus_t::CUSPARSE_STATUS_INSUFFICIENT_RESOURCES => {
+                ErrorKind::OutOfMemory
+            }
+            sys::cusparseStatus_t::CUSPARSE_STATUS_INVALID_VALUE => ErrorKind::InvalidValue,
+            sys::cusparseStatus_t::CUSPARSE_STATUS_NOT_SUPPORTED
+            | sys::cusparseStatus_t::CUSPARSE_STATUS_ARCH_MISMATCH => ErrorKind::NotSupported,
+            sys::cusparseStatus_t::CUSPARSE_STATUS_NOT_INITIALIZED => ErrorKind::NotInitialized,
+            sys::cusparseStatus_t::CUSPARSE_STATUS_EXECUTION_FAILED => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// See [cuda docs](https://docs.nvidia.com/cuda/cusparse/#cusparsecreate)
 pub fn create() -> Result<sys::cusparseHandle_t, CusparseError> {
     let mut handle = MaybeUninit::uninit();
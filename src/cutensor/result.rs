@@ -1,4 +1,5 @@
 use super::sys;
+use crate::ErrorKind;
 use core::mem::MaybeUninit;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -23,6 +24,26 @@ impl std::fmt::Display for CutensorError {
 #[cfg(feature = "std")]
 impl std::error::Error for CutensorError {}
 
+impl CutensorError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cutensorStatus_t::CUTENSOR_STATUS_ALLOC_FAILED
+            | sys::cutensorStatus_t::CUTENSOR_STATUS_INSUFFICIENT_WORKSPACE => {
+                ErrorKind::OutOfMemory
+            }
+            sys::cutensorStatus_t::CUTENSOR_STATUS_INVALID_VALUE => ErrorKind::InvalidValue,
+            sys::cutensorStatus_t::CUTENSOR_STATUS_NOT_SUPPORTED
+            | sys::cutensorStatus_t::CUTENSOR_STATUS_ARCH_MISMATCH
+            | sys::cutensorStatus_t::CUTENSOR_STATUS_INSUFFICIENT_DRIVER => ErrorKind::NotSupported,
+            sys::cutensorStatus_t::CUTENSOR_STATUS_NOT_INITIALIZED => ErrorKind::NotInitialized,
+            sys::cutensorStatus_t::CUTENSOR_STATUS_EXECUTION_FAILED => ErrorKind::LaunchFailure,
+            sys::cutensorStatus_t::CUTENSOR_STATUS_IO_ERROR => ErrorKind::Io,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// Creates a cuTENSOR handle. See
 /// [nvidia docs](https://docs.nvidia.com/cuda/cutensor/latest/index.html)
 pub fn create_handle() -> Result<sys::cutensorHandle_t, CutensorError> {
@@ -12,6 +12,7 @@
 //! turns into [stream::create()], where [stream] is a module.
 
 use super::sys::{self};
+use crate::ErrorKind;
 use core::ffi::{c_uchar, c_uint, c_void, CStr};
 use std::mem::MaybeUninit;
 
@@ -54,6 +55,29 @@ impl DriverError {
     }
 }
 
+impl DriverError {
+    /// Whether this is a "sticky" error, which leaves the context it happened in unusable.
+    /// Every later call in that context fails with the same error, until the context is
+    /// destroyed, or reset with [crate::driver::CudaContext::reset()] for primary contexts.
+    ///
+    /// These are errors raised while a kernel was running, like illegal memory accesses.
+    pub fn is_sticky(&self) -> bool {
+        matches!(
+            self.0,
+            sys::CUresult::CUDA_ERROR_ILLEGAL_ADDRESS
+                | sys::CUresult::CUDA_ERROR_ILLEGAL_INSTRUCTION
+                | sys::CUresult::CUDA_ERROR_MISALIGNED_ADDRESS
+                | sys::CUresult::CUDA_ERROR_INVALID_ADDRESS_SPACE
+                | sys::CUresult::CUDA_ERROR_INVALID_PC
+                | sys::CUresult::CUDA_ERROR_HARDWARE_STACK_ERROR
+                | sys::CUresult::CUDA_ERROR_ASSERT
+                | sys::CUresult::CUDA_ERROR_LAUNCH_FAILED
+                | sys::CUresult::CUDA_ERROR_LAUNCH_TIMEOUT
+                | sys::CUresult::CUDA_ERROR_ECC_UNCORRECTABLE
+        )
+    }
+}
+
 impl std::fmt::Debug for DriverError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.error_string() {
@@ -81,6 +105,43 @@ impl std::fmt::Display for DriverError {
 #[cfg(feature = "std")]
 impl std::error::Error for DriverError {}
 
+impl DriverError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::CUresult::CUDA_ERROR_OUT_OF_MEMORY => ErrorKind::OutOfMemory,
+            sys::CUresult::CUDA_ERROR_INVALID_VALUE | sys::CUresult::CUDA_ERROR_INVALID_DEVICE => {
+                ErrorKind::InvalidValue
+            }
+            sys::CUresult::CUDA_ERROR_NOT_SUPPORTED => ErrorKind::NotSupported,
+            sys::CUresult::CUDA_ERROR_NOT_INITIALIZED
+            | sys::CUresult::CUDA_ERROR_DEINITIALIZED
+            | sys::CUresult::CUDA_ERROR_NO_DEVICE => ErrorKind::NotInitialized,
+            sys::CUresult::CUDA_ERROR_INVALID_HANDLE
+            | sys::CUresult::CUDA_ERROR_INVALID_CONTEXT
+            | sys::CUresult::CUDA_ERROR_CONTEXT_IS_DESTROYED => ErrorKind::InvalidHandle,
+            sys::CUresult::CUDA_ERROR_NOT_FOUND | sys::CUresult::CUDA_ERROR_FILE_NOT_FOUND => {
+                ErrorKind::NotFound
+            }
+            sys::CUresult::CUDA_ERROR_LAUNCH_FAILED
+            | sys::CUresult::CUDA_ERROR_LAUNCH_OUT_OF_RESOURCES
+            | sys::CUresult::CUDA_ERROR_LAUNCH_TIMEOUT
+            | sys::CUresult::CUDA_ERROR_ILLEGAL_ADDRESS
+            | sys::CUresult::CUDA_ERROR_ILLEGAL_INSTRUCTION
+            | sys::CUresult::CUDA_ERROR_MISALIGNED_ADDRESS
+            | sys::CUresult::CUDA_ERROR_INVALID_PC
+            | sys::CUresult::CUDA_ERROR_HARDWARE_STACK_ERROR
+            | sys::CUresult::CUDA_ERROR_ASSERT => ErrorKind::LaunchFailure,
+            sys::CUresult::CUDA_ERROR_INVALID_PTX
+            | sys::CUresult::CUDA_ERROR_INVALID_IMAGE
+            | sys::CUresult::CUDA_ERROR_NO_BINARY_FOR_GPU
+            | sys::CUresult::CUDA_ERROR_JIT_COMPILER_NOT_FOUND
+            | sys::CUresult::CUDA_ERROR_UNSUPPORTED_PTX_VERSION => ErrorKind::Compilation,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// Initializes the CUDA driver API.
 /// **MUST BE CALLED BEFORE ANYTHING ELSE**
 ///
@@ -89,6 +150,16 @@ pub fn init() -> Result<(), DriverError> {
     unsafe { sys::cuInit(0).result() }
 }
 
+/// The latest CUDA version the installed driver supports, e.g. `12080` for 12.8. This can be
+/// older than the version cudarc was built for.
+///
+/// See [cuDriverGetVersion() docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VERSION.html)
+pub fn version() -> Result<i32, DriverError> {
+    let mut version = 0;
+    unsafe { sys::cuDriverGetVersion(&mut version) }.result()?;
+    Ok(version)
+}
+
 pub mod device {
     //! Device management functions (`cuDevice*`).
     //!
@@ -219,6 +290,47 @@ pub mod device {
     ) -> Result<(), DriverError> {
         sys::cuDeviceSetMemPool(dev, pool).result()
     }
+
+    /// Query graph memory usage of a device.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    ///
+    /// # Safety
+    /// Must be a device returned from [get].
+    pub unsafe fn get_graph_mem_attribute(
+        dev: sys::CUdevice,
+        attr: sys::CUgraphMem_attribute,
+    ) -> Result<u64, DriverError> {
+        let mut value: u64 = 0;
+        sys::cuDeviceGetGraphMemAttribute(dev, attr, (&mut value) as *mut u64 as *mut _)
+            .result()?;
+        Ok(value)
+    }
+
+    /// Set a graph memory attribute of a device. Only the high watermark attributes can be set,
+    /// and only to 0 (which resets them).
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    ///
+    /// # Safety
+    /// Must be a device returned from [get].
+    pub unsafe fn set_graph_mem_attribute(
+        dev: sys::CUdevice,
+        attr: sys::CUgraphMem_attribute,
+        mut value: u64,
+    ) -> Result<(), DriverError> {
+        sys::cuDeviceSetGraphMemAttribute(dev, attr, (&mut value) as *mut u64 as *mut _).result()
+    }
+
+    /// Free unused memory that was cached on the device for use with graphs back to the OS.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    ///
+    /// # Safety
+    /// Must be a device returned from [get].
+    pub unsafe fn graph_mem_trim(dev: sys::CUdevice) -> Result<(), DriverError> {
+        sys::cuDeviceGraphMemTrim(dev).result()
+    }
 }
 
 pub mod function {
@@ -276,6 +388,79 @@ pub mod function {
 
         Ok(())
     }
+
+    /// Returns whether the function is loaded, which with lazy loading
+    /// (`CUDA_MODULE_LOADING=LAZY`) only happens on first use.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__EXEC.html)
+    ///
+    /// # Safety
+    /// Function must exist.
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub unsafe fn is_loaded(
+        f: sys::CUfunction,
+    ) -> Result<sys::CUfunctionLoadingState, super::DriverError> {
+        let mut state = MaybeUninit::uninit();
+        unsafe {
+            sys::cuFuncIsLoaded(state.as_mut_ptr(), f).result()?;
+            Ok(state.assume_init())
+        }
+    }
+
+    /// Loads the function if it has not been loaded yet.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__EXEC.html)
+    ///
+    /// # Safety
+    /// Function must exist.
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub unsafe fn load(f: sys::CUfunction) -> Result<(), super::DriverError> {
+        unsafe { sys::cuFuncLoad(f).result() }
+    }
+
+    /// Returns the (mangled) name of a CUDA function.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__EXEC.html)
+    ///
+    /// # Safety
+    /// Function must exist.
+    #[cfg(any(
+        feature = "cuda-12030",
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub unsafe fn get_name(f: sys::CUfunction) -> Result<std::string::String, super::DriverError> {
+        let mut name = MaybeUninit::uninit();
+        unsafe {
+            sys::cuFuncGetName(name.as_mut_ptr(), f).result()?;
+            let name = core::ffi::CStr::from_ptr(name.assume_init());
+            Ok(std::string::String::from_utf8_lossy(name.to_bytes()).into())
+        }
+    }
 }
 
 pub mod occupancy {
@@ -462,6 +647,20 @@ pub mod primary_ctx {
     pub unsafe fn release(dev: sys::CUdevice) -> Result<(), DriverError> {
         sys::cuDevicePrimaryCtxRelease_v2(dev).result()
     }
+
+    /// Destroys all allocations and resets all state of the primary context of `dev`,
+    /// without releasing it.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__PRIMARY__CTX.html#group__CUDA__PRIMARY__CTX)
+    ///
+    /// # Safety
+    ///
+    /// This is only safe with a device that was returned from [super::device::get]. Nothing
+    /// created in the primary context (memory, streams, events, modules, ...) may be used
+    /// afterwards.
+    pub unsafe fn reset(dev: sys::CUdevice) -> Result<(), DriverError> {
+        sys::cuDevicePrimaryCtxReset_v2(dev).result()
+    }
 }
 
 pub mod ctx {
@@ -805,6 +1004,175 @@ pub mod stream {
         sys::cuStreamIsCapturing(stream, status.as_mut_ptr()).result()?;
         Ok(status.assume_init())
     }
+
+    /// Waits on `stream` until the value at `addr` satisfies the condition in `flags` against `value`.
+    /// `flags` is a combination of [sys::CUstreamWaitValue_flags] values.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEMOP.html)
+    /// # Safety
+    /// Stream must be valid, `addr` must be a valid 4 byte aligned device address.
+    pub unsafe fn wait_value_32(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: u32,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        #[cfg(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060"))]
+        sys::cuStreamWaitValue32(stream, addr, value, flags).result()?;
+        #[cfg(not(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060")))]
+        sys::cuStreamWaitValue32_v2(stream, addr, value, flags).result()?;
+        Ok(())
+    }
+
+    /// Waits on `stream` until the value at `addr` satisfies the condition in `flags` against `value`.
+    /// `flags` is a combination of [sys::CUstreamWaitValue_flags] values.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEMOP.html)
+    /// # Safety
+    /// Stream must be valid, `addr` must be a valid 8 byte aligned device address.
+    pub unsafe fn wait_value_64(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: u64,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        #[cfg(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060"))]
+        sys::cuStreamWaitValue64(stream, addr, value, flags).result()?;
+        #[cfg(not(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060")))]
+        sys::cuStreamWaitValue64_v2(stream, addr, value, flags).result()?;
+        Ok(())
+    }
+
+    /// Writes `value` to `addr` once the preceding work on `stream` is done.
+    /// `flags` is a combination of [sys::CUstreamWriteValue_flags] values.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEMOP.html)
+    /// # Safety
+    /// Stream must be valid, `addr` must be a valid 4 byte aligned device address.
+    pub unsafe fn write_value_32(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: u32,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        #[cfg(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060"))]
+        sys::cuStreamWriteValue32(stream, addr, value, flags).result()?;
+        #[cfg(not(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060")))]
+        sys::cuStreamWriteValue32_v2(stream, addr, value, flags).result()?;
+        Ok(())
+    }
+
+    /// Writes `value` to `addr` once the preceding work on `stream` is done.
+    /// `flags` is a combination of [sys::CUstreamWriteValue_flags] values.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEMOP.html)
+    /// # Safety
+    /// Stream must be valid, `addr` must be a valid 8 byte aligned device address.
+    pub unsafe fn write_value_64(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: u64,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        #[cfg(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060"))]
+        sys::cuStreamWriteValue64(stream, addr, value, flags).result()?;
+        #[cfg(not(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060")))]
+        sys::cuStreamWriteValue64_v2(stream, addr, value, flags).result()?;
+        Ok(())
+    }
+
+    /// Submits a batch of wait/write memory operations to `stream`.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEMOP.html)
+    /// # Safety
+    /// Stream must be valid, and all addresses in `ops` must be valid aligned device addresses.
+    pub unsafe fn batch_mem_op(
+        stream: sys::CUstream,
+        ops: &mut [sys::CUstreamBatchMemOpParams],
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        #[cfg(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060"))]
+        sys::cuStreamBatchMemOp(stream, ops.len() as u32, ops.as_mut_ptr(), flags).result()?;
+        #[cfg(not(any(feature = "cuda-11040", feature = "cuda-11050", feature = "cuda-11060")))]
+        sys::cuStreamBatchMemOp_v2(stream, ops.len() as u32, ops.as_mut_ptr(), flags).result()?;
+        Ok(())
+    }
+
+    /// Returns the capture status, capture id, graph being captured into, and the nodes the next
+    /// captured operation will depend on. The id, graph and dependencies are only written
+    /// if the stream is actively capturing.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__STREAM.html)
+    /// # Safety
+    /// Stream must be valid
+    pub unsafe fn get_capture_info(
+        stream: sys::CUstream,
+    ) -> Result<
+        (
+            sys::CUstreamCaptureStatus,
+            u64,
+            sys::CUgraph,
+            std::vec::Vec<sys::CUgraphNode>,
+        ),
+        DriverError,
+    > {
+        let mut status = MaybeUninit::uninit();
+        let mut id = 0;
+        let mut graph = std::ptr::null_mut();
+        let mut deps: *const sys::CUgraphNode = std::ptr::null();
+        let mut num_deps = 0;
+        #[cfg(not(any(
+            feature = "cuda-12030",
+            feature = "cuda-12040",
+            feature = "cuda-12050",
+            feature = "cuda-12060",
+            feature = "cuda-12080",
+            feature = "cuda-12090",
+            feature = "cuda-13000",
+            feature = "cuda-13010",
+            feature = "cuda-13020"
+        )))]
+        sys::cuStreamGetCaptureInfo_v2(
+            stream,
+            status.as_mut_ptr(),
+            &mut id,
+            &mut graph,
+            &mut deps,
+            &mut num_deps,
+        )
+        .result()?;
+        #[cfg(any(
+            feature = "cuda-12030",
+            feature = "cuda-12040",
+            feature = "cuda-12050",
+            feature = "cuda-12060",
+            feature = "cuda-12080",
+            feature = "cuda-12090",
+            feature = "cuda-13000",
+            feature = "cuda-13010",
+            feature = "cuda-13020"
+        ))]
+        {
+            // Edge data has to be requested, otherwise edges with data fail with CUDA_ERROR_LOSSY_QUERY.
+            let mut edge_data: *const sys::CUgraphEdgeData = std::ptr::null();
+            sys::cuStreamGetCaptureInfo_v3(
+                stream,
+                status.as_mut_ptr(),
+                &mut id,
+                &mut graph,
+                &mut deps,
+                &mut edge_data,
+                &mut num_deps,
+            )
+            .result()?;
+        }
+        let deps = if deps.is_null() || num_deps == 0 {
+            std::vec::Vec::new()
+        } else {
+            std::slice::from_raw_parts(deps, num_deps).to_vec()
+        };
+        Ok((status.assume_init(), id, graph, deps))
+    }
 }
 
 /// Allocates memory with stream ordered semantics.
@@ -868,6 +1236,44 @@ pub unsafe fn free_host(host_ptr: *mut c_void) -> Result<(), DriverError> {
     sys::cuMemFreeHost(host_ptr).result()
 }
 
+/// Page-locks an existing host memory range.
+///
+/// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1gf0a9fe11544326dabd743b7aa6b54223)
+///
+/// # Safety
+/// 1. `host_ptr` must be valid for `num_bytes` bytes, and stay valid until [host_unregister()] is called.
+/// 2. The range must not already be registered.
+pub unsafe fn host_register(
+    host_ptr: *mut c_void,
+    num_bytes: usize,
+    flags: c_uint,
+) -> Result<(), DriverError> {
+    sys::cuMemHostRegister_v2(host_ptr, num_bytes, flags).result()
+}
+
+/// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1g63f450c8125359be87b7623b1c0b2a14)
+///
+/// # Safety
+/// 1. `host_ptr` must be the base address passed to [host_register()].
+pub unsafe fn host_unregister(host_ptr: *mut c_void) -> Result<(), DriverError> {
+    sys::cuMemHostUnregister(host_ptr).result()
+}
+
+/// Gets the device pointer that maps to page-locked host memory allocated with
+/// [sys::CU_MEMHOSTALLOC_DEVICEMAP] or registered with [sys::CU_MEMHOSTREGISTER_DEVICEMAP].
+///
+/// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1g57a39e5cba26af4d06be67fc77cc62f0)
+///
+/// # Safety
+/// 1. `host_ptr` must point into memory returned by [malloc_host()] or registered with [host_register()].
+pub unsafe fn host_get_device_pointer(
+    host_ptr: *mut c_void,
+) -> Result<sys::CUdeviceptr, DriverError> {
+    let mut dev_ptr = MaybeUninit::uninit();
+    sys::cuMemHostGetDevicePointer_v2(dev_ptr.as_mut_ptr(), host_ptr, 0).result()?;
+    Ok(dev_ptr.assume_init())
+}
+
 /// Advise about the usage of a given memory range.
 ///
 /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__UNIFIED.html#group__CUDA__UNIFIED_1g27608c857a9254789c13f3e3b72029e2)
@@ -1076,6 +1482,84 @@ pub unsafe fn memcpy_dtod_async(
     sys::cuMemcpyDtoDAsync_v2(dst, src, num_bytes, stream).result()
 }
 
+/// Copies memory between any two addresses with stream ordered semantics. The direction
+/// of the copy is inferred from the addresses with unified addressing, so either side
+/// can be host memory.
+///
+/// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html)
+///
+/// # Safety
+/// 1. Both addresses must be valid for `num_bytes` bytes.
+/// 2. Host memory must stay valid until the copy is complete.
+pub unsafe fn memcpy_async(
+    dst: sys::CUdeviceptr,
+    src: sys::CUdeviceptr,
+    num_bytes: usize,
+    stream: sys::CUstream,
+) -> Result<(), DriverError> {
+    sys::cuMemcpyAsync(dst, src, num_bytes, stream).result()
+}
+
+/// Performs a batch of copies with stream ordered semantics, in a single driver call.
+/// Like [memcpy_async], the direction of each copy is inferred from the addresses.
+///
+/// `attrs[i]` applies to all copies from `attr_idxs[i]` up to `attr_idxs[i + 1]`,
+/// so `attr_idxs[0]` must be 0.
+///
+/// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html)
+///
+/// # Safety
+/// 1. All addresses must be valid for their corresponding size.
+/// 2. Host memory must stay valid until the copies are complete.
+/// 3. The stream must not be the legacy NULL stream.
+#[cfg(any(
+    feature = "cuda-12080",
+    feature = "cuda-12090",
+    feature = "cuda-13000",
+    feature = "cuda-13010",
+    feature = "cuda-13020"
+))]
+pub unsafe fn memcpy_batch_async(
+    dsts: &mut [sys::CUdeviceptr],
+    srcs: &mut [sys::CUdeviceptr],
+    sizes: &mut [usize],
+    attrs: &mut [sys::CUmemcpyAttributes],
+    attr_idxs: &mut [usize],
+    stream: sys::CUstream,
+) -> Result<(), DriverError> {
+    assert_eq!(dsts.len(), srcs.len());
+    assert_eq!(dsts.len(), sizes.len());
+    assert_eq!(attrs.len(), attr_idxs.len());
+    #[cfg(any(feature = "cuda-12080", feature = "cuda-12090"))]
+    {
+        let mut fail_idx = 0;
+        sys::cuMemcpyBatchAsync(
+            dsts.as_mut_ptr(),
+            srcs.as_mut_ptr(),
+            sizes.as_mut_ptr(),
+            dsts.len(),
+            attrs.as_mut_ptr(),
+            attr_idxs.as_mut_ptr(),
+            attrs.len(),
+            &mut fail_idx,
+            stream,
+        )
+        .result()
+    }
+    #[cfg(not(any(feature = "cuda-12080", feature = "cuda-12090")))]
+    sys::cuMemcpyBatchAsync_v2(
+        dsts.as_mut_ptr(),
+        srcs.as_mut_ptr(),
+        sizes.as_mut_ptr(),
+        dsts.len(),
+        attrs.as_mut_ptr(),
+        attr_idxs.as_mut_ptr(),
+        attrs.len(),
+        stream,
+    )
+    .result()
+}
+
 /// Copies memory from Device to Device
 ///
 /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1g1725774abf8b51b91945f3336b778c8b)
@@ -1194,6 +1678,32 @@ pub mod module {
         Ok((dptr.assume_init(), bytes.assume_init()))
     }
 
+    /// Returns all functions of the module.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MODULE.html)
+    ///
+    /// # Safety
+    /// `module` must be a properly allocated and not freed module.
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub unsafe fn enumerate_functions(
+        module: sys::CUmodule,
+    ) -> Result<std::vec::Vec<sys::CUfunction>, DriverError> {
+        let mut count = 0;
+        sys::cuModuleGetFunctionCount(&mut count, module).result()?;
+        let mut functions = std::vec![std::ptr::null_mut(); count as usize];
+        sys::cuModuleEnumerateFunctions(functions.as_mut_ptr(), count, module).result()?;
+        Ok(functions)
+    }
+
     /// Unloads a module.
     ///
     /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MODULE.html#group__CUDA__MODULE_1g8ea3d716524369de3763104ced4ea57b)
@@ -1522,6 +2032,189 @@ pub mod graph {
     ) -> Result<(), DriverError> {
         sys::cuGraphUpload(graph_exec, stream).result()
     }
+
+    /// Returns all nodes of `graph`.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    /// # Safety
+    /// graph must be valid
+    pub unsafe fn get_nodes(
+        graph: sys::CUgraph,
+    ) -> Result<std::vec::Vec<sys::CUgraphNode>, DriverError> {
+        let mut num_nodes = 0;
+        sys::cuGraphGetNodes(graph, std::ptr::null_mut(), &mut num_nodes).result()?;
+        let mut nodes = std::vec![std::ptr::null_mut(); num_nodes];
+        sys::cuGraphGetNodes(graph, nodes.as_mut_ptr(), &mut num_nodes).result()?;
+        nodes.truncate(num_nodes);
+        Ok(nodes)
+    }
+
+    /// Returns all dependency edges of `graph` as `(from, to)` pairs.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    /// # Safety
+    /// graph must be valid
+    pub unsafe fn get_edges(
+        graph: sys::CUgraph,
+    ) -> Result<std::vec::Vec<(sys::CUgraphNode, sys::CUgraphNode)>, DriverError> {
+        let mut num_edges = 0;
+        #[cfg(not(any(
+            feature = "cuda-12030",
+            feature = "cuda-12040",
+            feature = "cuda-12050",
+            feature = "cuda-12060",
+            feature = "cuda-12080",
+            feature = "cuda-12090",
+            feature = "cuda-13000",
+            feature = "cuda-13010",
+            feature = "cuda-13020"
+        )))]
+        let (from, to) = {
+            sys::cuGraphGetEdges(
+                graph,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut num_edges,
+            )
+            .result()?;
+            let mut from = std::vec![std::ptr::null_mut(); num_edges];
+            let mut to = std::vec![std::ptr::null_mut(); num_edges];
+            sys::cuGraphGetEdges(graph, from.as_mut_ptr(), to.as_mut_ptr(), &mut num_edges)
+                .result()?;
+            (from, to)
+        };
+        #[cfg(any(
+            feature = "cuda-12030",
+            feature = "cuda-12040",
+            feature = "cuda-12050",
+            feature = "cuda-12060",
+            feature = "cuda-12080",
+            feature = "cuda-12090",
+            feature = "cuda-13000",
+            feature = "cuda-13010",
+            feature = "cuda-13020"
+        ))]
+        let (from, to) = {
+            sys::cuGraphGetEdges_v2(
+                graph,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut num_edges,
+            )
+            .result()?;
+            let mut from = std::vec![std::ptr::null_mut(); num_edges];
+            let mut to = std::vec![std::ptr::null_mut(); num_edges];
+            // Edge data has to be requested, otherwise edges with data fail with CUDA_ERROR_LOSSY_QUERY.
+            let mut edge_data = std::vec![std::mem::zeroed::<sys::CUgraphEdgeData>(); num_edges];
+            sys::cuGraphGetEdges_v2(
+                graph,
+                from.as_mut_ptr(),
+                to.as_mut_ptr(),
+                edge_data.as_mut_ptr(),
+                &mut num_edges,
+            )
+            .result()?;
+            (from, to)
+        };
+        Ok(from.into_iter().zip(to).take(num_edges).collect())
+    }
+
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    /// # Safety
+    /// node must be valid
+    pub unsafe fn node_get_type(
+        node: sys::CUgraphNode,
+    ) -> Result<sys::CUgraphNodeType, DriverError> {
+        let mut node_type = MaybeUninit::uninit();
+        sys::cuGraphNodeGetType(node, node_type.as_mut_ptr()).result()?;
+        Ok(node_type.assume_init())
+    }
+
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    /// # Safety
+    /// node must be a valid kernel node
+    pub unsafe fn kernel_node_get_params(
+        node: sys::CUgraphNode,
+    ) -> Result<sys::CUDA_KERNEL_NODE_PARAMS, DriverError> {
+        let mut params = MaybeUninit::zeroed();
+        #[cfg(any(
+            feature = "cuda-11040",
+            feature = "cuda-11050",
+            feature = "cuda-11060",
+            feature = "cuda-11070",
+            feature = "cuda-11080"
+        ))]
+        sys::cuGraphKernelNodeGetParams(node, params.as_mut_ptr()).result()?;
+        #[cfg(not(any(
+            feature = "cuda-11040",
+            feature = "cuda-11050",
+            feature = "cuda-11060",
+            feature = "cuda-11070",
+            feature = "cuda-11080"
+        )))]
+        sys::cuGraphKernelNodeGetParams_v2(node, params.as_mut_ptr()).result()?;
+        Ok(params.assume_init())
+    }
+
+    /// Writes a DOT description of `graph` to `path`. `flags` is a combination of
+    /// [sys::CUgraphDebugDot_flags] values.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    /// # Safety
+    /// graph must be valid
+    pub unsafe fn debug_dot_print(
+        graph: sys::CUgraph,
+        path: &CStr,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        sys::cuGraphDebugDotPrint(graph, path.as_ptr(), flags).result()
+    }
+
+    /// Adds a memory allocation node to `graph`. The allocated address is written to `params.dptr`.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    /// # Safety
+    /// graph & dependencies must be valid, `params` must describe a valid allocation.
+    pub unsafe fn add_mem_alloc_node(
+        graph: sys::CUgraph,
+        dependencies: &[sys::CUgraphNode],
+        params: &mut sys::CUDA_MEM_ALLOC_NODE_PARAMS,
+    ) -> Result<sys::CUgraphNode, DriverError> {
+        let mut node = MaybeUninit::uninit();
+        sys::cuGraphAddMemAllocNode(
+            node.as_mut_ptr(),
+            graph,
+            dependencies.as_ptr(),
+            dependencies.len(),
+            params,
+        )
+        .result()?;
+        Ok(node.assume_init())
+    }
+
+    /// Adds a memory free node to `graph`, freeing `dptr` which must have been allocated by
+    /// a memory allocation node.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    /// # Safety
+    /// graph & dependencies must be valid, `dptr` must be a graph allocation that is not freed elsewhere.
+    pub unsafe fn add_mem_free_node(
+        graph: sys::CUgraph,
+        dependencies: &[sys::CUgraphNode],
+        dptr: sys::CUdeviceptr,
+    ) -> Result<sys::CUgraphNode, DriverError> {
+        let mut node = MaybeUninit::uninit();
+        sys::cuGraphAddMemFreeNode(
+            node.as_mut_ptr(),
+            graph,
+            dependencies.as_ptr(),
+            dependencies.len(),
+            dptr,
+        )
+        .result()?;
+        Ok(node.assume_init())
+    }
 }
 
 pub mod mem_pool {
@@ -1616,11 +2309,100 @@ pub mod mem_pool {
     }
 }
 
+#[cfg(any(
+    feature = "cuda-12010",
+    feature = "cuda-12020",
+    feature = "cuda-12030",
+    feature = "cuda-12040",
+    feature = "cuda-12050",
+    feature = "cuda-12060",
+    feature = "cuda-12080",
+    feature = "cuda-12090",
+    feature = "cuda-13000",
+    feature = "cuda-13010",
+    feature = "cuda-13020"
+))]
+pub mod coredump {
+    //! Coredump attribute functions (`cuCoredump*`).
+    //!
+    //! See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__COREDUMP.html)
+
+    use super::{sys, DriverError};
+    use core::ffi::c_void;
+
+    /// Sets a coredump attribute of the current context.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__COREDUMP.html)
+    ///
+    /// # Safety
+    /// `value` must point to `size` bytes of the type the attribute expects.
+    pub unsafe fn set_attribute(
+        attrib: sys::CUcoredumpSettings,
+        value: *mut c_void,
+        mut size: usize,
+    ) -> Result<(), DriverError> {
+        sys::cuCoredumpSetAttribute(attrib, value, &mut size).result()
+    }
+
+    /// Sets a coredump attribute for all contexts created afterwards.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__COREDUMP.html)
+    ///
+    /// # Safety
+    /// `value` must point to `size` bytes of the type the attribute expects.
+    pub unsafe fn set_attribute_global(
+        attrib: sys::CUcoredumpSettings,
+        value: *mut c_void,
+        mut size: usize,
+    ) -> Result<(), DriverError> {
+        sys::cuCoredumpSetAttributeGlobal(attrib, value, &mut size).result()
+    }
+
+    /// Gets a coredump attribute of the current context, returning the number of bytes
+    /// written to `value`.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__COREDUMP.html)
+    ///
+    /// # Safety
+    /// `value` must point to `size` writable bytes.
+    pub unsafe fn get_attribute(
+        attrib: sys::CUcoredumpSettings,
+        value: *mut c_void,
+        mut size: usize,
+    ) -> Result<usize, DriverError> {
+        sys::cuCoredumpGetAttribute(attrib, value, &mut size).result()?;
+        Ok(size)
+    }
+
+    /// Gets a global coredump attribute, returning the number of bytes written to `value`.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__COREDUMP.html)
+    ///
+    /// # Safety
+    /// `value` must point to `size` writable bytes.
+    pub unsafe fn get_attribute_global(
+        attrib: sys::CUcoredumpSettings,
+        value: *mut c_void,
+        mut size: usize,
+    ) -> Result<usize, DriverError> {
+        sys::cuCoredumpGetAttributeGlobal(attrib, value, &mut size).result()?;
+        Ok(size)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::safe::{CudaContext, CudaSlice};
     use super::*;
 
+    #[test]
+    fn test_sticky_errors() {
+        assert!(DriverError(sys::CUresult::CUDA_ERROR_ILLEGAL_ADDRESS).is_sticky());
+        assert!(DriverError(sys::CUresult::CUDA_ERROR_LAUNCH_FAILED).is_sticky());
+        assert!(!DriverError(sys::CUresult::CUDA_ERROR_OUT_OF_MEMORY).is_sticky());
+        assert!(!DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE).is_sticky());
+    }
+
     #[test]
     #[ignore = "must be executed with multiple gpus"]
     fn peer_transfer_contexts() -> Result<(), DriverError> {
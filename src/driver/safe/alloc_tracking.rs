@@ -0,0 +1,305 @@
+//! Opt-in tracking of live [CudaSlice](super::CudaSlice), [PinnedHostSlice](super::PinnedHostSlice)
+//! and [UnifiedSlice](super::UnifiedSlice) allocations.
+//!
+//! Enabled with the `alloc-tracking` feature. Every allocation made through a [CudaContext]
+//! is registered with that context, and removed again when it is dropped (or leaked). Use
+//! [CudaContext::live_allocations()] to see what is currently holding memory.
+//!
+//! Live slices keep their [CudaContext] alive, so dropping your handle to a context doesn't
+//! drop it while allocations are outstanding, and nothing is ever printed. Instead call
+//! [report_live_allocations()], e.g. when an allocation runs out of memory or at the end of
+//! `main`, to get the allocations of every context that is still alive, including contexts
+//! only kept alive by their allocations.
+//!
+//! Backtraces are captured with [Backtrace::capture()], so they are only collected when
+//! `RUST_LIB_BACKTRACE` or `RUST_BACKTRACE` is set. Otherwise [AllocationInfo::backtrace] is
+//! disabled and tracking an allocation costs little more than a map insert.
+
+use crate::driver::sys;
+
+use super::core::CudaContext;
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    collections::BTreeMap,
+    fmt,
+    string::String,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::{Arc, Mutex, Weak},
+    vec::Vec,
+};
+
+/// Every [CudaContext] created with the `alloc-tracking` feature, for
+/// [report_live_allocations()].
+static CONTEXTS: Mutex<Vec<Weak<CudaContext>>> = Mutex::new(Vec::new());
+
+pub(crate) fn register_context(ctx: &Arc<CudaContext>) {
+    let mut contexts = CONTEXTS.lock().unwrap_or_else(|e| e.into_inner());
+    contexts.retain(|ctx| ctx.strong_count() > 0);
+    contexts.push(Arc::downgrade(ctx));
+}
+
+/// A human readable report of the live allocations of every [CudaContext] that is still
+/// alive, or `None` if there are none. See [CudaContext::allocation_report()].
+///
+/// Only available with the `alloc-tracking` feature.
+pub fn report_live_allocations() -> Option<String> {
+    let contexts: Vec<Arc<CudaContext>> = CONTEXTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    let reports: Vec<String> = contexts
+        .iter()
+        .filter_map(|ctx| ctx.allocation_report())
+        .collect();
+    if reports.is_empty() {
+        None
+    } else {
+        Some(reports.concat())
+    }
+}
+
+/// The kind of memory an [AllocationInfo] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AllocationKind {
+    /// Device memory owned by a [CudaSlice](super::CudaSlice).
+    Device,
+    /// Page locked host memory owned by a [PinnedHostSlice](super::PinnedHostSlice).
+    PinnedHost,
+    /// Managed memory owned by a [UnifiedSlice](super::UnifiedSlice).
+    Unified,
+}
+
+/// A single live allocation, as returned by [CudaContext::live_allocations()].
+#[derive(Debug, Clone)]
+pub struct AllocationInfo {
+    /// Unique (per context) id of the allocation. Ids increase with allocation order.
+    pub id: u64,
+    pub kind: AllocationKind,
+    /// The device pointer, or the host address for [AllocationKind::PinnedHost].
+    pub ptr: u64,
+    /// The number of elements of `type_name`.
+    pub len: usize,
+    pub num_bytes: usize,
+    /// The result of [std::any::type_name()] for the element type.
+    pub type_name: &'static str,
+    /// The stream the allocation was made on, if any.
+    pub stream: Option<sys::CUstream>,
+    /// Where the allocation was made. Only captured when `RUST_LIB_BACKTRACE` or
+    /// `RUST_BACKTRACE` is set, see [Backtrace::capture()].
+    pub backtrace: Arc<Backtrace>,
+}
+
+unsafe impl Send for AllocationInfo {}
+unsafe impl Sync for AllocationInfo {}
+
+impl fmt::Display for AllocationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {:?} {} bytes ({} x {}) at {:#x}",
+            self.id, self.kind, self.num_bytes, self.len, self.type_name, self.ptr
+        )?;
+        if let Some(stream) = self.stream {
+            write!(f, " on stream {stream:?}")?;
+        }
+        if self.backtrace.status() == BacktraceStatus::Captured {
+            write!(f, "\n{}", self.backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+const NUM_SHARDS: usize = 16;
+
+/// Registry of live allocations owned by a [CudaContext].
+///
+/// Entries are spread over several independently locked shards keyed by id, so
+/// concurrent allocations/frees on different threads rarely contend. Backtraces (if enabled)
+/// are captured before any lock is taken.
+#[derive(Debug)]
+pub(crate) struct AllocRegistry {
+    next_id: AtomicU64,
+    shards: [Mutex<BTreeMap<u64, AllocationInfo>>; NUM_SHARDS],
+}
+
+impl Default for AllocRegistry {
+    fn default() -> Self {
+        Self {
+            next_id: AtomicU64::new(0),
+            shards: std::array::from_fn(|_| Mutex::new(BTreeMap::new())),
+        }
+    }
+}
+
+impl AllocRegistry {
+    fn shard(&self, id: u64) -> std::sync::MutexGuard<'_, BTreeMap<u64, AllocationInfo>> {
+        self.shards[id as usize % NUM_SHARDS]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Records a new allocation of `len` elements of `T`, returning the id to later [AllocRegistry::remove()] it with.
+    pub(crate) fn insert<T>(
+        &self,
+        kind: AllocationKind,
+        ptr: u64,
+        len: usize,
+        stream: Option<sys::CUstream>,
+    ) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let info = AllocationInfo {
+            id,
+            kind,
+            ptr,
+            len,
+            num_bytes: len * std::mem::size_of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            stream,
+            backtrace: Arc::new(Backtrace::capture()),
+        };
+        self.shard(id).insert(id, info);
+        id
+    }
+
+    pub(crate) fn remove(&self, id: u64) {
+        self.shard(id).remove(&id);
+    }
+
+    /// All live allocations, ordered by id.
+    pub(crate) fn snapshot(&self) -> Vec<AllocationInfo> {
+        let mut entries = Vec::new();
+        for shard in self.shards.iter() {
+            let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            entries.extend(shard.values().cloned());
+        }
+        entries.sort_by_key(|info| info.id);
+        entries
+    }
+
+    /// A human readable report of all live allocations, or `None` if there are none.
+    pub(crate) fn report(&self) -> Option<String> {
+        let entries = self.snapshot();
+        if entries.is_empty() {
+            return None;
+        }
+        let total: usize = entries.iter().map(|info| info.num_bytes).sum();
+        let mut report = std::format!(
+            "{} live allocation(s) totalling {total} bytes:\n",
+            entries.len()
+        );
+        for info in entries.iter() {
+            report.push_str(&std::format!("{info}\n"));
+        }
+        Some(report)
+    }
+}
+
+impl CudaContext {
+    /// Snapshot of every [CudaSlice](super::CudaSlice), [PinnedHostSlice](super::PinnedHostSlice)
+    /// and [UnifiedSlice](super::UnifiedSlice) allocated in this context that has not yet been
+    /// dropped, ordered by allocation order.
+    ///
+    /// Only available with the `alloc-tracking` feature.
+    pub fn live_allocations(&self) -> Vec<AllocationInfo> {
+        self.allocations.snapshot()
+    }
+
+    /// A human readable report of [CudaContext::live_allocations()] with their backtraces, or
+    /// `None` if there are none.
+    ///
+    /// Only available with the `alloc-tracking` feature.
+    pub fn allocation_report(&self) -> Option<String> {
+        let report = self.allocations.report()?;
+        Some(std::format!("context on device {}: {report}", self.ordinal))
+    }
+
+    /// Total number of bytes held by [CudaContext::live_allocations()].
+    pub fn live_allocated_bytes(&self) -> usize {
+        self.allocations
+            .snapshot()
+            .iter()
+            .map(|info| info.num_bytes)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_insert_remove() {
+        let registry = AllocRegistry::default();
+        assert!(registry.snapshot().is_empty());
+        assert!(registry.report().is_none());
+
+        let a = registry.insert::<f32>(AllocationKind::Device, 0x1000, 10, None);
+        let b = registry.insert::<u8>(AllocationKind::PinnedHost, 0x2000, 3, None);
+        let c = registry.insert::<f64>(AllocationKind::Unified, 0x3000, 2, None);
+
+        let live = registry.snapshot();
+        assert_eq!(live.iter().map(|i| i.id).collect::<Vec<_>>(), [a, b, c]);
+        assert_eq!(live[0].num_bytes, 40);
+        assert_eq!(live[0].type_name, "f32");
+        assert_eq!(live[1].kind, AllocationKind::PinnedHost);
+        assert_eq!(live[2].num_bytes, 16);
+
+        registry.remove(b);
+        let live = registry.snapshot();
+        assert_eq!(live.iter().map(|i| i.id).collect::<Vec<_>>(), [a, c]);
+
+        let report = registry.report().unwrap();
+        assert!(report.starts_with("2 live allocation(s) totalling 56 bytes"));
+
+        registry.remove(a);
+        registry.remove(c);
+        assert!(registry.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_registry_threaded() {
+        let registry = Arc::new(AllocRegistry::default());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    let ids: Vec<u64> = (0..50)
+                        .map(|i| registry.insert::<u32>(AllocationKind::Device, i, 1, None))
+                        .collect();
+                    for id in ids.iter().step_by(2) {
+                        registry.remove(*id);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let live = registry.snapshot();
+        assert_eq!(live.len(), 100);
+        assert!(live.windows(2).all(|w| w[0].id < w[1].id));
+    }
+
+    #[test]
+    fn test_report_live_allocations() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+        let slice = stream.alloc_zeros::<f32>(100).unwrap();
+
+        let report = ctx.allocation_report().unwrap();
+        assert!(report.contains("400 bytes (100 x f32)"));
+        assert!(report_live_allocations().unwrap().contains(&report));
+
+        // The slice keeps the context alive, so it is still reported without a handle.
+        let weak = Arc::downgrade(&ctx);
+        drop(stream);
+        drop(ctx);
+        assert!(report_live_allocations().unwrap().contains(&report));
+
+        drop(slice);
+        assert!(weak.upgrade().is_none());
+    }
+}
@@ -4,15 +4,22 @@ use crate::driver::{
 };
 
 use std::{
+    collections::BTreeMap,
     ffi::CString,
     marker::PhantomData,
     ops::{Bound, RangeBounds},
     string::String,
-    sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
-    sync::Arc,
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    sync::{Arc, Mutex},
     vec::Vec,
 };
 
+/// Incremented by every [CudaContext::reset()].
+static PRIMARY_RESET_EPOCH: AtomicU64 = AtomicU64::new(0);
+/// The device and epoch of every [CudaContext::reset()], so other [CudaContext]s of the same
+/// primary context can find out they were invalidated.
+static PRIMARY_RESETS: Mutex<Vec<(sys::CUdevice, u64)>> = Mutex::new(Vec::new());
+
 /// Represents a CUDA context on a certain device.
 ///
 /// - [`CudaContext::new()`] retains the device's primary context.
@@ -40,6 +47,12 @@ pub struct CudaContext {
     pub(crate) num_streams: AtomicUsize,
     pub(crate) event_tracking: AtomicBool,
     pub(crate) error_state: AtomicU32,
+    /// Set by [CudaContext::reset()], after which nothing created in this context is valid.
+    pub(crate) invalidated: AtomicBool,
+    /// The last [PRIMARY_RESET_EPOCH] that [CudaContext::is_invalidated()] checked against.
+    pub(crate) reset_epoch: AtomicU64,
+    #[cfg(feature = "alloc-tracking")]
+    pub(crate) allocations: super::alloc_tracking::AllocRegistry,
 }
 
 unsafe impl Send for CudaContext {}
@@ -91,8 +104,14 @@ impl CudaContext {
             num_streams: AtomicUsize::new(0),
             event_tracking: AtomicBool::new(true),
             error_state: AtomicU32::new(0),
+            invalidated: AtomicBool::new(false),
+            reset_epoch: AtomicU64::new(PRIMARY_RESET_EPOCH.load(Ordering::Acquire)),
+            #[cfg(feature = "alloc-tracking")]
+            allocations: Default::default(),
         });
         ctx.bind_to_thread()?;
+        #[cfg(feature = "alloc-tracking")]
+        super::alloc_tracking::register_context(&ctx);
         Ok(ctx)
     }
 
@@ -163,8 +182,14 @@ impl CudaContext {
             num_streams: AtomicUsize::new(0),
             event_tracking: AtomicBool::new(true),
             error_state: AtomicU32::new(0),
+            invalidated: AtomicBool::new(false),
+            reset_epoch: AtomicU64::new(PRIMARY_RESET_EPOCH.load(Ordering::Acquire)),
+            #[cfg(feature = "alloc-tracking")]
+            allocations: Default::default(),
         });
         ctx.bind_to_thread()?;
+        #[cfg(feature = "alloc-tracking")]
+        super::alloc_tracking::register_context(&ctx);
         Ok(ctx)
     }
 
@@ -214,8 +239,14 @@ impl CudaContext {
             num_streams: AtomicUsize::new(0),
             event_tracking: AtomicBool::new(true),
             error_state: AtomicU32::new(0),
+            invalidated: AtomicBool::new(false),
+            reset_epoch: AtomicU64::new(PRIMARY_RESET_EPOCH.load(Ordering::Acquire)),
+            #[cfg(feature = "alloc-tracking")]
+            allocations: Default::default(),
         });
         ctx.bind_to_thread()?;
+        #[cfg(feature = "alloc-tracking")]
+        super::alloc_tracking::register_context(&ctx);
         Ok(ctx)
     }
 
@@ -251,8 +282,14 @@ impl CudaContext {
             num_streams: AtomicUsize::new(0),
             event_tracking: AtomicBool::new(true),
             error_state: AtomicU32::new(0),
+            invalidated: AtomicBool::new(false),
+            reset_epoch: AtomicU64::new(PRIMARY_RESET_EPOCH.load(Ordering::Acquire)),
+            #[cfg(feature = "alloc-tracking")]
+            allocations: Default::default(),
         });
         ctx.bind_to_thread()?;
+        #[cfg(feature = "alloc-tracking")]
+        super::alloc_tracking::register_context(&ctx);
         Ok(ctx)
     }
 
@@ -498,26 +535,177 @@ impl CudaContext {
     /// that couldn't return a result (e.g. Drop calls).
     ///
     /// If there are any errors stored, this method will return the Err value, and
-    /// then clear the stored error state.
+    /// then clear the stored error state. [Sticky](DriverError::is_sticky()) errors are
+    /// not cleared, since the context stays unusable, and after [CudaContext::reset()]
+    /// this always returns [sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED].
     pub fn check_err(&self) -> Result<(), DriverError> {
-        let error_state = self.error_state.swap(0, Ordering::Relaxed);
+        if self.is_invalidated() {
+            return Err(DriverError(
+                sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED,
+            ));
+        }
+        let error_state = self.error_state.load(Ordering::Relaxed);
         if error_state == 0 {
-            Ok(())
-        } else {
-            Err(result::DriverError(unsafe {
-                std::mem::transmute::<u32, sys::cudaError_enum>(error_state)
-            }))
+            return Ok(());
+        }
+        let err = result::DriverError(unsafe {
+            std::mem::transmute::<u32, sys::cudaError_enum>(error_state)
+        });
+        if !err.is_sticky() {
+            // Only clear what we read, in case a sticky error was stored since.
+            let _ = self.error_state.compare_exchange(
+                error_state,
+                0,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
         }
+        Err(err)
     }
 
     /// Records a result for later inspection when a Result can be returned.
+    ///
+    /// A stored [sticky](DriverError::is_sticky()) error is never overwritten.
     pub fn record_err<T>(&self, result: Result<T, DriverError>) {
         if let Err(err) = result {
-            self.error_state.store(err.0 as u32, Ordering::Relaxed)
+            let _ = self
+                .error_state
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |prev| {
+                    let prev_is_sticky = prev != 0
+                        && DriverError(unsafe {
+                            std::mem::transmute::<u32, sys::cudaError_enum>(prev)
+                        })
+                        .is_sticky();
+                    (!prev_is_sticky).then_some(err.0 as u32)
+                });
+        }
+    }
+
+    /// Whether [CudaContext::reset()] was called on this context. If so, every method of
+    /// this context and everything created in it returns
+    /// [sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED], and dropping them does
+    /// nothing, since their resources were already destroyed by the reset.
+    pub fn is_invalidated(&self) -> bool {
+        if self.invalidated.load(Ordering::Acquire) {
+            return true;
+        }
+        if !self.is_primary {
+            return false;
+        }
+        let epoch = PRIMARY_RESET_EPOCH.load(Ordering::Acquire);
+        let seen = self.reset_epoch.load(Ordering::Acquire);
+        if epoch == seen {
+            return false;
+        }
+        let resets = PRIMARY_RESETS.lock().unwrap();
+        let was_reset = resets
+            .iter()
+            .any(|&(dev, reset)| dev == self.cu_device && reset > seen);
+        if was_reset {
+            self.invalidated.store(true, Ordering::Release);
+        } else {
+            self.reset_epoch.store(epoch, Ordering::Release);
+        }
+        was_reset
+    }
+
+    /// Probes whether this context is still usable, e.g. after a kernel failed.
+    ///
+    /// **This synchronizes the context**, since errors raised by running kernels are only
+    /// reported once the work has finished. A sticky error found this way is stored, so
+    /// every later [CudaContext::check_err()] keeps returning it.
+    ///
+    /// Non-sticky errors of the probe itself are stored for the next
+    /// [CudaContext::check_err()] and reported as [ContextHealth::Healthy].
+    pub fn health(&self) -> ContextHealth {
+        if self.is_invalidated() {
+            return ContextHealth::Invalidated;
+        }
+        let error_state = self.error_state.load(Ordering::Relaxed);
+        if error_state != 0 {
+            let err = DriverError(unsafe {
+                std::mem::transmute::<u32, sys::cudaError_enum>(error_state)
+            });
+            if err.is_sticky() {
+                return ContextHealth::Poisoned(err);
+            }
         }
+        let probe = (|| {
+            if match result::ctx::get_current()? {
+                Some(curr_ctx) => curr_ctx != self.cu_ctx,
+                None => true,
+            } {
+                unsafe { result::ctx::set_current(self.cu_ctx) }?;
+            }
+            result::ctx::synchronize()
+        })();
+        match probe {
+            Err(err) if err.is_sticky() => {
+                self.record_err(Err::<(), _>(err));
+                ContextHealth::Poisoned(err)
+            }
+            other => {
+                self.record_err(other);
+                ContextHealth::Healthy
+            }
+        }
+    }
+
+    /// Resets the device's primary context after a [sticky](DriverError::is_sticky())
+    /// error, and returns a fresh [CudaContext] for the device.
+    ///
+    /// All memory, streams, events, modules and graphs of the primary context are destroyed.
+    /// This context, **and every other [CudaContext] of this device's primary context**, is
+    /// then [invalidated](CudaContext::is_invalidated()): everything that was created in it
+    /// returns [sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED] instead of touching
+    /// resources that no longer exist, and can be dropped as usual. Using old device, pinned or
+    /// unified memory with the new context's streams fails with the same error.
+    ///
+    /// If the reset itself fails, nothing is invalidated.
+    ///
+    /// Handles of the other libraries (cuBLAS, cuDNN, NCCL, ...) created on this context
+    /// should be dropped before calling this, since they free device memory of their own.
+    ///
+    /// Returns [sys::cudaError_enum::CUDA_ERROR_NOT_SUPPORTED] for non-primary contexts,
+    /// which should be dropped and recreated instead.
+    ///
+    /// Example:
+    /// ```ignore
+    /// if let ContextHealth::Poisoned(err) = ctx.health() {
+    ///     log::error!("device {} poisoned by {err:?}, resetting", ctx.ordinal());
+    ///     ctx = ctx.reset()?;
+    ///     model = Model::load(&ctx)?;
+    /// }
+    /// ```
+    pub fn reset(self: &Arc<Self>) -> Result<Arc<Self>, DriverError> {
+        if !self.is_primary {
+            return Err(DriverError(sys::cudaError_enum::CUDA_ERROR_NOT_SUPPORTED));
+        }
+        {
+            // Only invalidate the device's contexts once the reset went through, otherwise
+            // they are all still usable.
+            let mut resets = PRIMARY_RESETS.lock().unwrap();
+            unsafe { result::primary_ctx::reset(self.cu_device) }?;
+            let epoch = PRIMARY_RESET_EPOCH.fetch_add(1, Ordering::AcqRel) + 1;
+            resets.push((self.cu_device, epoch));
+        }
+        self.invalidated.store(true, Ordering::Release);
+        Self::new(self.ordinal)
     }
 }
 
+/// The result of [CudaContext::health()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextHealth {
+    /// The context can be used.
+    Healthy,
+    /// A [sticky](DriverError::is_sticky()) error happened, so nothing in this context can be
+    /// used anymore. See [CudaContext::reset()].
+    Poisoned(DriverError),
+    /// [CudaContext::reset()] was called, and a new context must be used.
+    Invalidated,
+}
+
 /// A lightweight synchronization primitive used to synchronize between [CudaStream]s.
 ///
 /// - Create using [CudaContext::new_event()].
@@ -539,6 +727,10 @@ unsafe impl Sync for CudaEvent {}
 
 impl Drop for CudaEvent {
     fn drop(&mut self) {
+        // The reset already destroyed this.
+        if self.ctx.is_invalidated() {
+            return;
+        }
         self.ctx.record_err(self.ctx.bind_to_thread());
         self.ctx
             .record_err(unsafe { result::event::destroy(self.cu_event) });
@@ -638,6 +830,10 @@ unsafe impl Sync for CudaStream {}
 
 impl Drop for CudaStream {
     fn drop(&mut self) {
+        // The reset already destroyed this.
+        if self.ctx.is_invalidated() {
+            return;
+        }
         self.ctx.record_err(self.ctx.bind_to_thread());
         let cu_stream = std::mem::replace(&mut self.cu_stream, std::ptr::null_mut());
         if !cu_stream.is_null() && cu_stream != (0x2 as _) {
@@ -791,6 +987,8 @@ pub struct CudaSlice<T> {
     pub(crate) read: Option<CudaEvent>,
     pub(crate) write: Option<CudaEvent>,
     pub(crate) stream: Arc<CudaStream>,
+    #[cfg(feature = "alloc-tracking")]
+    pub(crate) alloc_id: u64,
     pub(crate) marker: PhantomData<*const T>,
 }
 
@@ -800,6 +998,12 @@ unsafe impl<T> Sync for CudaSlice<T> {}
 impl<T> Drop for CudaSlice<T> {
     fn drop(&mut self) {
         let ctx = &self.stream.ctx;
+        #[cfg(feature = "alloc-tracking")]
+        ctx.allocations.remove(self.alloc_id);
+        // The reset already destroyed this.
+        if ctx.is_invalidated() {
+            return;
+        }
         if let Some(read) = self.read.as_ref() {
             ctx.record_err(self.stream.wait(read));
         }
@@ -877,7 +1081,7 @@ pub struct CudaView<'a, T> {
     pub(crate) read: &'a Option<CudaEvent>,
     pub(crate) write: &'a Option<CudaEvent>,
     pub(crate) stream: &'a Arc<CudaStream>,
-    marker: PhantomData<&'a [T]>,
+    pub(crate) marker: PhantomData<&'a [T]>,
 }
 
 impl<T> CudaSlice<T> {
@@ -924,7 +1128,7 @@ pub struct CudaViewMut<'a, T> {
     pub(crate) read: &'a Option<CudaEvent>,
     pub(crate) write: &'a Option<CudaEvent>,
     pub(crate) stream: &'a Arc<CudaStream>,
-    marker: PhantomData<&'a mut [T]>,
+    pub(crate) marker: PhantomData<&'a mut [T]>,
 }
 
 impl<T> CudaSlice<T> {
@@ -1144,6 +1348,35 @@ impl Drop for SyncOnDrop<'_> {
     }
 }
 
+/// Whether `ctx` was [reset](CudaContext::reset()), which freed all of its memory, so a
+/// pointer into it may alias a new allocation. If so, records
+/// [sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED] on `stream`'s context, and the
+/// memory should be replaced by a null pointer/empty slice.
+#[inline(always)]
+pub(crate) fn record_if_reset(ctx: &CudaContext, stream: &CudaStream) -> bool {
+    let reset = ctx.is_invalidated();
+    if reset {
+        stream.ctx.record_err(Err::<(), _>(DriverError(
+            sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED,
+        )));
+    }
+    reset
+}
+
+impl CudaContext {
+    /// Returns [sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED] if this context was
+    /// [reset](CudaContext::reset()).
+    pub(crate) fn check_not_reset(&self) -> Result<(), DriverError> {
+        if self.is_invalidated() {
+            Err(DriverError(
+                sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Abstraction over [CudaSlice]/[CudaView]
 pub trait DevicePtr<T>: DeviceSlice<T> {
     /// Retrieve the device pointer with the intent to read the device memory
@@ -1151,6 +1384,9 @@ pub trait DevicePtr<T>: DeviceSlice<T> {
     ///
     /// Implementations of this method should ensure `stream` waits for any previous
     /// writes of this memory before continuing (do not need to wait for any previous reads).
+    /// If the memory's context was [reset](CudaContext::reset()), the implementations in this
+    /// crate return a null pointer instead, and record
+    /// [sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED] on `stream`'s context.
     ///
     /// The [SyncOnDrop] item of the return tuple should be dropped **after** the read of
     /// the [sys::CUdeviceptr] is scheduled.
@@ -1165,6 +1401,9 @@ pub trait DevicePtr<T>: DeviceSlice<T> {
 
 impl<T> DevicePtr<T> for CudaSlice<T> {
     fn device_ptr<'a>(&'a self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(write) = self.write.as_ref() {
                 stream.ctx.record_err(stream.wait(write));
@@ -1179,6 +1418,9 @@ impl<T> DevicePtr<T> for CudaSlice<T> {
 
 impl<T> DevicePtr<T> for CudaView<'_, T> {
     fn device_ptr<'a>(&'a self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(write) = self.write.as_ref() {
                 stream.ctx.record_err(stream.wait(write));
@@ -1192,6 +1434,9 @@ impl<'a, T> CudaView<'a, T> {
     /// Identical behavior to [DevicePtr::device_ptr()], but the lifetime on the returned
     /// [SyncOnDrop], matches the lifetime of the view.
     pub fn view_ptr(self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(write) = self.write.as_ref() {
                 stream.ctx.record_err(stream.wait(write));
@@ -1203,6 +1448,9 @@ impl<'a, T> CudaView<'a, T> {
 
 impl<T> DevicePtr<T> for CudaViewMut<'_, T> {
     fn device_ptr<'a>(&'a self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(write) = self.write.as_ref() {
                 stream.ctx.record_err(stream.wait(write));
@@ -1216,6 +1464,9 @@ impl<'a, T> CudaViewMut<'a, T> {
     /// Identical behavior to [DevicePtr::device_ptr()], but the lifetime on the returned
     /// [SyncOnDrop], matches the lifetime of the view.
     pub fn view_ptr(self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(write) = self.write.as_ref() {
                 stream.ctx.record_err(stream.wait(write));
@@ -1231,7 +1482,8 @@ pub trait DevicePtrMut<T>: DeviceSlice<T> {
     /// associated with it.
     ///
     /// Implementations of this method should ensure `stream` waits for any previous
-    /// reads/writes of this memory before continuing.
+    /// reads/writes of this memory before continuing. Like [DevicePtr::device_ptr()], the
+    /// implementations in this crate return a null pointer if the memory's context was reset.
     ///
     /// The [SyncOnDrop] item of the return tuple should be dropped **after** the write of
     /// the [sys::CUdeviceptr] is scheduled.
@@ -1252,6 +1504,9 @@ impl<T> DevicePtrMut<T> for CudaSlice<T> {
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(read) = self.read.as_ref() {
                 stream.ctx.record_err(stream.wait(read));
@@ -1272,6 +1527,9 @@ impl<T> DevicePtrMut<T> for CudaViewMut<'_, T> {
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(read) = self.read.as_ref() {
                 stream.ctx.record_err(stream.wait(read));
@@ -1288,6 +1546,9 @@ impl<'a, T> CudaViewMut<'a, T> {
     /// Identical behavior to [DevicePtrMut::device_ptr_mut()], but the lifetime on the returned
     /// [SyncOnDrop], matches the lifetime of the view.
     pub fn view_ptr_mut(self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(read) = self.read.as_ref() {
                 stream.ctx.record_err(stream.wait(read));
@@ -1384,11 +1645,17 @@ impl<T> HostSlice<T> for Vec<T> {
 ///
 /// Allocate this with [CudaContext::alloc_pinned()], and do device copies with
 /// [CudaStream::clone_htod()]/[CudaStream::memcpy_htod()]/[CudaStream::memcpy_dtoh()]
+///
+/// If allocated with [sys::CU_MEMHOSTALLOC_DEVICEMAP], kernels can access the memory
+/// directly through [PinnedHostSlice::device_view()]/[PinnedHostSlice::device_view_mut()].
 #[derive(Debug)]
 pub struct PinnedHostSlice<T> {
     pub(crate) ptr: *mut T,
     pub(crate) len: usize,
-    pub(crate) event: CudaEvent,
+    /// Always `Some`. Stored as an option so device views can borrow it.
+    pub(crate) event: Option<CudaEvent>,
+    #[cfg(feature = "alloc-tracking")]
+    pub(crate) alloc_id: u64,
 }
 
 unsafe impl<T> Send for PinnedHostSlice<T> {}
@@ -1396,8 +1663,14 @@ unsafe impl<T> Sync for PinnedHostSlice<T> {}
 
 impl<T> Drop for PinnedHostSlice<T> {
     fn drop(&mut self) {
-        let ctx = &self.event.ctx;
-        ctx.record_err(self.event.synchronize());
+        let ctx = &self.event().ctx;
+        #[cfg(feature = "alloc-tracking")]
+        ctx.allocations.remove(self.alloc_id);
+        // The reset already destroyed this.
+        if ctx.is_invalidated() {
+            return;
+        }
+        ctx.record_err(self.event().synchronize());
         ctx.record_err(unsafe { result::free_host(self.ptr as _) });
     }
 }
@@ -1412,25 +1685,49 @@ impl CudaContext {
     pub unsafe fn alloc_pinned<T: DeviceRepr>(
         self: &Arc<Self>,
         len: usize,
+    ) -> Result<PinnedHostSlice<T>, DriverError> {
+        self.alloc_pinned_with_flags(len, sys::CU_MEMHOSTALLOC_WRITECOMBINED)
+    }
+
+    /// Allocates page locked host memory with the given `flags`, which is a combination of
+    /// [sys::CU_MEMHOSTALLOC_PORTABLE], [sys::CU_MEMHOSTALLOC_DEVICEMAP] and
+    /// [sys::CU_MEMHOSTALLOC_WRITECOMBINED]. Use 0 for no flags.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1g572ca4011bfcb25034888a14d4e035b9)
+    ///
+    /// # Safety
+    /// 1. This is unsafe because the memory is unset after this call.
+    pub unsafe fn alloc_pinned_with_flags<T: DeviceRepr>(
+        self: &Arc<Self>,
+        len: usize,
+        flags: u32,
     ) -> Result<PinnedHostSlice<T>, DriverError> {
         self.bind_to_thread()?;
-        let ptr = result::malloc_host(
-            len * std::mem::size_of::<T>(),
-            sys::CU_MEMHOSTALLOC_WRITECOMBINED,
-        )?;
+        let ptr = result::malloc_host(len * std::mem::size_of::<T>(), flags)?;
         let ptr = ptr as *mut T;
         assert!(!ptr.is_null());
         assert!(len * std::mem::size_of::<T>() < isize::MAX as usize);
         assert!(ptr.is_aligned());
         let event = self.new_event(Some(sys::CUevent_flags::CU_EVENT_BLOCKING_SYNC))?;
-        Ok(PinnedHostSlice { ptr, len, event })
+        Ok(PinnedHostSlice {
+            ptr,
+            len,
+            event: Some(event),
+            #[cfg(feature = "alloc-tracking")]
+            alloc_id: self.allocations.insert::<T>(
+                super::alloc_tracking::AllocationKind::PinnedHost,
+                ptr as u64,
+                len,
+                None,
+            ),
+        })
     }
 }
 
 impl<T> PinnedHostSlice<T> {
     /// The context this was created in.
     pub fn context(&self) -> &Arc<CudaContext> {
-        &self.event.ctx
+        &self.event().ctx
     }
 
     /// The number of elements `T` in this slice.
@@ -1446,34 +1743,42 @@ impl<T> PinnedHostSlice<T> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    pub(crate) fn event(&self) -> &CudaEvent {
+        self.event.as_ref().unwrap()
+    }
 }
 
 impl<T: ValidAsZeroBits> PinnedHostSlice<T> {
     /// Waits for any scheduled work to complete and then returns a refernce
     /// to the host side data.
     pub fn as_ptr(&self) -> Result<*const T, DriverError> {
-        self.event.synchronize()?;
+        self.context().check_not_reset()?;
+        self.event().synchronize()?;
         Ok(self.ptr)
     }
 
     /// Waits for any scheduled work to complete and then returns a refernce
     /// to the host side data.
     pub fn as_mut_ptr(&mut self) -> Result<*mut T, DriverError> {
-        self.event.synchronize()?;
+        self.context().check_not_reset()?;
+        self.event().synchronize()?;
         Ok(self.ptr)
     }
 
     /// Waits for any scheduled work to complete and then returns a refernce
     /// to the host side data.
     pub fn as_slice(&self) -> Result<&[T], DriverError> {
-        self.event.synchronize()?;
+        self.context().check_not_reset()?;
+        self.event().synchronize()?;
         Ok(unsafe { std::slice::from_raw_parts(self.ptr, self.len) })
     }
 
     /// Waits for any scheduled work to complete and then returns a refernce
     /// to the host side data.
     pub fn as_mut_slice(&mut self) -> Result<&mut [T], DriverError> {
-        self.event.synchronize()?;
+        self.context().check_not_reset()?;
+        self.event().synchronize()?;
         Ok(unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) })
     }
 }
@@ -1487,20 +1792,26 @@ impl<T> HostSlice<T> for PinnedHostSlice<T> {
         &'a self,
         stream: &'a CudaStream,
     ) -> (&'a [T], SyncOnDrop<'a>) {
-        stream.ctx.record_err(stream.wait(&self.event));
+        if record_if_reset(self.context(), stream) {
+            return (&[], SyncOnDrop::Record(None));
+        }
+        stream.ctx.record_err(stream.wait(self.event()));
         (
             std::slice::from_raw_parts(self.ptr, self.len),
-            SyncOnDrop::Record(Some((&self.event, stream))),
+            SyncOnDrop::Record(Some((self.event(), stream))),
         )
     }
     unsafe fn stream_synced_mut_slice<'a>(
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (&'a mut [T], SyncOnDrop<'a>) {
-        stream.ctx.record_err(stream.wait(&self.event));
+        if record_if_reset(self.context(), stream) {
+            return (&mut [], SyncOnDrop::Record(None));
+        }
+        stream.ctx.record_err(stream.wait(self.event()));
         (
             std::slice::from_raw_parts_mut(self.ptr, self.len),
-            SyncOnDrop::Record(Some((&self.event, stream))),
+            SyncOnDrop::Record(Some((self.event(), stream))),
         )
     }
 }
@@ -1520,6 +1831,8 @@ impl CudaStream {
             read: None,
             write: None,
             stream: self.clone(),
+            #[cfg(feature = "alloc-tracking")]
+            alloc_id: self.track_alloc::<T>(cu_device_ptr, 0),
             marker: PhantomData,
         })
     }
@@ -1551,6 +1864,8 @@ impl CudaStream {
             read,
             write,
             stream: self.clone(),
+            #[cfg(feature = "alloc-tracking")]
+            alloc_id: self.track_alloc::<T>(cu_device_ptr, len),
             marker: PhantomData,
         })
     }
@@ -1573,6 +1888,7 @@ impl CudaStream {
         self.ctx.bind_to_thread()?;
         let num_bytes = dst.num_bytes();
         let (dptr, _record) = dst.device_ptr_mut(self);
+        self.ctx.check_err()?;
         unsafe { result::memset_d8_async(dptr, 0, num_bytes, self.cu_stream) }?;
         Ok(())
     }
@@ -1608,6 +1924,7 @@ impl CudaStream {
         self.ctx.bind_to_thread()?;
         let (src, _record_src) = unsafe { src.stream_synced_slice(self) };
         let (dst, _record_dst) = dst.device_ptr_mut(self);
+        self.ctx.check_err()?;
         unsafe { result::memcpy_htod_async(dst, src, self.cu_stream) }
     }
 
@@ -1650,6 +1967,7 @@ impl CudaStream {
         self.ctx.bind_to_thread()?;
         let (src, _record_src) = src.device_ptr(self);
         let (dst, _record_dst) = unsafe { dst.stream_synced_mut_slice(self) };
+        self.ctx.check_err()?;
         unsafe { result::memcpy_dtoh_async(dst, src, self.cu_stream) }
     }
 
@@ -1670,6 +1988,7 @@ impl CudaStream {
         if src_ctx == dst_ctx {
             let (src_ptr, _record_src) = src.device_ptr(self);
             let (dst_ptr, _record_dst) = dst.device_ptr_mut(self);
+            self.ctx.check_err()?;
             unsafe { result::memcpy_dtod_async(dst_ptr, src_ptr, num_bytes, self.cu_stream) }
         } else {
             // NOTE: Although we want the current stream to wait on src to be ready,
@@ -2152,6 +2471,14 @@ pub(super) fn to_range(range: impl RangeBounds<usize>, len: usize) -> Option<(us
 pub struct CudaModule {
     pub(crate) cu_module: sys::CUmodule,
     pub(crate) ctx: Arc<CudaContext>,
+    /// Maps nvrtc name expressions to mangled names, see [CudaModule::load_function()].
+    pub(crate) lowered_names: BTreeMap<String, String>,
+    /// The kernels declared in the PTX this was loaded from, to check launch arguments.
+    #[cfg(feature = "debug-launch-checks")]
+    pub(crate) entries: Vec<crate::nvrtc::PtxEntry>,
+    /// The events shared by every [super::CudaGlobal] of a symbol, keyed by its address.
+    pub(crate) globals:
+        Mutex<BTreeMap<sys::CUdeviceptr, std::sync::Weak<super::global::GlobalEvents>>>,
 }
 
 unsafe impl Send for CudaModule {}
@@ -2159,6 +2486,10 @@ unsafe impl Sync for CudaModule {}
 
 impl Drop for CudaModule {
     fn drop(&mut self) {
+        // The reset already destroyed this.
+        if self.ctx.is_invalidated() {
+            return;
+        }
         self.ctx.record_err(self.ctx.bind_to_thread());
         self.ctx
             .record_err(unsafe { result::module::unload(self.cu_module) });
@@ -2176,7 +2507,9 @@ impl CudaContext {
     ) -> Result<Arc<CudaModule>, result::DriverError> {
         self.bind_to_thread()?;
 
-        let cu_module = match ptx.0 {
+        #[cfg(feature = "debug-launch-checks")]
+        let entries = ptx.info().map(|info| info.entries).unwrap_or_default();
+        let cu_module = match ptx.kind {
             crate::nvrtc::PtxKind::Image(image) => unsafe {
                 result::module::load_data(image.as_ptr() as *const _)
             },
@@ -2195,6 +2528,10 @@ impl CudaContext {
         Ok(Arc::new(CudaModule {
             cu_module,
             ctx: self.clone(),
+            lowered_names: ptx.lowered_names,
+            #[cfg(feature = "debug-launch-checks")]
+            entries,
+            globals: Mutex::new(BTreeMap::new()),
         }))
     }
 }
@@ -2203,8 +2540,10 @@ impl CudaContext {
 #[derive(Debug, Clone)]
 pub struct CudaFunction {
     pub(crate) cu_function: sys::CUfunction,
-    #[allow(unused)]
     pub(crate) module: Arc<CudaModule>,
+    /// The parameters from the module's PTX, if it was loaded from PTX.
+    #[cfg(feature = "debug-launch-checks")]
+    pub(crate) signature: Option<crate::nvrtc::PtxEntry>,
 }
 
 unsafe impl Send for CudaFunction {}
@@ -2212,12 +2551,22 @@ unsafe impl Sync for CudaFunction {}
 
 impl CudaModule {
     /// Loads a function from the loaded module with the given name.
+    ///
+    /// `fn_name` can also be one of the [crate::nvrtc::CompileOptions::name_expressions] the
+    /// module was compiled with, like `my_kernel<float, 128>`, which loads its instantiation.
     pub fn load_function(self: &Arc<Self>, fn_name: &str) -> Result<CudaFunction, DriverError> {
+        let fn_name = self
+            .lowered_names
+            .get(fn_name)
+            .map(String::as_str)
+            .unwrap_or(fn_name);
         let fn_name_c = CString::new(fn_name).unwrap();
         let cu_function = unsafe { result::module::get_function(self.cu_module, fn_name_c) }?;
         Ok(CudaFunction {
             cu_function,
             module: self.clone(),
+            #[cfg(feature = "debug-launch-checks")]
+            signature: self.entries.iter().find(|e| e.name == fn_name).cloned(),
         })
     }
 
@@ -2252,9 +2601,128 @@ impl CudaModule {
             marker: PhantomData,
         })
     }
+
+    /// All kernels in this module, in no particular order. Useful when the kernel names are
+    /// not known ahead of time, e.g. for user provided PTX. See [CudaFunction::name()].
+    ///
+    /// Requires cuda 12.4 or newer.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MODULE.html)
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub fn functions(self: &Arc<Self>) -> Result<Vec<CudaFunction>, DriverError> {
+        self.ctx.bind_to_thread()?;
+        let functions = unsafe { result::module::enumerate_functions(self.cu_module) }?;
+        Ok(functions
+            .into_iter()
+            .map(|cu_function| CudaFunction {
+                cu_function,
+                module: self.clone(),
+                #[cfg(feature = "debug-launch-checks")]
+                signature: None,
+            })
+            .collect())
+    }
+
+    /// The (mangled) names of all kernels in this module, sorted. Any of these can be passed
+    /// to [CudaModule::load_function()].
+    ///
+    /// Requires cuda 12.4 or newer.
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub fn function_names(self: &Arc<Self>) -> Result<Vec<String>, DriverError> {
+        let mut names = self
+            .functions()?
+            .iter()
+            .map(CudaFunction::name)
+            .collect::<Result<Vec<_>, _>>()?;
+        names.sort();
+        Ok(names)
+    }
 }
 
 impl CudaFunction {
+    /// The (mangled) name of this kernel. `extern "C"` kernels are not mangled.
+    ///
+    /// Requires cuda 12.3 or newer.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__EXEC.html)
+    #[cfg(any(
+        feature = "cuda-12030",
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub fn name(&self) -> Result<String, DriverError> {
+        self.module.ctx.bind_to_thread()?;
+        unsafe { result::function::get_name(self.cu_function) }
+    }
+
+    /// Whether this kernel has been loaded onto the device yet. With lazy loading
+    /// (`CUDA_MODULE_LOADING=LAZY`, the default since cuda 12.2) kernels are only loaded on
+    /// first launch.
+    ///
+    /// Requires cuda 12.4 or newer.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__EXEC.html)
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub fn is_loaded(&self) -> Result<bool, DriverError> {
+        self.module.ctx.bind_to_thread()?;
+        let state = unsafe { result::function::is_loaded(self.cu_function) }?;
+        Ok(state == sys::CUfunctionLoadingState::CU_FUNCTION_LOADING_STATE_LOADED)
+    }
+
+    /// Loads this kernel now instead of on first launch, e.g. to keep the loading cost
+    /// out of a latency sensitive path. Does nothing if it is already loaded.
+    ///
+    /// Requires cuda 12.4 or newer.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__EXEC.html)
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub fn load_eagerly(&self) -> Result<(), DriverError> {
+        self.module.ctx.bind_to_thread()?;
+        unsafe { result::function::load(self.cu_function) }
+    }
+
     pub fn occupancy_available_dynamic_smem_per_block(
         &self,
         num_blocks: u32,
@@ -2465,6 +2933,9 @@ impl<T> CudaSlice<T> {
         let mut s = std::mem::ManuallyDrop::new(self);
         let ptr = s.cu_device_ptr;
 
+        #[cfg(feature = "alloc-tracking")]
+        s.stream.ctx.allocations.remove(s.alloc_id);
+
         // Ensure pending operations are complete before resources are released.
         if let Some(read) = s.read.as_ref() {
             s.stream.ctx.record_err(s.stream.wait(read));
@@ -2512,9 +2983,21 @@ impl CudaStream {
             read,
             write,
             stream: self.clone(),
+            #[cfg(feature = "alloc-tracking")]
+            alloc_id: self.track_alloc::<T>(cu_device_ptr, len),
             marker: PhantomData,
         }
     }
+
+    #[cfg(feature = "alloc-tracking")]
+    fn track_alloc<T>(&self, cu_device_ptr: sys::CUdeviceptr, len: usize) -> u64 {
+        self.ctx.allocations.insert::<T>(
+            super::alloc_tracking::AllocationKind::Device,
+            cu_device_ptr,
+            len,
+            Some(self.cu_stream),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -2920,4 +3403,95 @@ mod tests {
         });
         handle.join().unwrap();
     }
+
+    #[test]
+    #[cfg(any(
+        feature = "cuda-12040",
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    fn test_module_functions() {
+        let ctx = CudaContext::new(0).unwrap();
+        let ptx = crate::nvrtc::compile_ptx(
+            r#"
+extern "C" __global__ void first(float *x) { x[0] = 1.0f; }
+extern "C" __global__ void second(float *x) { x[0] = 2.0f; }
+__global__ void mangled(int *x) { x[0] = 3; }
+"#,
+        )
+        .unwrap();
+        let module = ctx.load_module(ptx).unwrap();
+
+        assert_eq!(
+            module.function_names().unwrap(),
+            ["_Z7mangledPi", "first", "second"]
+        );
+        let first = module.load_function("first").unwrap();
+        assert_eq!(first.name().unwrap(), "first");
+
+        for f in module.functions().unwrap() {
+            f.load_eagerly().unwrap();
+            assert!(f.is_loaded().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sticky_errors_are_not_cleared() {
+        let ctx = CudaContext::new(0).unwrap();
+        let invalid = DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE);
+        ctx.record_err(Err::<(), _>(invalid));
+        assert_eq!(ctx.check_err(), Err(invalid));
+        assert_eq!(ctx.check_err(), Ok(()));
+
+        let illegal = DriverError(sys::CUresult::CUDA_ERROR_ILLEGAL_ADDRESS);
+        ctx.record_err(Err::<(), _>(illegal));
+        ctx.record_err(Err::<(), _>(invalid));
+        assert_eq!(ctx.check_err(), Err(illegal));
+        assert_eq!(ctx.check_err(), Err(illegal));
+        assert_eq!(ctx.health(), ContextHealth::Poisoned(illegal));
+    }
+
+    #[test]
+    #[ignore = "resets the primary context, which breaks tests running concurrently"]
+    fn test_reset_invalidates_handles() {
+        let ctx = CudaContext::new(0).unwrap();
+        let other = CudaContext::new(0).unwrap();
+        assert_eq!(ctx.health(), ContextHealth::Healthy);
+        let stream = ctx.new_stream().unwrap();
+        let slice = stream.clone_htod(&[1.0f32; 16]).unwrap();
+        let pinned = unsafe { ctx.alloc_pinned::<f32>(16) }.unwrap();
+
+        let fresh = ctx.reset().unwrap();
+        assert_eq!(ctx.health(), ContextHealth::Invalidated);
+        assert!(other.is_invalidated());
+        let destroyed = DriverError(sys::CUresult::CUDA_ERROR_CONTEXT_IS_DESTROYED);
+        assert_eq!(stream.clone_dtoh(&slice).unwrap_err(), destroyed);
+        assert_eq!(other.new_stream().unwrap_err(), destroyed);
+
+        // The old slice can't be used on the new context's streams either.
+        assert!(!fresh.is_invalidated());
+        let fresh_stream = fresh.default_stream();
+        assert_eq!(fresh_stream.clone_dtoh(&slice).unwrap_err(), destroyed);
+        assert_eq!(fresh.check_err(), Ok(()));
+        assert_eq!(pinned.as_slice().unwrap_err(), destroyed);
+        let mut fresh_slice = fresh_stream.alloc_zeros::<f32>(16).unwrap();
+        assert_eq!(
+            fresh_stream
+                .memcpy_htod(&pinned, &mut fresh_slice)
+                .unwrap_err(),
+            destroyed
+        );
+        drop(pinned);
+        drop(slice);
+        drop(stream);
+
+        let stream = fresh_stream;
+        let slice = stream.clone_htod(&[2.0f32; 4]).unwrap();
+        assert_eq!(stream.clone_dtoh(&slice).unwrap(), [2.0; 4]);
+    }
 }
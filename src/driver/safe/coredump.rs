@@ -0,0 +1,315 @@
+use core::ffi::{c_void, CStr};
+use std::{ffi::CString, string::String, vec::Vec};
+
+use crate::driver::{result, sys};
+
+use super::{CudaContext, DriverError};
+
+/// Settings for GPU coredumps, which are written when a kernel raises an exception (like an
+/// illegal memory access) and can be inspected with `cuda-gdb`.
+///
+/// Only the settings that are `Some` are changed. Apply to every context created afterwards
+/// with [CoredumpConfig::apply_global()], or to a single context with
+/// [CudaContext::set_coredump_config()]. Unlike the `CUDA_ENABLE_COREDUMP_ON_EXCEPTION` etc.
+/// environment variables, these can be changed at any time.
+///
+/// Example:
+/// ```ignore
+/// CoredumpConfig::new()
+///     .enable_on_exception(true)
+///     .lightweight(true)
+///     .file("/var/crash/%p-%h.nvcudmp")
+///     .apply_global()?;
+/// ```
+///
+/// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__COREDUMP.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CoredumpConfig {
+    /// Write a coredump when a kernel raises an exception.
+    pub enable_on_exception: Option<bool>,
+    /// Also write a coredump of the host process when a GPU coredump is written.
+    pub trigger_host: Option<bool>,
+    /// Leave memory contents out of the coredump, which makes it much smaller.
+    pub lightweight: Option<bool>,
+    /// Allow writing coredumps by writing to the [CoredumpConfig::pipe].
+    pub enable_user_trigger: Option<bool>,
+    /// Where coredumps are written. Supports `%p` (process id), `%h` (hostname) and `%t`
+    /// (timestamp) patterns.
+    pub file: Option<String>,
+    /// The named pipe that triggers a coredump when written to, with the same patterns as
+    /// [CoredumpConfig::file].
+    pub pipe: Option<String>,
+    /// A combination of [sys::CUCoredumpGenerationFlags], selecting what goes into a coredump.
+    #[cfg(any(
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub generation_flags: Option<u32>,
+}
+
+/// The value of a single coredump attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CoredumpValue {
+    Bool(bool),
+    Str(CString),
+    #[allow(unused)]
+    Flags(u32),
+}
+
+impl CoredumpValue {
+    fn set(&self, attrib: sys::CUcoredumpSettings, global: bool) -> Result<(), DriverError> {
+        let (value, size) = match self {
+            CoredumpValue::Bool(b) => (b as *const bool as *mut c_void, 1),
+            CoredumpValue::Str(s) => (s.as_ptr() as *mut c_void, s.as_bytes_with_nul().len()),
+            CoredumpValue::Flags(f) => (f as *const u32 as *mut c_void, 4),
+        };
+        // The driver only reads from `value`.
+        unsafe {
+            if global {
+                result::coredump::set_attribute_global(attrib, value, size)
+            } else {
+                result::coredump::set_attribute(attrib, value, size)
+            }
+        }
+    }
+}
+
+/// The longest path the driver accepts for [CoredumpConfig::file] and [CoredumpConfig::pipe],
+/// including the nul terminator.
+const MAX_PATH_LEN: usize = 1024;
+
+impl CoredumpConfig {
+    /// A config that changes nothing. Set fields with the builder methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable_on_exception(mut self, enable: bool) -> Self {
+        self.enable_on_exception = Some(enable);
+        self
+    }
+
+    pub fn trigger_host(mut self, enable: bool) -> Self {
+        self.trigger_host = Some(enable);
+        self
+    }
+
+    pub fn lightweight(mut self, enable: bool) -> Self {
+        self.lightweight = Some(enable);
+        self
+    }
+
+    pub fn enable_user_trigger(mut self, enable: bool) -> Self {
+        self.enable_user_trigger = Some(enable);
+        self
+    }
+
+    pub fn file(mut self, pattern: impl Into<String>) -> Self {
+        self.file = Some(pattern.into());
+        self
+    }
+
+    pub fn pipe(mut self, pattern: impl Into<String>) -> Self {
+        self.pipe = Some(pattern.into());
+        self
+    }
+
+    #[cfg(any(
+        feature = "cuda-12050",
+        feature = "cuda-12060",
+        feature = "cuda-12080",
+        feature = "cuda-12090",
+        feature = "cuda-13000",
+        feature = "cuda-13010",
+        feature = "cuda-13020"
+    ))]
+    pub fn generation_flags(mut self, flags: u32) -> Self {
+        self.generation_flags = Some(flags);
+        self
+    }
+
+    /// The attributes to set, in order. Paths come first, so enabling coredumps never
+    /// writes to a previous location.
+    ///
+    /// Returns [sys::cudaError_enum::CUDA_ERROR_INVALID_VALUE] for paths that contain a nul
+    /// byte or are too long.
+    pub(crate) fn settings(
+        &self,
+    ) -> Result<Vec<(sys::CUcoredumpSettings, CoredumpValue)>, DriverError> {
+        use sys::CUcoredumpSettings::*;
+        let path = |p: &String| {
+            let p = CString::new(p.as_str())
+                .map_err(|_| DriverError(sys::cudaError_enum::CUDA_ERROR_INVALID_VALUE))?;
+            if p.as_bytes_with_nul().len() > MAX_PATH_LEN {
+                return Err(DriverError(sys::cudaError_enum::CUDA_ERROR_INVALID_VALUE));
+            }
+            Ok(CoredumpValue::Str(p))
+        };
+        let mut settings = Vec::new();
+        if let Some(file) = self.file.as_ref() {
+            settings.push((CU_COREDUMP_FILE, path(file)?));
+        }
+        if let Some(pipe) = self.pipe.as_ref() {
+            settings.push((CU_COREDUMP_PIPE, path(pipe)?));
+        }
+        #[cfg(any(
+            feature = "cuda-12050",
+            feature = "cuda-12060",
+            feature = "cuda-12080",
+            feature = "cuda-12090",
+            feature = "cuda-13000",
+            feature = "cuda-13010",
+            feature = "cuda-13020"
+        ))]
+        if let Some(flags) = self.generation_flags {
+            settings.push((CU_COREDUMP_GENERATION_FLAGS, CoredumpValue::Flags(flags)));
+        }
+        for (attrib, value) in [
+            (CU_COREDUMP_LIGHTWEIGHT, self.lightweight),
+            (CU_COREDUMP_TRIGGER_HOST, self.trigger_host),
+            (CU_COREDUMP_ENABLE_USER_TRIGGER, self.enable_user_trigger),
+            (CU_COREDUMP_ENABLE_ON_EXCEPTION, self.enable_on_exception),
+        ] {
+            if let Some(value) = value {
+                settings.push((attrib, CoredumpValue::Bool(value)));
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Applies this to every context created afterwards. Existing contexts are not changed.
+    pub fn apply_global(&self) -> Result<(), DriverError> {
+        result::init()?;
+        for (attrib, value) in self.settings()? {
+            value.set(attrib, true)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the current global settings. Every field is `Some`.
+    pub fn global() -> Result<Self, DriverError> {
+        result::init()?;
+        Self::read(true)
+    }
+
+    fn read(global: bool) -> Result<Self, DriverError> {
+        use sys::CUcoredumpSettings::*;
+        let get = |attrib, value: *mut c_void, size| unsafe {
+            if global {
+                result::coredump::get_attribute_global(attrib, value, size)
+            } else {
+                result::coredump::get_attribute(attrib, value, size)
+            }
+        };
+        let get_bool = |attrib| {
+            let mut value = false;
+            get(attrib, &mut value as *mut bool as *mut c_void, 1)?;
+            Ok::<_, DriverError>(Some(value))
+        };
+        let get_path = |attrib| {
+            let mut buf = [0u8; MAX_PATH_LEN];
+            get(attrib, buf.as_mut_ptr() as *mut c_void, buf.len())?;
+            let path = CStr::from_bytes_until_nul(&buf)
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            Ok::<_, DriverError>(Some(path))
+        };
+        Ok(Self {
+            enable_on_exception: get_bool(CU_COREDUMP_ENABLE_ON_EXCEPTION)?,
+            trigger_host: get_bool(CU_COREDUMP_TRIGGER_HOST)?,
+            lightweight: get_bool(CU_COREDUMP_LIGHTWEIGHT)?,
+            enable_user_trigger: get_bool(CU_COREDUMP_ENABLE_USER_TRIGGER)?,
+            file: get_path(CU_COREDUMP_FILE)?,
+            pipe: get_path(CU_COREDUMP_PIPE)?,
+            #[cfg(any(
+                feature = "cuda-12050",
+                feature = "cuda-12060",
+                feature = "cuda-12080",
+                feature = "cuda-12090",
+                feature = "cuda-13000",
+                feature = "cuda-13010",
+                feature = "cuda-13020"
+            ))]
+            generation_flags: {
+                let mut flags = 0u32;
+                get(
+                    CU_COREDUMP_GENERATION_FLAGS,
+                    &mut flags as *mut u32 as *mut c_void,
+                    4,
+                )?;
+                Some(flags)
+            },
+        })
+    }
+}
+
+impl CudaContext {
+    /// Applies `config` to this context only. The driver may not allow every setting per
+    /// context (e.g. [CoredumpConfig::pipe]), in which case it returns an error, and settings
+    /// applied before the failing one stay applied.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__COREDUMP.html)
+    pub fn set_coredump_config(&self, config: &CoredumpConfig) -> Result<(), DriverError> {
+        let settings = config.settings()?;
+        self.bind_to_thread()?;
+        for (attrib, value) in settings {
+            value.set(attrib, false)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the coredump settings of this context. Every field is `Some`.
+    pub fn coredump_config(&self) -> Result<CoredumpConfig, DriverError> {
+        self.bind_to_thread()?;
+        CoredumpConfig::read(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sys::CUcoredumpSettings::*;
+
+    #[test]
+    fn test_settings() {
+        assert_eq!(CoredumpConfig::new().settings().unwrap(), []);
+
+        let config = CoredumpConfig::new()
+            .enable_on_exception(true)
+            .lightweight(false)
+            .file("/tmp/core.%p");
+        assert_eq!(
+            config.settings().unwrap(),
+            [
+                (
+                    CU_COREDUMP_FILE,
+                    CoredumpValue::Str(CString::new("/tmp/core.%p").unwrap())
+                ),
+                (CU_COREDUMP_LIGHTWEIGHT, CoredumpValue::Bool(false)),
+                (CU_COREDUMP_ENABLE_ON_EXCEPTION, CoredumpValue::Bool(true)),
+            ]
+        );
+
+        let nul = CoredumpConfig::new().pipe("a\0b");
+        assert!(nul.settings().is_err());
+        let long = CoredumpConfig::new().file("a".repeat(MAX_PATH_LEN));
+        assert!(long.settings().is_err());
+    }
+
+    #[test]
+    fn test_context_coredump_config() {
+        let ctx = CudaContext::new(0).unwrap();
+        let config = CoredumpConfig::new()
+            .lightweight(true)
+            .file("/tmp/cudarc-test-core.%p");
+        ctx.set_coredump_config(&config).unwrap();
+        let current = ctx.coredump_config().unwrap();
+        assert_eq!(current.lightweight, Some(true));
+        assert_eq!(current.file.as_deref(), Some("/tmp/cudarc-test-core.%p"));
+    }
+}
@@ -0,0 +1,761 @@
+//! Zero copy interchange of device memory with other frameworks through
+//! [DLPack](https://dmlc.github.io/dlpack/latest/).
+//!
+//! Enabled with the `dlpack` feature. Export with [CudaSlice::to_dlpack()] (or
+//! [CudaView::to_dlpack()]), which hands out a [DLManagedTensor] whose deleter keeps the memory
+//! alive. Import with [CudaContext::from_dlpack()], which takes ownership of a tensor produced
+//! elsewhere, or [CudaContext::from_dlpack_borrowed()].
+//!
+//! The structs here mirror the C definitions in `dlpack.h` (the unversioned `DLManagedTensor`,
+//! which is what the `"dltensor"` PyCapsule holds), so they can be passed through PyO3 or any
+//! other FFI layer as is.
+
+use core::{ffi::c_void, marker::PhantomData, ptr::NonNull};
+use std::{boxed::Box, sync::Arc, vec::Vec};
+
+use crate::driver::sys;
+
+use super::{CudaContext, CudaEvent, CudaSlice, CudaStream, CudaView, CudaViewMut, DriverError};
+
+/// The kind of device a [DLTensor] lives on. Mirrors `DLDeviceType`.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DLDeviceType(pub i32);
+
+impl DLDeviceType {
+    pub const CPU: Self = Self(1);
+    pub const CUDA: Self = Self(2);
+    pub const CUDA_HOST: Self = Self(3);
+    pub const CUDA_MANAGED: Self = Self(13);
+}
+
+/// Mirrors `DLDevice`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DLDevice {
+    pub device_type: DLDeviceType,
+    pub device_id: i32,
+}
+
+/// The element type of a [DLTensor]. Mirrors `DLDataType`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DLDataType {
+    /// One of [DLDataType::INT], [DLDataType::UINT], [DLDataType::FLOAT],
+    /// [DLDataType::BFLOAT] or [DLDataType::BOOL].
+    pub code: u8,
+    pub bits: u8,
+    pub lanes: u16,
+}
+
+impl DLDataType {
+    pub const INT: u8 = 0;
+    pub const UINT: u8 = 1;
+    pub const FLOAT: u8 = 2;
+    pub const BFLOAT: u8 = 4;
+    pub const BOOL: u8 = 6;
+
+    pub const fn new(code: u8, bits: u8) -> Self {
+        Self {
+            code,
+            bits,
+            lanes: 1,
+        }
+    }
+}
+
+/// Mirrors `DLTensor`.
+///
+/// `shape` and `strides` have `ndim` elements each, and `strides` (in elements, not bytes)
+/// may be null for a compact row major tensor.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DLTensor {
+    pub data: *mut c_void,
+    pub device: DLDevice,
+    pub ndim: i32,
+    pub dtype: DLDataType,
+    pub shape: *mut i64,
+    pub strides: *mut i64,
+    pub byte_offset: u64,
+}
+
+/// Mirrors `DLManagedTensor`. Whoever ends up owning this must call `deleter` exactly once.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DLManagedTensor {
+    pub dl_tensor: DLTensor,
+    pub manager_ctx: *mut c_void,
+    pub deleter: Option<unsafe extern "C" fn(*mut DLManagedTensor)>,
+}
+
+/// Rust types with a DLPack [DLDataType].
+pub trait DLPackType {
+    const DTYPE: DLDataType;
+}
+
+macro_rules! dlpack_type {
+    ($RustTy:ty, $Code:ident, $Bits:expr) => {
+        impl DLPackType for $RustTy {
+            const DTYPE: DLDataType = DLDataType::new(DLDataType::$Code, $Bits);
+        }
+    };
+}
+
+dlpack_type!(bool, BOOL, 8);
+dlpack_type!(i8, INT, 8);
+dlpack_type!(i16, INT, 16);
+dlpack_type!(i32, INT, 32);
+dlpack_type!(i64, INT, 64);
+dlpack_type!(u8, UINT, 8);
+dlpack_type!(u16, UINT, 16);
+dlpack_type!(u32, UINT, 32);
+dlpack_type!(u64, UINT, 64);
+dlpack_type!(f32, FLOAT, 32);
+dlpack_type!(f64, FLOAT, 64);
+#[cfg(feature = "f16")]
+dlpack_type!(half::f16, FLOAT, 16);
+#[cfg(feature = "f16")]
+dlpack_type!(half::bf16, BFLOAT, 16);
+
+/// Errors of converting to or from a [DLManagedTensor].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DLPackError {
+    Driver(DriverError),
+    /// The tensor is not in device memory of the importing context.
+    WrongDevice {
+        expected: DLDevice,
+        found: DLDevice,
+    },
+    /// The tensor's element type is not the one requested.
+    DTypeMismatch {
+        expected: DLDataType,
+        found: DLDataType,
+    },
+    /// The tensor's shape has a negative dimension, or a different number of dimensions
+    /// than strides.
+    InvalidShape,
+    /// An imported tensor is not compact row major, so it can't be viewed as a slice.
+    NotContiguous,
+    /// An imported tensor's data pointer is not aligned for the element type.
+    Misaligned,
+    /// An exported shape and strides reach past the end of the slice.
+    OutOfBounds {
+        required: usize,
+        len: usize,
+    },
+}
+
+impl From<DriverError> for DLPackError {
+    fn from(value: DriverError) -> Self {
+        Self::Driver(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for DLPackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DLPackError {}
+
+impl DLPackError {
+    /// The [crate::ErrorKind] of this error.
+    pub fn kind(&self) -> crate::ErrorKind {
+        match self {
+            Self::Driver(e) => e.kind(),
+            _ => crate::ErrorKind::InvalidValue,
+        }
+    }
+}
+
+/// Error of [CudaSlice::to_dlpack()], which hands the slice back.
+#[derive(Debug)]
+pub struct DLPackExportError<T> {
+    pub error: DLPackError,
+    pub slice: CudaSlice<T>,
+}
+
+impl<T> From<DLPackExportError<T>> for DLPackError {
+    fn from(value: DLPackExportError<T>) -> Self {
+        value.error
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::fmt::Display for DLPackExportError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::fmt::Debug> std::error::Error for DLPackExportError<T> {}
+
+/// The row major strides of a compact tensor of `shape`, or `None` on overflow.
+pub(crate) fn compact_strides(shape: &[usize]) -> Option<Vec<usize>> {
+    let mut strides = std::vec![1usize; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1].checked_mul(shape[i + 1])?;
+    }
+    Some(strides)
+}
+
+/// The number of elements a tensor of `shape` and `strides` spans, i.e. one more than its
+/// largest offset. 0 if any dimension is 0, `None` on overflow.
+pub(crate) fn required_len(shape: &[usize], strides: &[usize]) -> Option<usize> {
+    if shape.contains(&0) {
+        return Some(0);
+    }
+    shape
+        .iter()
+        .zip(strides.iter())
+        .try_fold(1usize, |acc, (&dim, &stride)| {
+            acc.checked_add((dim - 1).checked_mul(stride)?)
+        })
+}
+
+/// Checks that a tensor of `shape` and `strides` (compact row major if `None`) fits in
+/// `len` elements, returning the strides.
+fn export_strides(
+    len: usize,
+    shape: &[usize],
+    strides: Option<&[usize]>,
+) -> Result<Vec<usize>, DLPackError> {
+    let strides = match strides {
+        Some(strides) => {
+            if strides.len() != shape.len() {
+                return Err(DLPackError::InvalidShape);
+            }
+            strides.to_vec()
+        }
+        None => compact_strides(shape).ok_or(DLPackError::InvalidShape)?,
+    };
+    let required = required_len(shape, &strides).ok_or(DLPackError::InvalidShape)?;
+    if required > len {
+        return Err(DLPackError::OutOfBounds { required, len });
+    }
+    Ok(strides)
+}
+
+/// Checks that `tensor` is a compact row major tensor of `dtype` on `device`, returning the
+/// number of elements.
+///
+/// # Safety
+/// `tensor.shape` and `tensor.strides` (unless null) must point to `tensor.ndim` elements.
+pub(crate) unsafe fn validate(
+    tensor: &DLTensor,
+    device: DLDevice,
+    dtype: DLDataType,
+    align: usize,
+) -> Result<usize, DLPackError> {
+    let found = tensor.device;
+    let device_matches = found.device_id == device.device_id
+        && (found.device_type == device.device_type
+            || found.device_type == DLDeviceType::CUDA_MANAGED);
+    if !device_matches {
+        return Err(DLPackError::WrongDevice {
+            expected: device,
+            found,
+        });
+    }
+    if tensor.dtype != dtype {
+        return Err(DLPackError::DTypeMismatch {
+            expected: dtype,
+            found: tensor.dtype,
+        });
+    }
+    if tensor.ndim < 0 || (tensor.ndim > 0 && tensor.shape.is_null()) {
+        return Err(DLPackError::InvalidShape);
+    }
+    let ndim = tensor.ndim as usize;
+    let shape = if ndim == 0 {
+        &[]
+    } else {
+        core::slice::from_raw_parts(tensor.shape, ndim)
+    };
+    if shape.iter().any(|&d| d < 0) {
+        return Err(DLPackError::InvalidShape);
+    }
+    let shape: Vec<usize> = shape.iter().map(|&d| d as usize).collect();
+    let len = shape
+        .iter()
+        .try_fold(1usize, |acc, &d| acc.checked_mul(d))
+        .ok_or(DLPackError::InvalidShape)?;
+    if !tensor.strides.is_null() && ndim > 0 && len > 0 {
+        let strides = core::slice::from_raw_parts(tensor.strides, ndim);
+        let expected = compact_strides(&shape).ok_or(DLPackError::InvalidShape)?;
+        // Strides of size 1 dimensions are never used, so any value is fine.
+        let compact = shape
+            .iter()
+            .zip(strides.iter().zip(expected.iter()))
+            .all(|(&dim, (&stride, &expected))| dim == 1 || stride == expected as i64);
+        if !compact {
+            return Err(DLPackError::NotContiguous);
+        }
+    }
+    let addr = tensor.data as usize + tensor.byte_offset as usize;
+    if !addr.is_multiple_of(align) {
+        return Err(DLPackError::Misaligned);
+    }
+    Ok(len)
+}
+
+/// What the `manager_ctx` of an exported tensor points to. `tensor` is first so the
+/// [DLManagedTensor] pointer handed out is also the pointer to this.
+#[repr(C)]
+struct Exported<O> {
+    tensor: DLManagedTensor,
+    shape: Vec<i64>,
+    strides: Vec<i64>,
+    owner: O,
+}
+
+/// Keeps exported device memory (and the stream it is freed on) alive.
+trait ExportOwner {
+    fn stream(&self) -> &Arc<CudaStream>;
+}
+
+impl<T> ExportOwner for CudaSlice<T> {
+    fn stream(&self) -> &Arc<CudaStream> {
+        &self.stream
+    }
+}
+
+impl ExportOwner for Arc<CudaStream> {
+    fn stream(&self) -> &Arc<CudaStream> {
+        self
+    }
+}
+
+unsafe extern "C" fn deleter<O: ExportOwner>(tensor: *mut DLManagedTensor) {
+    if tensor.is_null() {
+        return;
+    }
+    let exported = Box::from_raw((*tensor).manager_ctx as *mut Exported<O>);
+    // The deleter may run on any thread, e.g. python's garbage collector.
+    let ctx = exported.owner.stream().context().clone();
+    ctx.record_err(ctx.bind_to_thread());
+    drop(exported);
+}
+
+/// Builds the exported tensor, with `strides` from [export_strides()].
+fn export<O: ExportOwner, T: DLPackType>(
+    owner: O,
+    ptr: sys::CUdeviceptr,
+    shape: &[usize],
+    strides: Vec<usize>,
+) -> NonNull<DLManagedTensor> {
+    let stream = owner.stream();
+    let device = DLDevice {
+        device_type: DLDeviceType::CUDA,
+        device_id: stream.context().ordinal() as i32,
+    };
+    let mut exported = Box::new(Exported {
+        tensor: DLManagedTensor {
+            dl_tensor: DLTensor {
+                data: ptr as *mut c_void,
+                device,
+                ndim: shape.len() as i32,
+                dtype: T::DTYPE,
+                shape: core::ptr::null_mut(),
+                strides: core::ptr::null_mut(),
+                byte_offset: 0,
+            },
+            manager_ctx: core::ptr::null_mut(),
+            deleter: Some(deleter::<O>),
+        },
+        shape: shape.iter().map(|&d| d as i64).collect(),
+        strides: strides.iter().map(|&s| s as i64).collect(),
+        owner,
+    });
+    exported.tensor.dl_tensor.shape = exported.shape.as_mut_ptr();
+    exported.tensor.dl_tensor.strides = exported.strides.as_mut_ptr();
+    let raw = Box::into_raw(exported);
+    unsafe {
+        (*raw).tensor.manager_ctx = raw as *mut c_void;
+        NonNull::new_unchecked(raw as *mut DLManagedTensor)
+    }
+}
+
+/// Orders all earlier uses of `read`/`write` before `stream`, which the consumer syncs with.
+fn wait_for_uses(
+    stream: &CudaStream,
+    read: &Option<CudaEvent>,
+    write: &Option<CudaEvent>,
+) -> Result<(), DriverError> {
+    stream.context().bind_to_thread()?;
+    if let Some(read) = read.as_ref() {
+        stream.wait(read)?;
+    }
+    if let Some(write) = write.as_ref() {
+        stream.wait(write)?;
+    }
+    Ok(())
+}
+
+impl<T: DLPackType> CudaSlice<T> {
+    /// Exports this slice as a [DLManagedTensor] of `shape`, without copying. `strides` are
+    /// in elements, and default to compact row major. The tensor's deleter drops the slice, so
+    /// the memory and stream stay alive until the consumer is done with it.
+    ///
+    /// The data is ready once all work on [CudaSlice::stream()] is done. Consumers on other
+    /// streams must order their work after it, e.g. by synchronizing, or through the
+    /// `stream` argument of python's `__dlpack__`.
+    ///
+    /// Returns [DLPackError::OutOfBounds] if `shape` and `strides` reach past the end of this
+    /// slice. On failure the slice is handed back in the [DLPackExportError].
+    ///
+    /// Example with PyO3, where the capsule is consumed by e.g. `torch.from_dlpack()`:
+    /// ```ignore
+    /// let tensor = slice.to_dlpack(&[rows, cols], None)?;
+    /// let capsule = unsafe {
+    ///     pyo3::ffi::PyCapsule_New(tensor.as_ptr().cast(), c"dltensor".as_ptr(), None)
+    /// };
+    /// ```
+    pub fn to_dlpack(
+        self,
+        shape: &[usize],
+        strides: Option<&[usize]>,
+    ) -> Result<NonNull<DLManagedTensor>, DLPackExportError<T>> {
+        // Everything that can fail happens before the slice is moved into the tensor.
+        let checked = export_strides(self.len, shape, strides).and_then(|strides| {
+            wait_for_uses(&self.stream, &self.read, &self.write)?;
+            Ok(strides)
+        });
+        match checked {
+            Ok(strides) => {
+                let ptr = self.cu_device_ptr;
+                Ok(export::<_, T>(self, ptr, shape, strides))
+            }
+            Err(error) => Err(DLPackExportError { error, slice: self }),
+        }
+    }
+}
+
+impl<T: DLPackType> CudaView<'_, T> {
+    /// Exports this view as a [DLManagedTensor]. See [CudaSlice::to_dlpack()].
+    ///
+    /// # Safety
+    /// The tensor only keeps the stream alive, not the memory. The slice this view is of
+    /// must outlive every use of the tensor, and must not be written to while the tensor
+    /// is in use.
+    pub unsafe fn to_dlpack(
+        &self,
+        shape: &[usize],
+        strides: Option<&[usize]>,
+    ) -> Result<NonNull<DLManagedTensor>, DLPackError> {
+        let strides = export_strides(self.len, shape, strides)?;
+        wait_for_uses(self.stream, self.read, self.write)?;
+        Ok(export::<_, T>(
+            self.stream.clone(),
+            self.ptr,
+            shape,
+            strides,
+        ))
+    }
+}
+
+/// A device tensor imported with [CudaContext::from_dlpack()] or
+/// [CudaContext::from_dlpack_borrowed()], viewed as a flat slice of `T`.
+///
+/// If owned, the producer's deleter is called on drop. Work is ordered on the context's
+/// default stream; the producer's own work on the tensor must be complete (or ordered before
+/// that stream) before using it.
+#[derive(Debug)]
+pub struct DLPackTensor<'a, T> {
+    managed: Option<NonNull<DLManagedTensor>>,
+    tensor: *const DLTensor,
+    ptr: sys::CUdeviceptr,
+    len: usize,
+    stream: Arc<CudaStream>,
+    marker: PhantomData<(&'a DLTensor, *const T)>,
+}
+
+unsafe impl<T> Send for DLPackTensor<'_, T> {}
+unsafe impl<T> Sync for DLPackTensor<'_, T> {}
+
+impl<T> Drop for DLPackTensor<'_, T> {
+    fn drop(&mut self) {
+        if let Some(managed) = self.managed.take() {
+            let managed = managed.as_ptr();
+            if let Some(deleter) = unsafe { (*managed).deleter } {
+                unsafe { deleter(managed) };
+            }
+        }
+    }
+}
+
+impl<T> DLPackTensor<'_, T> {
+    /// The number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The underlying [DLTensor].
+    pub fn dl_tensor(&self) -> &DLTensor {
+        unsafe { &*self.tensor }
+    }
+
+    /// The shape of the tensor.
+    pub fn shape(&self) -> &[i64] {
+        let tensor = self.dl_tensor();
+        if tensor.ndim == 0 {
+            return &[];
+        }
+        unsafe { core::slice::from_raw_parts(tensor.shape, tensor.ndim as usize) }
+    }
+
+    /// The stream views of this tensor use.
+    pub fn stream(&self) -> &Arc<CudaStream> {
+        &self.stream
+    }
+
+    pub fn as_view(&self) -> CudaView<'_, T> {
+        CudaView {
+            ptr: self.ptr,
+            len: self.len,
+            read: &None::<CudaEvent>,
+            write: &None::<CudaEvent>,
+            stream: &self.stream,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn as_view_mut(&mut self) -> CudaViewMut<'_, T> {
+        CudaViewMut {
+            ptr: self.ptr,
+            len: self.len,
+            read: &None::<CudaEvent>,
+            write: &None::<CudaEvent>,
+            stream: &self.stream,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl CudaContext {
+    /// Takes ownership of a [DLManagedTensor] produced elsewhere (e.g. the pointer in a
+    /// `"dltensor"` PyCapsule), viewing it as a flat slice of `T`. The tensor's deleter is
+    /// called when the returned [DLPackTensor] is dropped.
+    ///
+    /// The tensor must be compact row major, of element type `T`, and in memory of this
+    /// context's device. On error the tensor is not consumed and its deleter is not called.
+    ///
+    /// # Safety
+    /// `tensor` must point to a valid [DLManagedTensor] that nothing else will delete.
+    pub unsafe fn from_dlpack<T: DLPackType>(
+        self: &Arc<Self>,
+        tensor: NonNull<DLManagedTensor>,
+    ) -> Result<DLPackTensor<'static, T>, DLPackError> {
+        let dl_tensor = core::ptr::addr_of!((*tensor.as_ptr()).dl_tensor);
+        let mut imported = self.import_dlpack(&*dl_tensor)?;
+        imported.managed = Some(tensor);
+        Ok(imported)
+    }
+
+    /// Borrows a [DLTensor] as a flat slice of `T`, without taking ownership.
+    /// See [CudaContext::from_dlpack()].
+    ///
+    /// # Safety
+    /// `tensor` must describe valid device memory, which must outlive the returned value.
+    pub unsafe fn from_dlpack_borrowed<'a, T: DLPackType>(
+        self: &Arc<Self>,
+        tensor: &'a DLTensor,
+    ) -> Result<DLPackTensor<'a, T>, DLPackError> {
+        self.import_dlpack(tensor)
+    }
+
+    unsafe fn import_dlpack<'a, T: DLPackType>(
+        self: &Arc<Self>,
+        tensor: &'a DLTensor,
+    ) -> Result<DLPackTensor<'a, T>, DLPackError> {
+        let device = DLDevice {
+            device_type: DLDeviceType::CUDA,
+            device_id: self.ordinal() as i32,
+        };
+        let len = validate(tensor, device, T::DTYPE, core::mem::align_of::<T>())?;
+        Ok(DLPackTensor {
+            managed: None,
+            tensor,
+            ptr: tensor.data as sys::CUdeviceptr + tensor.byte_offset,
+            len,
+            stream: self.default_stream(),
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cuda(device_id: i32) -> DLDevice {
+        DLDevice {
+            device_type: DLDeviceType::CUDA,
+            device_id,
+        }
+    }
+
+    fn host_tensor(shape: &mut [i64], strides: Option<&mut [i64]>) -> DLTensor {
+        DLTensor {
+            data: 256 as *mut c_void,
+            device: cuda(0),
+            ndim: shape.len() as i32,
+            dtype: f32::DTYPE,
+            shape: shape.as_mut_ptr(),
+            strides: strides.map_or(core::ptr::null_mut(), |s| s.as_mut_ptr()),
+            byte_offset: 8,
+        }
+    }
+
+    #[test]
+    fn test_layout_of_structs() {
+        assert_eq!(core::mem::size_of::<DLDevice>(), 8);
+        assert_eq!(core::mem::size_of::<DLDataType>(), 4);
+        assert_eq!(core::mem::size_of::<DLTensor>(), 48);
+        assert_eq!(core::mem::size_of::<DLManagedTensor>(), 64);
+    }
+
+    #[test]
+    fn test_dtypes() {
+        assert_eq!(f32::DTYPE, DLDataType::new(2, 32));
+        assert_eq!(i64::DTYPE, DLDataType::new(0, 64));
+        assert_eq!(u8::DTYPE, DLDataType::new(1, 8));
+        assert_eq!(bool::DTYPE, DLDataType::new(6, 8));
+        #[cfg(feature = "f16")]
+        assert_eq!(half::bf16::DTYPE, DLDataType::new(4, 16));
+    }
+
+    #[test]
+    fn test_strides() {
+        assert_eq!(compact_strides(&[2, 3, 4]).unwrap(), [12, 4, 1]);
+        assert_eq!(compact_strides(&[]).unwrap(), [0usize; 0]);
+        assert_eq!(compact_strides(&[0, usize::MAX, 2]), None);
+        assert_eq!(required_len(&[2, 3, 4], &[12, 4, 1]), Some(24));
+        assert_eq!(required_len(&[2, 3], &[1, 2]), Some(6));
+        assert_eq!(required_len(&[3, 2], &[8, 1]), Some(18));
+        assert_eq!(required_len(&[3, 0], &[1, 1]), Some(0));
+        assert_eq!(required_len(&[], &[]), Some(1));
+        assert_eq!(required_len(&[3, 2], &[usize::MAX, 1]), None);
+
+        assert_eq!(export_strides(6, &[2, 3], None), Ok(std::vec![3, 1]));
+        assert_eq!(
+            export_strides(4, &[2, 3], Some(&[1, 2])),
+            Err(DLPackError::OutOfBounds {
+                required: 6,
+                len: 4
+            })
+        );
+        assert_eq!(
+            export_strides(4, &[2], Some(&[1, 2])),
+            Err(DLPackError::InvalidShape)
+        );
+        assert_eq!(
+            export_strides(4, &[1 << 40, 1 << 40], None),
+            Err(DLPackError::InvalidShape)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut shape = [2, 3];
+        let tensor = host_tensor(&mut shape, None);
+        assert_eq!(unsafe { validate(&tensor, cuda(0), f32::DTYPE, 4) }, Ok(6));
+        assert_eq!(
+            unsafe { validate(&tensor, cuda(1), f32::DTYPE, 4) },
+            Err(DLPackError::WrongDevice {
+                expected: cuda(1),
+                found: cuda(0)
+            })
+        );
+        assert_eq!(
+            unsafe { validate(&tensor, cuda(0), f64::DTYPE, 8) },
+            Err(DLPackError::DTypeMismatch {
+                expected: f64::DTYPE,
+                found: f32::DTYPE
+            })
+        );
+        assert_eq!(
+            unsafe { validate(&tensor, cuda(0), f32::DTYPE, 16) },
+            Err(DLPackError::Misaligned)
+        );
+
+        let mut strides = [3, 1];
+        let tensor = host_tensor(&mut shape, Some(&mut strides));
+        assert_eq!(unsafe { validate(&tensor, cuda(0), f32::DTYPE, 4) }, Ok(6));
+
+        let mut strides = [1, 2];
+        let tensor = host_tensor(&mut shape, Some(&mut strides));
+        assert_eq!(
+            unsafe { validate(&tensor, cuda(0), f32::DTYPE, 4) },
+            Err(DLPackError::NotContiguous)
+        );
+
+        // Strides of unit dimensions don't matter.
+        let mut shape = [1, 4];
+        let mut strides = [99, 1];
+        let tensor = host_tensor(&mut shape, Some(&mut strides));
+        assert_eq!(unsafe { validate(&tensor, cuda(0), f32::DTYPE, 4) }, Ok(4));
+
+        let mut shape = [-1, 4];
+        let tensor = host_tensor(&mut shape, None);
+        assert_eq!(
+            unsafe { validate(&tensor, cuda(0), f32::DTYPE, 4) },
+            Err(DLPackError::InvalidShape)
+        );
+    }
+
+    #[test]
+    fn test_dlpack_round_trip() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+        let slice = stream
+            .clone_htod(&[1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0])
+            .unwrap();
+        let err = stream
+            .clone_htod(&[0.0f32; 4])
+            .unwrap()
+            .to_dlpack(&[2, 3], None)
+            .unwrap_err();
+        assert_eq!(
+            err.error,
+            DLPackError::OutOfBounds {
+                required: 6,
+                len: 4
+            }
+        );
+        // The slice is handed back.
+        assert_eq!(stream.clone_dtoh(&err.slice).unwrap(), [0.0; 4]);
+
+        let tensor = slice.to_dlpack(&[2, 3], None).unwrap();
+        let dl = unsafe { &tensor.as_ref().dl_tensor };
+        assert_eq!(dl.ndim, 2);
+        assert_eq!(dl.device, cuda(0));
+        assert_eq!(unsafe { core::slice::from_raw_parts(dl.shape, 2) }, [2, 3]);
+        assert_eq!(
+            unsafe { core::slice::from_raw_parts(dl.strides, 2) },
+            [3, 1]
+        );
+
+        assert!(matches!(
+            unsafe { ctx.from_dlpack::<u32>(tensor) },
+            Err(DLPackError::DTypeMismatch { .. })
+        ));
+        let imported = unsafe { ctx.from_dlpack::<f32>(tensor) }.unwrap();
+        assert_eq!(imported.shape(), [2, 3]);
+        assert_eq!(
+            stream.clone_dtoh(&imported.as_view()).unwrap(),
+            [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+        drop(imported);
+        stream.synchronize().unwrap();
+    }
+}
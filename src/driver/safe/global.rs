@@ -0,0 +1,396 @@
+use std::{ffi::CString, marker::PhantomData, string::String, sync::Arc};
+
+use crate::driver::{result, sys, DriverError};
+
+use super::{
+    CudaEvent, CudaModule, CudaStream, CudaView, CudaViewMut, DevicePtr, DevicePtrMut, DeviceRepr,
+    DeviceSlice, SyncOnDrop,
+};
+
+/// A typed `__device__` or `__constant__` variable of a loaded [CudaModule]. Create with
+/// [CudaModule::global()].
+///
+/// Holds `len` elements of `T` - `len` is 1 when `T` matches the symbol exactly, or the
+/// number of elements when `T` is the element type of an array symbol.
+///
+/// Like [super::CudaSlice], this can be used with [CudaStream::memcpy_htod()]/[CudaStream::memcpy_dtoh()],
+/// viewed with [CudaGlobal::as_view()], and passed to kernels as a pointer. Uses are tracked with
+/// [CudaEvent]s in the same way, and every [CudaGlobal] of the same symbol shares those events,
+/// so uses through different handles are ordered against each other too.
+///
+/// The module stays loaded for as long as this exists.
+#[derive(Debug)]
+pub struct CudaGlobal<T> {
+    pub(crate) cu_device_ptr: sys::CUdeviceptr,
+    pub(crate) len: usize,
+    pub(crate) events: Arc<GlobalEvents>,
+    pub(crate) stream: Arc<CudaStream>,
+    pub(crate) module: Arc<CudaModule>,
+    marker: PhantomData<*const T>,
+}
+
+/// The read/write events of a symbol, shared by all of its [CudaGlobal]s. See [CudaModule::global()].
+#[derive(Debug)]
+pub(crate) struct GlobalEvents {
+    pub(crate) read: Option<CudaEvent>,
+    pub(crate) write: Option<CudaEvent>,
+}
+
+unsafe impl<T> Send for CudaGlobal<T> {}
+unsafe impl<T> Sync for CudaGlobal<T> {}
+
+/// Error returned by [CudaModule::global()], [CudaGlobal::read()] and [CudaGlobal::write()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlobalError {
+    /// Looking up the symbol failed, e.g. because it doesn't exist.
+    Driver(DriverError),
+    /// The symbol's size is not a (non-zero) multiple of the size of the requested type.
+    SizeMismatch {
+        name: String,
+        symbol_bytes: usize,
+        type_bytes: usize,
+    },
+    /// [CudaGlobal::read()]/[CudaGlobal::write()] on a symbol that holds `len` elements.
+    NotSingleElement { len: usize },
+}
+
+impl From<DriverError> for GlobalError {
+    fn from(value: DriverError) -> Self {
+        Self::Driver(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for GlobalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GlobalError {}
+
+impl GlobalError {
+    /// The [crate::ErrorKind] of this error.
+    pub fn kind(&self) -> crate::ErrorKind {
+        match self {
+            Self::Driver(e) => e.kind(),
+            Self::SizeMismatch { .. } | Self::NotSingleElement { .. } => {
+                crate::ErrorKind::InvalidValue
+            }
+        }
+    }
+}
+
+impl CudaModule {
+    /// Gets a typed `__device__`/`__constant__` symbol from the loaded module.
+    ///
+    /// The symbol's size must be `size_of::<T>()`, or a multiple of it for array symbols,
+    /// otherwise [GlobalError::SizeMismatch] is returned.
+    ///
+    /// Calling this again for the same symbol, with any `T`, returns a handle that shares its
+    /// read/write events with the handles that are still alive.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // In CUDA: __constant__ float coefficients[4];
+    /// let mut coefficients = module.global::<[f32; 4]>("coefficients")?;
+    /// coefficients.write(&stream, &[1.0, 2.0, 3.0, 4.0])?;
+    ///
+    /// // Or as 4 separate f32s
+    /// let coefficients = module.global::<f32>("coefficients")?;
+    /// assert_eq!(coefficients.len(), 4);
+    /// ```
+    pub fn global<T: DeviceRepr>(
+        self: &Arc<Self>,
+        name: &str,
+    ) -> Result<CudaGlobal<T>, GlobalError> {
+        self.ctx.bind_to_thread()?;
+        let name_c =
+            CString::new(name).map_err(|_| DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE))?;
+        let (cu_device_ptr, symbol_bytes) =
+            unsafe { result::module::get_global(self.cu_module, name_c) }?;
+        let type_bytes = std::mem::size_of::<T>();
+        if type_bytes == 0 || symbol_bytes == 0 || !symbol_bytes.is_multiple_of(type_bytes) {
+            return Err(GlobalError::SizeMismatch {
+                name: name.into(),
+                symbol_bytes,
+                type_bytes,
+            });
+        }
+        let events = {
+            let mut globals = self.globals.lock().unwrap_or_else(|e| e.into_inner());
+            match globals
+                .get(&cu_device_ptr)
+                .and_then(std::sync::Weak::upgrade)
+            {
+                Some(events) => events,
+                None => {
+                    let events = Arc::new(if self.ctx.is_event_tracking() {
+                        GlobalEvents {
+                            read: Some(self.ctx.new_event(None)?),
+                            write: Some(self.ctx.new_event(None)?),
+                        }
+                    } else {
+                        GlobalEvents {
+                            read: None,
+                            write: None,
+                        }
+                    });
+                    globals.retain(|_, events| events.strong_count() > 0);
+                    globals.insert(cu_device_ptr, Arc::downgrade(&events));
+                    events
+                }
+            }
+        };
+        Ok(CudaGlobal {
+            cu_device_ptr,
+            len: symbol_bytes / type_bytes,
+            events,
+            stream: self.ctx.default_stream(),
+            module: self.clone(),
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<T> CudaGlobal<T> {
+    /// The number of elements of `T` in the symbol.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The size of the symbol in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.len * std::mem::size_of::<T>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The module this symbol belongs to.
+    pub fn module(&self) -> &Arc<CudaModule> {
+        &self.module
+    }
+
+    /// The underlying device pointer of the symbol.
+    ///
+    /// # Safety
+    /// While this function is marked as safe, actually using the
+    /// returned object is unsafe.
+    pub fn cu_device_ptr(&self) -> sys::CUdeviceptr {
+        self.cu_device_ptr
+    }
+
+    pub fn as_view(&self) -> CudaView<'_, T> {
+        CudaView {
+            ptr: self.cu_device_ptr,
+            len: self.len,
+            read: &self.events.read,
+            write: &self.events.write,
+            stream: &self.stream,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn as_view_mut(&mut self) -> CudaViewMut<'_, T> {
+        CudaViewMut {
+            ptr: self.cu_device_ptr,
+            len: self.len,
+            read: &self.events.read,
+            write: &self.events.write,
+            stream: &self.stream,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: DeviceRepr> CudaGlobal<T> {
+    /// Schedules a copy of `value` into the symbol on `stream`.
+    ///
+    /// Fails with [GlobalError::NotSingleElement] if the symbol holds more than one `T`, use
+    /// [CudaGlobal::as_view_mut()] with [CudaStream::memcpy_htod()] for those.
+    pub fn write(&mut self, stream: &Arc<CudaStream>, value: &T) -> Result<(), GlobalError> {
+        self.check_single_element()?;
+        stream.memcpy_htod(std::slice::from_ref(value), self)?;
+        Ok(())
+    }
+
+    /// Copies the value of the symbol to the host, after waiting for `stream` to finish.
+    ///
+    /// Fails with [GlobalError::NotSingleElement] if the symbol holds more than one `T`, use
+    /// [CudaStream::clone_dtoh()] for those.
+    pub fn read(&self, stream: &Arc<CudaStream>) -> Result<T, GlobalError> {
+        self.check_single_element()?;
+        let mut value = std::mem::MaybeUninit::<T>::uninit();
+        stream.ctx.bind_to_thread()?;
+        {
+            let (src, _record_src) = self.device_ptr(stream);
+            stream.ctx.check_err()?;
+            unsafe {
+                result::memcpy_dtoh_async(std::slice::from_mut(&mut value), src, stream.cu_stream)
+            }?;
+        }
+        stream.synchronize()?;
+        Ok(unsafe { value.assume_init() })
+    }
+
+    fn check_single_element(&self) -> Result<(), GlobalError> {
+        if self.len == 1 {
+            Ok(())
+        } else {
+            Err(GlobalError::NotSingleElement { len: self.len })
+        }
+    }
+}
+
+impl<T> DeviceSlice<T> for CudaGlobal<T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn stream(&self) -> &Arc<CudaStream> {
+        &self.stream
+    }
+}
+
+impl<T> DevicePtr<T> for CudaGlobal<T> {
+    fn device_ptr<'a>(&'a self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
+        if self.stream.context().is_managing_stream_synchronization() {
+            if let Some(write) = self.events.write.as_ref() {
+                stream.ctx.record_err(stream.wait(write));
+            }
+        }
+        (
+            self.cu_device_ptr,
+            SyncOnDrop::record_event(&self.events.read, stream),
+        )
+    }
+}
+
+impl<T> DevicePtrMut<T> for CudaGlobal<T> {
+    fn device_ptr_mut<'a>(
+        &'a mut self,
+        stream: &'a CudaStream,
+    ) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (0, SyncOnDrop::Record(None));
+        }
+        if self.stream.context().is_managing_stream_synchronization() {
+            if let Some(read) = self.events.read.as_ref() {
+                stream.ctx.record_err(stream.wait(read));
+            }
+            if let Some(write) = self.events.write.as_ref() {
+                stream.ctx.record_err(stream.wait(write));
+            }
+        }
+        (
+            self.cu_device_ptr,
+            SyncOnDrop::record_event(&self.events.write, stream),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        driver::{CudaContext, LaunchConfig, PushKernelArg},
+        nvrtc::compile_ptx,
+    };
+
+    use super::*;
+
+    const SRC: &str = r#"
+__constant__ float coefficients[4];
+__device__ unsigned int counter;
+
+extern "C" __global__ void apply(float *out, unsigned int *count) {
+    out[threadIdx.x] = coefficients[threadIdx.x] * 2.0f;
+    atomicAdd(&counter, 1);
+    *count = 7;
+}
+"#;
+
+    #[test]
+    fn test_global_read_write() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+        let module = ctx.load_module(compile_ptx(SRC).unwrap()).unwrap();
+        let f = module.load_function("apply").unwrap();
+
+        let mut coefficients = module.global::<[f32; 4]>("coefficients").unwrap();
+        assert_eq!(coefficients.len(), 1);
+        coefficients.write(&stream, &[1.0, 2.0, 3.0, 4.0]).unwrap();
+
+        let mut counter = module.global::<u32>("counter").unwrap();
+        counter.write(&stream, &0).unwrap();
+
+        let mut out = stream.alloc_zeros::<f32>(4).unwrap();
+        let mut count = module.global::<u32>("counter").unwrap();
+        assert!(Arc::ptr_eq(&counter.events, &count.events));
+        assert!(!Arc::ptr_eq(&counter.events, &coefficients.events));
+        let cfg = LaunchConfig {
+            grid_dim: (1, 1, 1),
+            block_dim: (4, 1, 1),
+            shared_mem_bytes: 0,
+        };
+        unsafe {
+            stream
+                .launch_builder(&f)
+                .arg(&mut out)
+                .arg(&mut count)
+                .launch(cfg)
+        }
+        .unwrap();
+
+        assert_eq!(stream.clone_dtoh(&out).unwrap(), [2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(count.read(&stream).unwrap(), 7);
+        assert_eq!(coefficients.read(&stream).unwrap(), [1.0, 2.0, 3.0, 4.0]);
+
+        let mut elements = module.global::<f32>("coefficients").unwrap();
+        assert_eq!(elements.len(), 4);
+        assert_eq!(
+            elements.read(&stream).unwrap_err(),
+            GlobalError::NotSingleElement { len: 4 }
+        );
+        assert_eq!(
+            elements.write(&stream, &0.0).unwrap_err(),
+            GlobalError::NotSingleElement { len: 4 }
+        );
+        assert_eq!(
+            stream.clone_dtoh(&elements.as_view()).unwrap(),
+            [1.0, 2.0, 3.0, 4.0]
+        );
+    }
+
+    #[test]
+    fn test_global_size_mismatch() {
+        let ctx = CudaContext::new(0).unwrap();
+        let module = ctx.load_module(compile_ptx(SRC).unwrap()).unwrap();
+
+        assert_eq!(
+            module.global::<[f32; 3]>("coefficients").unwrap_err(),
+            GlobalError::SizeMismatch {
+                name: "coefficients".into(),
+                symbol_bytes: 16,
+                type_bytes: 12,
+            }
+        );
+        assert_eq!(
+            module.global::<u64>("counter").unwrap_err(),
+            GlobalError::SizeMismatch {
+                name: "counter".into(),
+                symbol_bytes: 4,
+                type_bytes: 8,
+            }
+        );
+        assert!(matches!(
+            module.global::<u32>("missing"),
+            Err(GlobalError::Driver(_))
+        ));
+    }
+}
@@ -1,10 +1,14 @@
-use std::sync::Arc;
+use std::{marker::PhantomData, sync::Arc, vec::Vec};
 
 use crate::driver::{result, sys};
 
-use super::{CudaStream, DriverError};
+use super::{
+    CudaContext, CudaStream, DevicePtr, DevicePtrMut, DeviceRepr, DeviceSlice, DriverError,
+    SyncOnDrop, ValidAsZeroBits,
+};
 
-/// Represents a replay-able Cuda Graph. Create with [CudaStream::begin_capture()] and [CudaStream::end_capture()].
+/// Represents a replay-able Cuda Graph. Create with [CudaStream::capture()], or with
+/// [CudaStream::begin_capture()] and [CudaStream::end_capture()].
 ///
 /// Once created you can replay with [CudaGraph::launch()].
 ///
@@ -19,15 +23,20 @@ use super::{CudaStream, DriverError};
 /// > Note that this includes APIs which may appear to be read-only, such as cudaGraphClone() (cuGraphClone()) and cudaGraphInstantiate() (cuGraphInstantiate()). No API or pair of APIs is guaranteed to be safe to call on the same graph object from two different threads without serialization.
 ///
 /// <https://docs.nvidia.com/cuda/cuda-driver-api/graphs-thread-safety.html#graphs-thread-safety>
+#[derive(Debug)]
 pub struct CudaGraph {
-    cu_graph: sys::CUgraph,
-    cu_graph_exec: sys::CUgraphExec,
-    stream: Arc<CudaStream>,
+    pub(crate) cu_graph: sys::CUgraph,
+    pub(crate) cu_graph_exec: sys::CUgraphExec,
+    pub(crate) stream: Arc<CudaStream>,
 }
 
 impl Drop for CudaGraph {
     fn drop(&mut self) {
         let ctx = &self.stream.ctx;
+        // The reset already destroyed this.
+        if ctx.is_invalidated() {
+            return;
+        }
 
         let cu_graph_exec = std::mem::replace(&mut self.cu_graph_exec, std::ptr::null_mut());
         if !cu_graph_exec.is_null() {
@@ -73,6 +82,353 @@ impl CudaStream {
         self.ctx.bind_to_thread()?;
         unsafe { result::stream::is_capturing(self.cu_stream) }
     }
+
+    /// Details about the capture this stream is currently part of (if any). Useful for library
+    /// code that needs to avoid operations that are illegal during capture, like
+    /// synchronous allocations or [CudaStream::synchronize()].
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__STREAM.html)
+    pub fn capture_info(&self) -> Result<CaptureInfo, DriverError> {
+        self.ctx.bind_to_thread()?;
+        let (status, id, cu_graph, dependencies) =
+            unsafe { result::stream::get_capture_info(self.cu_stream) }?;
+        Ok(CaptureInfo {
+            status,
+            id,
+            cu_graph,
+            dependencies,
+        })
+    }
+
+    /// Begins capturing and returns a [CaptureGuard] that ends the capture when dropped,
+    /// even if the code in between panics or returns early.
+    ///
+    /// Use [CaptureGuard::end()] to get the captured [CudaGraph]. The same restrictions as
+    /// [CudaStream::capture()] apply.
+    pub fn capture_guard(
+        self: &Arc<Self>,
+        mode: sys::CUstreamCaptureMode,
+    ) -> Result<CaptureGuard<'_>, DriverError> {
+        self.begin_capture(mode)?;
+        // Ends the capture if getting its id fails.
+        let mut guard = CaptureGuard {
+            stream: self,
+            capture_id: 0,
+            ended: false,
+        };
+        guard.capture_id = self.capture_info()?.id;
+        Ok(guard)
+    }
+
+    /// Captures all work that `f` submits to this stream into a [CudaGraph].
+    ///
+    /// Capture is always ended, including when `f` returns an error or panics.
+    /// `flags` is passed to [cuGraphInstantiate](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html#group__CUDA__GRAPH_1gb53b435e178cccfa37ac87285d2c3fa1)
+    ///
+    /// # Event tracking
+    ///
+    /// While event tracking is enabled (the default, see
+    /// [CudaContext::disable_event_tracking()]), using a [super::CudaSlice] that was allocated
+    /// outside of the capture makes the captured stream wait on events recorded outside of
+    /// it, which fails and invalidates the capture. Disable event tracking before allocating
+    /// slices that are used in a capture.
+    ///
+    /// Copies from pageable host memory (e.g. a `Vec`) can't be captured either, use a
+    /// [super::PinnedHostSlice] allocated with event tracking disabled, or copy to the device
+    /// before capturing.
+    ///
+    /// Example:
+    /// ```ignore
+    /// unsafe { ctx.disable_event_tracking() };
+    /// let stream = ctx.new_stream()?;
+    /// let mut dev = stream.clone_htod(&host)?;
+    /// let graph = stream.capture(CU_STREAM_CAPTURE_MODE_THREAD_LOCAL, flags, |s| {
+    ///     unsafe { s.launch_builder(&f).arg(&mut dev).launch(cfg) }?;
+    ///     Ok(())
+    /// })?;
+    /// graph.launch()?;
+    /// ```
+    pub fn capture<F>(
+        self: &Arc<Self>,
+        mode: sys::CUstreamCaptureMode,
+        flags: sys::CUgraphInstantiate_flags,
+        f: F,
+    ) -> Result<CudaGraph, DriverError>
+    where
+        F: FnOnce(&Arc<CudaStream>) -> Result<(), DriverError>,
+    {
+        let guard = self.capture_guard(mode)?;
+        f(self)?;
+        guard.end(flags)?.ok_or(DriverError(
+            sys::CUresult::CUDA_ERROR_STREAM_CAPTURE_INVALIDATED,
+        ))
+    }
+}
+
+/// The result of [CudaStream::capture_info()].
+#[derive(Debug, Clone)]
+pub struct CaptureInfo {
+    pub status: sys::CUstreamCaptureStatus,
+    /// Unique id of the capture sequence. Only valid while [CaptureInfo::is_active()].
+    pub id: u64,
+    /// The graph being captured into. Owned by the capture, and only valid while
+    /// [CaptureInfo::is_active()] - **you must not destroy it**.
+    pub cu_graph: sys::CUgraph,
+    /// The nodes that the next operation captured on the stream will depend on.
+    pub dependencies: Vec<sys::CUgraphNode>,
+}
+
+impl CaptureInfo {
+    /// Whether the stream is currently being captured.
+    pub fn is_active(&self) -> bool {
+        self.status == sys::CUstreamCaptureStatus::CU_STREAM_CAPTURE_STATUS_ACTIVE
+    }
+}
+
+/// Ends a stream capture when dropped. Create with [CudaStream::capture_guard()].
+///
+/// If dropped without calling [CaptureGuard::end()], the partially captured graph is discarded.
+#[derive(Debug)]
+pub struct CaptureGuard<'a> {
+    stream: &'a Arc<CudaStream>,
+    capture_id: u64,
+    ended: bool,
+}
+
+impl CaptureGuard<'_> {
+    /// The stream being captured.
+    pub fn stream(&self) -> &Arc<CudaStream> {
+        self.stream
+    }
+
+    /// Ends the capture and instantiates the captured graph, see [CudaStream::end_capture()].
+    pub fn end(
+        mut self,
+        flags: sys::CUgraphInstantiate_flags,
+    ) -> Result<Option<CudaGraph>, DriverError> {
+        self.ended = true;
+        self.stream.end_capture(flags)
+    }
+}
+
+impl CaptureGuard<'_> {
+    /// Allocates `len` elements of `T` that are owned by the graph being captured. The
+    /// allocation becomes a memory allocation node in the graph, and dropping the [GraphSlice]
+    /// adds the matching free node, so the memory only exists while the graph is running.
+    ///
+    /// The [GraphSlice] borrows the guard, so it has to be dropped before [CaptureGuard::end()].
+    ///
+    /// Requires a device that supports stream ordered allocations, otherwise
+    /// [sys::CUresult::CUDA_ERROR_NOT_SUPPORTED] is returned.
+    ///
+    /// # Safety
+    /// This is unsafe because the memory is unset.
+    pub unsafe fn alloc<T: DeviceRepr>(
+        &self,
+        len: usize,
+    ) -> Result<GraphSlice<'_, T>, DriverError> {
+        let stream = self.stream;
+        if !stream.ctx.has_async_alloc {
+            return Err(DriverError(sys::CUresult::CUDA_ERROR_NOT_SUPPORTED));
+        }
+        stream.ctx.bind_to_thread()?;
+        // While capturing, this adds a memory allocation node instead of allocating.
+        let cu_device_ptr = result::malloc_async(stream.cu_stream, len * std::mem::size_of::<T>())?;
+        Ok(GraphSlice {
+            cu_device_ptr,
+            len,
+            stream,
+            capture_id: self.capture_id,
+            marker: PhantomData,
+        })
+    }
+
+    /// Allocates graph owned memory with all values zero'd out. See [CaptureGuard::alloc()].
+    pub fn alloc_zeros<T: DeviceRepr + ValidAsZeroBits>(
+        &self,
+        len: usize,
+    ) -> Result<GraphSlice<'_, T>, DriverError> {
+        let mut dst = unsafe { self.alloc(len) }?;
+        self.stream.memset_zeros(&mut dst)?;
+        Ok(dst)
+    }
+}
+
+impl Drop for CaptureGuard<'_> {
+    fn drop(&mut self) {
+        if self.ended {
+            return;
+        }
+        let ctx = &self.stream.ctx;
+        // The reset already destroyed this.
+        if ctx.is_invalidated() {
+            return;
+        }
+        ctx.record_err(ctx.bind_to_thread());
+        match unsafe { result::stream::end_capture(self.stream.cu_stream) } {
+            Ok(cu_graph) if !cu_graph.is_null() => {
+                ctx.record_err(unsafe { result::graph::destroy(cu_graph) })
+            }
+            Ok(_) => (),
+            // Work submitted during the capture can invalidate it, which is expected
+            // when abandoning a capture.
+            Err(DriverError(sys::CUresult::CUDA_ERROR_STREAM_CAPTURE_INVALIDATED)) => (),
+            Err(err) => ctx.record_err(Err::<(), _>(err)),
+        }
+    }
+}
+
+/// Device memory owned by a graph under construction. Create with [CaptureGuard::alloc()]
+/// or [CaptureGuard::alloc_zeros()].
+///
+/// Can be used like a [super::CudaSlice] within the capture, including as a kernel argument.
+/// The memory only exists while the graph runs, so it can only be used on streams that are
+/// part of the capture (the captured stream, or streams joined to it). Anything else fails
+/// with [sys::CUresult::CUDA_ERROR_STREAM_CAPTURE_ISOLATION]. There is no event tracking for
+/// graph memory, the capture orders its uses instead.
+#[derive(Debug)]
+pub struct GraphSlice<'a, T> {
+    pub(crate) cu_device_ptr: sys::CUdeviceptr,
+    pub(crate) len: usize,
+    pub(crate) stream: &'a Arc<CudaStream>,
+    /// The [CaptureInfo::id] of the capture this was allocated in.
+    pub(crate) capture_id: u64,
+    marker: PhantomData<*const T>,
+}
+
+unsafe impl<T> Send for GraphSlice<'_, T> {}
+unsafe impl<T> Sync for GraphSlice<'_, T> {}
+
+impl<T> Drop for GraphSlice<'_, T> {
+    fn drop(&mut self) {
+        let ctx = &self.stream.ctx;
+        // The reset already destroyed this.
+        if ctx.is_invalidated() {
+            return;
+        }
+        ctx.record_err(ctx.bind_to_thread());
+        // While capturing, this adds a memory free node.
+        ctx.record_err(unsafe { result::free_async(self.cu_device_ptr, self.stream.cu_stream) });
+    }
+}
+
+impl<T> GraphSlice<'_, T> {
+    /// The number of elements of `T` in this object.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of bytes in this object.
+    pub fn num_bytes(&self) -> usize {
+        self.len * std::mem::size_of::<T>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns [sys::CUresult::CUDA_ERROR_STREAM_CAPTURE_ISOLATION] unless `stream` is still
+    /// part of the capture this was allocated in.
+    pub(crate) fn check_capture(&self, stream: &CudaStream) -> Result<(), DriverError> {
+        let (status, id, _, _) = unsafe { result::stream::get_capture_info(stream.cu_stream) }?;
+        if status == sys::CUstreamCaptureStatus::CU_STREAM_CAPTURE_STATUS_ACTIVE
+            && id == self.capture_id
+        {
+            Ok(())
+        } else {
+            Err(DriverError(
+                sys::CUresult::CUDA_ERROR_STREAM_CAPTURE_ISOLATION,
+            ))
+        }
+    }
+}
+
+impl<T> DeviceSlice<T> for GraphSlice<'_, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn stream(&self) -> &Arc<CudaStream> {
+        self.stream
+    }
+}
+
+impl<T> DevicePtr<T> for GraphSlice<'_, T> {
+    fn device_ptr<'a>(&'a self, stream: &'a CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if let Err(err) = self.check_capture(stream) {
+            stream.ctx.record_err(Err::<(), _>(err));
+            return (0, SyncOnDrop::Record(None));
+        }
+        (self.cu_device_ptr, SyncOnDrop::Record(None))
+    }
+}
+
+impl<T> DevicePtrMut<T> for GraphSlice<'_, T> {
+    fn device_ptr_mut<'a>(
+        &'a mut self,
+        stream: &'a CudaStream,
+    ) -> (sys::CUdeviceptr, SyncOnDrop<'a>) {
+        if let Err(err) = self.check_capture(stream) {
+            stream.ctx.record_err(Err::<(), _>(err));
+            return (0, SyncOnDrop::Record(None));
+        }
+        (self.cu_device_ptr, SyncOnDrop::Record(None))
+    }
+}
+
+/// Graph memory statistics of a device, see [CudaContext::graph_mem_usage()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GraphMemUsage {
+    /// Bytes currently associated with graph allocations.
+    pub used_mem_current: u64,
+    /// High watermark of [GraphMemUsage::used_mem_current].
+    pub used_mem_high: u64,
+    /// Bytes currently reserved for graph allocations, used or not.
+    pub reserved_mem_current: u64,
+    /// High watermark of [GraphMemUsage::reserved_mem_current].
+    pub reserved_mem_high: u64,
+}
+
+impl CudaContext {
+    /// Memory usage of graph allocations (see [CaptureGuard::alloc()]) on this context's device.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    pub fn graph_mem_usage(&self) -> Result<GraphMemUsage, DriverError> {
+        use sys::CUgraphMem_attribute::*;
+        self.bind_to_thread()?;
+        let get = |attr| unsafe { result::device::get_graph_mem_attribute(self.cu_device, attr) };
+        Ok(GraphMemUsage {
+            used_mem_current: get(CU_GRAPH_MEM_ATTR_USED_MEM_CURRENT)?,
+            used_mem_high: get(CU_GRAPH_MEM_ATTR_USED_MEM_HIGH)?,
+            reserved_mem_current: get(CU_GRAPH_MEM_ATTR_RESERVED_MEM_CURRENT)?,
+            reserved_mem_high: get(CU_GRAPH_MEM_ATTR_RESERVED_MEM_HIGH)?,
+        })
+    }
+
+    /// Resets [GraphMemUsage::used_mem_high] and [GraphMemUsage::reserved_mem_high].
+    pub fn reset_graph_mem_high_watermarks(&self) -> Result<(), DriverError> {
+        use sys::CUgraphMem_attribute::*;
+        self.bind_to_thread()?;
+        unsafe {
+            result::device::set_graph_mem_attribute(
+                self.cu_device,
+                CU_GRAPH_MEM_ATTR_USED_MEM_HIGH,
+                0,
+            )?;
+            result::device::set_graph_mem_attribute(
+                self.cu_device,
+                CU_GRAPH_MEM_ATTR_RESERVED_MEM_HIGH,
+                0,
+            )
+        }
+    }
+
+    /// Releases memory reserved for graph allocations that is not in use back to the OS.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    pub fn trim_graph_memory(&self) -> Result<(), DriverError> {
+        self.bind_to_thread()?;
+        unsafe { result::device::graph_mem_trim(self.cu_device) }
+    }
 }
 
 impl CudaGraph {
@@ -115,3 +471,142 @@ impl CudaGraph {
         self.cu_graph_exec
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::CudaContext;
+
+    use super::*;
+
+    const MODE: sys::CUstreamCaptureMode =
+        sys::CUstreamCaptureMode::CU_STREAM_CAPTURE_MODE_THREAD_LOCAL;
+    const FLAGS: sys::CUgraphInstantiate_flags =
+        sys::CUgraphInstantiate_flags::CUDA_GRAPH_INSTANTIATE_FLAG_AUTO_FREE_ON_LAUNCH;
+
+    #[test]
+    fn test_capture() {
+        let ctx = CudaContext::new(0).unwrap();
+        // `src` and `dev` are allocated outside of the capture, see [CudaStream::capture()].
+        unsafe { ctx.disable_event_tracking() };
+        let stream = ctx.new_stream().unwrap();
+        // Pageable host memory can't be copied from during a capture.
+        let src = stream.clone_htod(&[1.0f32, 2.0, 3.0, 4.0]).unwrap();
+        let mut dev = stream.alloc_zeros::<f32>(4).unwrap();
+
+        assert!(!stream.capture_info().unwrap().is_active());
+        let graph = stream
+            .capture(MODE, FLAGS, |s| {
+                let info = s.capture_info()?;
+                assert!(info.is_active());
+                assert!(!info.cu_graph.is_null());
+                s.memcpy_dtod(&src, &mut dev)
+            })
+            .unwrap();
+        assert!(!stream.capture_info().unwrap().is_active());
+
+        graph.launch().unwrap();
+        assert_eq!(stream.clone_dtoh(&dev).unwrap(), [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_capture_ends_on_error() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.new_stream().unwrap();
+
+        let err = stream
+            .capture(MODE, FLAGS, |_| {
+                Err(DriverError(sys::CUresult::CUDA_ERROR_UNKNOWN))
+            })
+            .unwrap_err();
+        assert_eq!(err, DriverError(sys::CUresult::CUDA_ERROR_UNKNOWN));
+        assert_eq!(
+            stream.capture_status().unwrap(),
+            sys::CUstreamCaptureStatus::CU_STREAM_CAPTURE_STATUS_NONE
+        );
+        ctx.check_err().unwrap();
+    }
+
+    #[test]
+    fn test_capture_guard_alloc() {
+        let ctx = CudaContext::new(0).unwrap();
+        // `src` and `out` are allocated outside of the capture, see [CudaStream::capture()].
+        unsafe { ctx.disable_event_tracking() };
+        let stream = ctx.new_stream().unwrap();
+        let src = stream.clone_htod(&[5u8; 16]).unwrap();
+        let mut out = stream.alloc_zeros::<u8>(16).unwrap();
+
+        let guard = stream.capture_guard(MODE).unwrap();
+        {
+            let mut scratch = unsafe { guard.alloc::<u8>(16) }.unwrap();
+            assert_eq!(scratch.num_bytes(), 16);
+            stream.memcpy_dtod(&src, &mut scratch).unwrap();
+            stream.memcpy_dtod(&scratch, &mut out).unwrap();
+
+            // The memory doesn't exist outside of the graph.
+            let other = ctx.new_stream().unwrap();
+            assert_eq!(
+                other.memcpy_dtod(&scratch, &mut out).unwrap_err(),
+                DriverError(sys::CUresult::CUDA_ERROR_STREAM_CAPTURE_ISOLATION)
+            );
+        }
+        let graph = guard.end(FLAGS).unwrap().unwrap();
+        ctx.check_err().unwrap();
+
+        graph.launch().unwrap();
+        assert_eq!(stream.clone_dtoh(&out).unwrap(), [5u8; 16]);
+    }
+
+    #[test]
+    fn test_capture_graph_memory() {
+        let ctx = CudaContext::new(0).unwrap();
+        unsafe { ctx.disable_event_tracking() };
+        let stream = ctx.new_stream().unwrap();
+        let src = stream.clone_htod(&[3u32; 8]).unwrap();
+        let mut out = stream.alloc_zeros::<u32>(8).unwrap();
+        ctx.trim_graph_memory().unwrap();
+
+        let guard = stream.capture_guard(MODE).unwrap();
+        {
+            let mut scratch = guard.alloc_zeros::<u32>(8).unwrap();
+            assert_eq!(scratch.len(), 8);
+            stream.memcpy_dtod(&src, &mut scratch).unwrap();
+            stream.memcpy_dtod(&scratch, &mut out).unwrap();
+        }
+        let graph = guard.end(FLAGS).unwrap().unwrap();
+
+        graph.launch().unwrap();
+        stream.synchronize().unwrap();
+        assert_eq!(stream.clone_dtoh(&out).unwrap(), [3u32; 8]);
+
+        let usage = ctx.graph_mem_usage().unwrap();
+        assert!(usage.reserved_mem_current >= 32);
+        assert!(usage.used_mem_high >= 32);
+        assert_eq!(usage.used_mem_current, 0);
+
+        drop(graph);
+        ctx.trim_graph_memory().unwrap();
+        ctx.reset_graph_mem_high_watermarks().unwrap();
+        let usage = ctx.graph_mem_usage().unwrap();
+        assert_eq!(usage.used_mem_high, 0);
+    }
+
+    #[test]
+    fn test_capture_guard_ends_on_panic() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.new_stream().unwrap();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = stream.capture_guard(MODE).unwrap();
+            panic!("oops");
+        }));
+        assert!(result.is_err());
+        assert_eq!(
+            stream.capture_status().unwrap(),
+            sys::CUstreamCaptureStatus::CU_STREAM_CAPTURE_STATUS_NONE
+        );
+
+        let guard = stream.capture_guard(MODE).unwrap();
+        assert!(guard.stream().capture_info().unwrap().is_active());
+        assert!(guard.end(FLAGS).unwrap().is_some());
+    }
+}
@@ -0,0 +1,386 @@
+//! Read-only introspection of [CudaGraph]s, e.g. to find out why a captured graph
+//! serializes work that was expected to run concurrently.
+//!
+//! [CudaGraph::info()] returns a [GraphInfo], a plain description of the nodes and edges
+//! of the graph that can be rendered with [GraphInfo::to_dot()] and [GraphInfo::to_json()].
+
+use std::{fmt::Write, string::String, vec::Vec};
+
+use crate::driver::{result, sys};
+
+use super::{CudaGraph, DriverError};
+
+/// The structure of a graph. Nodes are identified by their index in [GraphInfo::nodes].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GraphInfo {
+    pub nodes: Vec<GraphNodeInfo>,
+    /// Dependencies as `(from, to)` node indices - `to` runs after `from`.
+    pub edges: Vec<(usize, usize)>,
+}
+
+/// A single node of a [GraphInfo].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphNodeInfo {
+    pub node_type: sys::CUgraphNodeType,
+    /// Only set for [sys::CUgraphNodeType::CU_GRAPH_NODE_TYPE_KERNEL] nodes.
+    pub kernel: Option<KernelNodeInfo>,
+}
+
+/// Launch parameters of a kernel node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelNodeInfo {
+    /// The mangled function name. Requires cuda 12.3 or newer, `None` otherwise.
+    pub function_name: Option<String>,
+    pub grid_dim: (u32, u32, u32),
+    pub block_dim: (u32, u32, u32),
+    pub shared_mem_bytes: u32,
+}
+
+impl GraphNodeInfo {
+    /// Short name of the node type, e.g. `"kernel"` or `"memcpy"`.
+    pub fn type_name(&self) -> String {
+        let name = std::format!("{:?}", self.node_type);
+        name.trim_start_matches("CU_GRAPH_NODE_TYPE_")
+            .to_lowercase()
+    }
+}
+
+impl CudaGraph {
+    /// Introspects the nodes and edges of this graph.
+    pub fn info(&self) -> Result<GraphInfo, DriverError> {
+        self.stream.ctx.bind_to_thread()?;
+        unsafe { GraphInfo::query(self.cu_graph) }
+    }
+
+    /// The DOT description of this graph generated by the driver. `flags` is a combination of
+    /// [sys::CUgraphDebugDot_flags] values.
+    ///
+    /// This contains more detail than [GraphInfo::to_dot()], but its format is not stable.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__GRAPH.html)
+    #[cfg(feature = "std")]
+    pub fn to_dot(&self, flags: u32) -> Result<String, DriverError> {
+        self.stream.ctx.bind_to_thread()?;
+        // The driver can only write to a file.
+        let file = TempDotFile::create()?;
+        let path_c = std::ffi::CString::new(file.0.to_string_lossy().as_bytes())
+            .map_err(|_| DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE))?;
+        unsafe { result::graph::debug_dot_print(self.cu_graph, &path_c, flags) }?;
+        std::fs::read_to_string(&file.0)
+            .map_err(|_| DriverError(sys::CUresult::CUDA_ERROR_FILE_NOT_FOUND))
+    }
+}
+
+/// A new, empty file in the temp directory for [CudaGraph::to_dot()], removed when dropped.
+#[cfg(feature = "std")]
+struct TempDotFile(std::path::PathBuf);
+
+#[cfg(feature = "std")]
+impl TempDotFile {
+    /// Creates a file that didn't exist before, so nothing else can have created it (or a
+    /// symlink in its place) to have the driver write somewhere else.
+    fn create() -> Result<Self, DriverError> {
+        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let dir = std::env::temp_dir();
+        for _ in 0..100 {
+            let nanos = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.subsec_nanos());
+            let path = dir.join(std::format!(
+                "cudarc-graph-{}-{}-{nanos}.dot",
+                std::process::id(),
+                COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            ));
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(Self(path)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(_) => break,
+            }
+        }
+        Err(DriverError(sys::CUresult::CUDA_ERROR_FILE_NOT_FOUND))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for TempDotFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl GraphInfo {
+    /// Introspects a raw graph, e.g. [super::CaptureInfo::cu_graph] while it is being captured.
+    ///
+    /// # Safety
+    /// `cu_graph` must be a valid graph, and the graph's context must be bound to the current thread.
+    pub unsafe fn query(cu_graph: sys::CUgraph) -> Result<Self, DriverError> {
+        let handles = result::graph::get_nodes(cu_graph)?;
+        let mut nodes = Vec::with_capacity(handles.len());
+        for &node in handles.iter() {
+            let node_type = result::graph::node_get_type(node)?;
+            let kernel = if node_type == sys::CUgraphNodeType::CU_GRAPH_NODE_TYPE_KERNEL {
+                let params = result::graph::kernel_node_get_params(node)?;
+                Some(KernelNodeInfo {
+                    function_name: function_name(params.func),
+                    grid_dim: (params.gridDimX, params.gridDimY, params.gridDimZ),
+                    block_dim: (params.blockDimX, params.blockDimY, params.blockDimZ),
+                    shared_mem_bytes: params.sharedMemBytes,
+                })
+            } else {
+                None
+            };
+            nodes.push(GraphNodeInfo { node_type, kernel });
+        }
+
+        let index = |node: sys::CUgraphNode| handles.iter().position(|&n| n == node);
+        let mut edges = Vec::new();
+        for (from, to) in result::graph::get_edges(cu_graph)? {
+            if let (Some(from), Some(to)) = (index(from), index(to)) {
+                edges.push((from, to));
+            }
+        }
+        Ok(Self { nodes, edges })
+    }
+
+    /// Indices of the nodes that `node` directly depends on.
+    pub fn dependencies(&self, node: usize) -> Vec<usize> {
+        self.edges
+            .iter()
+            .filter(|(_, to)| *to == node)
+            .map(|(from, _)| *from)
+            .collect()
+    }
+
+    /// Renders the graph in the DOT format, e.g. to view with graphviz. The output only depends
+    /// on the contents of this [GraphInfo], so it can be used for snapshot tests.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cuda_graph {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut label = std::format!("{i}: {}", node.type_name());
+            if let Some(kernel) = node.kernel.as_ref() {
+                if let Some(name) = kernel.function_name.as_ref() {
+                    label.push_str("\\n");
+                    label.push_str(&escape_dot(name));
+                }
+                let _ = write!(
+                    label,
+                    "\\ngrid {:?} block {:?} smem {}",
+                    kernel.grid_dim, kernel.block_dim, kernel.shared_mem_bytes
+                );
+            }
+            let _ = writeln!(dot, "    n{i} [label=\"{label}\"];");
+        }
+        for (from, to) in self.edges.iter() {
+            let _ = writeln!(dot, "    n{from} -> n{to};");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a single line of JSON:
+    ///
+    /// ```json
+    /// {"nodes":[{"id":0,"type":"kernel","kernel":{"name":"k","grid_dim":[1,1,1],"block_dim":[32,1,1],"shared_mem_bytes":0}}],"edges":[]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"nodes\":[");
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(json, "{{\"id\":{i},\"type\":\"{}\"", node.type_name());
+            if let Some(kernel) = node.kernel.as_ref() {
+                json.push_str(",\"kernel\":{\"name\":");
+                match kernel.function_name.as_ref() {
+                    Some(name) => {
+                        let _ = write!(json, "\"{}\"", escape_json(name));
+                    }
+                    None => json.push_str("null"),
+                }
+                let (gx, gy, gz) = kernel.grid_dim;
+                let (bx, by, bz) = kernel.block_dim;
+                let _ = write!(
+                    json,
+                    ",\"grid_dim\":[{gx},{gy},{gz}],\"block_dim\":[{bx},{by},{bz}],\"shared_mem_bytes\":{}}}",
+                    kernel.shared_mem_bytes
+                );
+            }
+            json.push('}');
+        }
+        json.push_str("],\"edges\":[");
+        for (i, (from, to)) in self.edges.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(json, "[{from},{to}]");
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+#[cfg(any(
+    feature = "cuda-12030",
+    feature = "cuda-12040",
+    feature = "cuda-12050",
+    feature = "cuda-12060",
+    feature = "cuda-12080",
+    feature = "cuda-12090",
+    feature = "cuda-13000",
+    feature = "cuda-13010",
+    feature = "cuda-13020"
+))]
+unsafe fn function_name(func: sys::CUfunction) -> Option<String> {
+    if func.is_null() {
+        return None;
+    }
+    result::function::get_name(func).ok()
+}
+
+#[cfg(not(any(
+    feature = "cuda-12030",
+    feature = "cuda-12040",
+    feature = "cuda-12050",
+    feature = "cuda-12060",
+    feature = "cuda-12080",
+    feature = "cuda-12090",
+    feature = "cuda-13000",
+    feature = "cuda-13010",
+    feature = "cuda-13020"
+)))]
+unsafe fn function_name(_func: sys::CUfunction) -> Option<String> {
+    None
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diamond() -> GraphInfo {
+        let kernel = |name: Option<&str>| GraphNodeInfo {
+            node_type: sys::CUgraphNodeType::CU_GRAPH_NODE_TYPE_KERNEL,
+            kernel: Some(KernelNodeInfo {
+                function_name: name.map(Into::into),
+                grid_dim: (4, 1, 1),
+                block_dim: (128, 1, 1),
+                shared_mem_bytes: 0,
+            }),
+        };
+        GraphInfo {
+            nodes: std::vec![
+                GraphNodeInfo {
+                    node_type: sys::CUgraphNodeType::CU_GRAPH_NODE_TYPE_MEMCPY,
+                    kernel: None,
+                },
+                kernel(Some("_Z3fooPf")),
+                kernel(None),
+                GraphNodeInfo {
+                    node_type: sys::CUgraphNodeType::CU_GRAPH_NODE_TYPE_MEMSET,
+                    kernel: None,
+                },
+            ],
+            edges: std::vec![(0, 1), (0, 2), (1, 3), (2, 3)],
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_temp_dot_file() {
+        let a = TempDotFile::create().unwrap();
+        let b = TempDotFile::create().unwrap();
+        assert_ne!(a.0, b.0);
+        assert!(a.0.exists());
+        let path = a.0.clone();
+        drop(a);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_graph_info_to_dot() {
+        assert_eq!(
+            diamond().to_dot(),
+            r#"digraph cuda_graph {
+    n0 [label="0: memcpy"];
+    n1 [label="1: kernel\n_Z3fooPf\ngrid (4, 1, 1) block (128, 1, 1) smem 0"];
+    n2 [label="2: kernel\ngrid (4, 1, 1) block (128, 1, 1) smem 0"];
+    n3 [label="3: memset"];
+    n0 -> n1;
+    n0 -> n2;
+    n1 -> n3;
+    n2 -> n3;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_graph_info_to_json() {
+        assert_eq!(
+            diamond().to_json(),
+            concat!(
+                r#"{"nodes":[{"id":0,"type":"memcpy"},"#,
+                r#"{"id":1,"type":"kernel","kernel":{"name":"_Z3fooPf","grid_dim":[4,1,1],"block_dim":[128,1,1],"shared_mem_bytes":0}},"#,
+                r#"{"id":2,"type":"kernel","kernel":{"name":null,"grid_dim":[4,1,1],"block_dim":[128,1,1],"shared_mem_bytes":0}},"#,
+                r#"{"id":3,"type":"memset"}],"#,
+                r#""edges":[[0,1],[0,2],[1,3],[2,3]]}"#
+            )
+        );
+        assert_eq!(GraphInfo::default().to_json(), r#"{"nodes":[],"edges":[]}"#);
+    }
+
+    #[test]
+    fn test_graph_info_escaping() {
+        assert_eq!(escape_json("a\"b\\c\n\u{1}"), "a\\\"b\\\\c\\n\\u0001");
+        assert_eq!(escape_dot("a\"b\\c"), "a\\\"b\\\\c");
+        assert_eq!(diamond().dependencies(3), [1, 2]);
+    }
+
+    #[test]
+    fn test_cuda_graph_info() {
+        let ctx = crate::driver::CudaContext::new(0).unwrap();
+        unsafe { ctx.disable_event_tracking() };
+        let stream = ctx.new_stream().unwrap();
+        let mut a = stream.alloc_zeros::<f32>(16).unwrap();
+        let mut b = stream.alloc_zeros::<f32>(16).unwrap();
+
+        let mode = sys::CUstreamCaptureMode::CU_STREAM_CAPTURE_MODE_THREAD_LOCAL;
+        let flags = sys::CUgraphInstantiate_flags::CUDA_GRAPH_INSTANTIATE_FLAG_AUTO_FREE_ON_LAUNCH;
+        let graph = stream
+            .capture(mode, flags, |s| {
+                s.memset_zeros(&mut a)?;
+                s.memset_zeros(&mut b)
+            })
+            .unwrap();
+
+        let info = graph.info().unwrap();
+        assert_eq!(info.nodes.len(), 2);
+        assert!(info.nodes.iter().all(|n| n.type_name() == "memset"));
+        assert_eq!(info.edges, [(0, 1)]);
+        #[cfg(feature = "std")]
+        assert!(graph.to_dot(0).unwrap().starts_with("digraph"));
+    }
+}
@@ -5,7 +5,10 @@ use crate::driver::{
     sys,
 };
 
-use super::{CudaEvent, CudaFunction, CudaSlice, CudaStream, CudaView, CudaViewMut, DeviceRepr};
+use super::{
+    CudaEvent, CudaFunction, CudaGlobal, CudaSlice, CudaStream, CudaView, CudaViewMut, DeviceRepr,
+    GraphSlice,
+};
 
 /// Configuration for [result::launch_kernel]
 ///
@@ -52,7 +55,12 @@ pub struct LaunchArgs<'a> {
     pub(super) waits: Vec<&'a CudaEvent>,
     pub(super) records: Vec<&'a CudaEvent>,
     pub(super) args: Vec<*mut std::ffi::c_void>,
+    /// The size of each of `args`, checked against the kernel's PTX signature.
+    #[cfg(feature = "debug-launch-checks")]
+    pub(super) arg_sizes: Vec<usize>,
     pub(super) flags: Option<sys::CUevent_flags>,
+    /// Whether an argument's context was [reset](super::CudaContext::reset()).
+    pub(super) invalidated: bool,
 }
 
 impl CudaStream {
@@ -67,7 +75,10 @@ impl CudaStream {
             waits: Vec::new(),
             records: Vec::new(),
             args: Vec::new(),
+            #[cfg(feature = "debug-launch-checks")]
+            arg_sizes: Vec::new(),
             flags: None,
+            invalidated: false,
         }
     }
 }
@@ -92,7 +103,7 @@ pub unsafe trait PushKernelArg<T> {
 unsafe impl<'a, 'b: 'a, T: DeviceRepr> PushKernelArg<&'b T> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b T) -> &mut Self {
-        self.args.push(arg as *const T as *mut _);
+        self.push_arg(arg);
         self
     }
 }
@@ -100,6 +111,7 @@ unsafe impl<'a, 'b: 'a, T: DeviceRepr> PushKernelArg<&'b T> for LaunchArgs<'a> {
 unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b CudaSlice<T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b CudaSlice<T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(write) = arg.write.as_ref() {
                 self.waits.push(write);
@@ -108,8 +120,7 @@ unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b CudaSlice<T>> for LaunchArgs<'a> {
                 self.records.push(read);
             }
         }
-        self.args
-            .push((&arg.cu_device_ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.cu_device_ptr);
         self
     }
 }
@@ -117,6 +128,7 @@ unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b CudaSlice<T>> for LaunchArgs<'a> {
 unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b mut CudaSlice<T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b mut CudaSlice<T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(read) = arg.read.as_ref() {
                 self.waits.push(read);
@@ -126,8 +138,62 @@ unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b mut CudaSlice<T>> for LaunchArgs<'a
                 self.records.push(write);
             }
         }
-        self.args
-            .push((&arg.cu_device_ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.cu_device_ptr);
+        self
+    }
+}
+
+unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b CudaGlobal<T>> for LaunchArgs<'a> {
+    #[inline(always)]
+    fn arg(&mut self, arg: &'b CudaGlobal<T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
+        if self.stream.context().is_managing_stream_synchronization() {
+            if let Some(write) = arg.events.write.as_ref() {
+                self.waits.push(write);
+            }
+            if let Some(read) = arg.events.read.as_ref() {
+                self.records.push(read);
+            }
+        }
+        self.push_arg(&arg.cu_device_ptr);
+        self
+    }
+}
+
+unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b mut CudaGlobal<T>> for LaunchArgs<'a> {
+    #[inline(always)]
+    fn arg(&mut self, arg: &'b mut CudaGlobal<T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
+        if self.stream.context().is_managing_stream_synchronization() {
+            if let Some(read) = arg.events.read.as_ref() {
+                self.waits.push(read);
+            }
+            if let Some(write) = arg.events.write.as_ref() {
+                self.waits.push(write);
+                self.records.push(write);
+            }
+        }
+        self.push_arg(&arg.cu_device_ptr);
+        self
+    }
+}
+
+unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b GraphSlice<'c, T>> for LaunchArgs<'a> {
+    #[inline(always)]
+    fn arg(&mut self, arg: &'b GraphSlice<'c, T>) -> &mut Self {
+        // Fails the launch unless the stream is part of the capture.
+        self.stream.ctx.record_err(arg.check_capture(self.stream));
+        self.push_arg(&arg.cu_device_ptr);
+        self
+    }
+}
+
+unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b mut GraphSlice<'c, T>> for LaunchArgs<'a> {
+    #[inline(always)]
+    fn arg(&mut self, arg: &'b mut GraphSlice<'c, T>) -> &mut Self {
+        // Fails the launch unless the stream is part of the capture.
+        self.stream.ctx.record_err(arg.check_capture(self.stream));
+        self.push_arg(&arg.cu_device_ptr);
         self
     }
 }
@@ -135,6 +201,7 @@ unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b mut CudaSlice<T>> for LaunchArgs<'a
 unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b CudaView<'c, T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b CudaView<'c, T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(write) = arg.write.as_ref() {
                 self.waits.push(write);
@@ -143,7 +210,7 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b CudaView<'c, T>> for Launch
                 self.records.push(read);
             }
         }
-        self.args.push((&arg.ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.ptr);
         self
     }
 }
@@ -151,6 +218,7 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b CudaView<'c, T>> for Launch
 unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b mut CudaViewMut<'c, T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b mut CudaViewMut<'c, T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         if self.stream.context().is_managing_stream_synchronization() {
             if let Some(read) = arg.read.as_ref() {
                 self.waits.push(read);
@@ -160,12 +228,41 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b mut CudaViewMut<'c, T>> for
                 self.records.push(write);
             }
         }
-        self.args.push((&arg.ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.ptr);
         self
     }
 }
 
 impl LaunchArgs<'_> {
+    /// Pushes a pointer to `arg`, which must live until the kernel is launched.
+    #[inline(always)]
+    pub(super) fn push_arg<T>(&mut self, arg: &T) {
+        self.args.push(arg as *const T as *mut _);
+        #[cfg(feature = "debug-launch-checks")]
+        self.arg_sizes.push(std::mem::size_of::<T>());
+    }
+
+    /// Returns [sys::CUresult::CUDA_ERROR_INVALID_VALUE] if the arguments don't match the
+    /// number and sizes of the kernel's parameters.
+    /// Kernels loaded from cubins or with `CudaModule::functions()` are not checked.
+    #[cfg(feature = "debug-launch-checks")]
+    fn check_signature(&self) -> Result<(), DriverError> {
+        let Some(entry) = self.func.signature.as_ref() else {
+            return Ok(());
+        };
+        let matches = self.arg_sizes.len() == entry.params.len()
+            && self
+                .arg_sizes
+                .iter()
+                .zip(entry.params.iter())
+                .all(|(size, param)| *size == param.size);
+        if matches {
+            Ok(())
+        } else {
+            Err(DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE))
+        }
+    }
+
     /// Calling this will make [LaunchArgs::launch()] and [LaunchArgs::launch_cooperative()]
     /// return 2 [CudaEvent]s that recorded before and after the kernel is submitted.
     pub fn record_kernel_launch(&mut self, flags: sys::CUevent_flags) -> &mut Self {
@@ -204,12 +301,25 @@ impl LaunchArgs<'_> {
     /// Since [LaunchArgs::launch()] properly records reads/writes for [CudaSlice]/[CudaView]/[CudaViewMut],
     /// and the drop implementation of [CudaSlice] waits on those events to finish,
     /// we will never encounter a use after free situation.
+    ///
+    /// # Errors
+    ///
+    /// With the `debug-launch-checks` feature, returns
+    /// [sys::CUresult::CUDA_ERROR_INVALID_VALUE] if the number or sizes of the arguments
+    /// don't match the kernel's parameters in its PTX.
     #[inline(always)]
     pub unsafe fn launch(
         &mut self,
         cfg: LaunchConfig,
     ) -> Result<Option<(CudaEvent, CudaEvent)>, DriverError> {
+        #[cfg(feature = "debug-launch-checks")]
+        self.check_signature()?;
         self.stream.ctx.bind_to_thread()?;
+        if self.invalidated {
+            return Err(DriverError(
+                sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED,
+            ));
+        }
         for &event in self.waits.iter() {
             self.stream.wait(event)?;
         }
@@ -244,7 +354,14 @@ impl LaunchArgs<'_> {
         &mut self,
         cfg: LaunchConfig,
     ) -> Result<Option<(CudaEvent, CudaEvent)>, DriverError> {
+        #[cfg(feature = "debug-launch-checks")]
+        self.check_signature()?;
         self.stream.ctx.bind_to_thread()?;
+        if self.invalidated {
+            return Err(DriverError(
+                sys::cudaError_enum::CUDA_ERROR_CONTEXT_IS_DESTROYED,
+            ));
+        }
         for &event in self.waits.iter() {
             self.stream.wait(event)?;
         }
@@ -376,6 +493,57 @@ extern \"C\" __global__ void sin_kernel(float *out, const float *inp, size_t num
         drop(a_dev);
     }
 
+    #[cfg(feature = "debug-launch-checks")]
+    #[test]
+    fn test_launch_checks() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+
+        let ptx = compile_ptx_with_opts(SIN_CU, Default::default()).unwrap();
+        let module = ctx.load_module(ptx).unwrap();
+        let sin_kernel = module.load_function("sin_kernel").unwrap();
+        let sizes: Vec<usize> = sin_kernel
+            .signature
+            .as_ref()
+            .unwrap()
+            .params
+            .iter()
+            .map(|p| p.size)
+            .collect();
+        assert_eq!(sizes, [8, 8, 8]);
+
+        let a = stream.alloc_zeros::<f32>(10).unwrap();
+        let mut b = stream.alloc_zeros::<f32>(10).unwrap();
+        let cfg = LaunchConfig::for_num_elems(10);
+        let err = DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE);
+        let missing_arg = unsafe {
+            stream
+                .launch_builder(&sin_kernel)
+                .arg(&mut b)
+                .arg(&a)
+                .launch(cfg)
+        };
+        assert_eq!(missing_arg.unwrap_err(), err);
+        let wrong_size = unsafe {
+            stream
+                .launch_builder(&sin_kernel)
+                .arg(&mut b)
+                .arg(&a)
+                .arg(&10u32)
+                .launch(cfg)
+        };
+        assert_eq!(wrong_size.unwrap_err(), err);
+        unsafe {
+            stream
+                .launch_builder(&sin_kernel)
+                .arg(&mut b)
+                .arg(&a)
+                .arg(&10usize)
+                .launch(cfg)
+        }
+        .unwrap();
+    }
+
     #[cfg(feature = "nvrtc")]
     #[test]
     fn test_large_launches() {
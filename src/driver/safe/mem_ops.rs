@@ -0,0 +1,251 @@
+use crate::driver::{result, sys};
+
+use super::{CudaStream, DevicePtr, DevicePtrMut, DeviceRepr, DriverError};
+
+/// The condition [CudaStream::wait_value()] waits for, comparing the value in memory
+/// (`*addr`) against the given `value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WaitCond {
+    /// `*addr >= value`, using a cyclic comparison that handles wrap around.
+    Geq,
+    /// `*addr == value`.
+    Eq,
+    /// `(*addr & value) != 0`.
+    And,
+    /// `!(*addr | value) != 0`.
+    Nor,
+}
+
+impl From<WaitCond> for sys::CUstreamWaitValue_flags {
+    fn from(value: WaitCond) -> Self {
+        match value {
+            WaitCond::Geq => sys::CUstreamWaitValue_flags::CU_STREAM_WAIT_VALUE_GEQ,
+            WaitCond::Eq => sys::CUstreamWaitValue_flags::CU_STREAM_WAIT_VALUE_EQ,
+            WaitCond::And => sys::CUstreamWaitValue_flags::CU_STREAM_WAIT_VALUE_AND,
+            WaitCond::Nor => sys::CUstreamWaitValue_flags::CU_STREAM_WAIT_VALUE_NOR,
+        }
+    }
+}
+
+/// Values that streams can wait on and write directly, see [CudaStream::wait_value()]
+/// and [CudaStream::write_value()]. Implemented for [u32] and [u64].
+pub trait StreamValue: DeviceRepr + Copy {
+    /// # Safety
+    /// See [result::stream::wait_value_32()].
+    #[doc(hidden)]
+    unsafe fn wait(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: Self,
+        flags: u32,
+    ) -> Result<(), DriverError>;
+
+    /// # Safety
+    /// See [result::stream::write_value_32()].
+    #[doc(hidden)]
+    unsafe fn write(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: Self,
+        flags: u32,
+    ) -> Result<(), DriverError>;
+}
+
+impl StreamValue for u32 {
+    unsafe fn wait(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: Self,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        result::stream::wait_value_32(stream, addr, value, flags)
+    }
+    unsafe fn write(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: Self,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        result::stream::write_value_32(stream, addr, value, flags)
+    }
+}
+
+impl StreamValue for u64 {
+    unsafe fn wait(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: Self,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        result::stream::wait_value_64(stream, addr, value, flags)
+    }
+    unsafe fn write(
+        stream: sys::CUstream,
+        addr: sys::CUdeviceptr,
+        value: Self,
+        flags: u32,
+    ) -> Result<(), DriverError> {
+        result::stream::write_value_64(stream, addr, value, flags)
+    }
+}
+
+impl CudaStream {
+    /// Blocks all later work on this stream until the first element of `src` satisfies
+    /// `cond` against `value`. The host thread is not blocked.
+    ///
+    /// `src` can be a [super::CudaSlice], [super::CudaView] or [super::CudaViewMut]. Together with
+    /// [CudaStream::write_value()] this can be used for flags between streams or between the
+    /// host and the device, without events.
+    ///
+    /// Fails with [sys::CUresult::CUDA_ERROR_INVALID_VALUE] if `src` is empty.
+    ///
+    /// **Note** that if nothing ever writes a matching value this stream will hang.
+    ///
+    /// # Event tracking
+    ///
+    /// The wait is ordered after earlier writes to `src`, but is not recorded as a read of
+    /// it: otherwise the write that satisfies the wait would first wait for the wait to be
+    /// satisfied, and both streams would hang. That is why this is unsafe, see below.
+    ///
+    /// The same applies to data guarded by the flag. If this stream reads it before the other
+    /// stream writes it (in the order the work is submitted), event tracking makes the
+    /// write wait for the read, which waits for the flag. Submit the writes first, or
+    /// allocate that data with event tracking disabled (see
+    /// [super::CudaContext::disable_event_tracking()]).
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEMOP.html)
+    ///
+    /// # Safety
+    ///
+    /// Since the wait is not tracked, dropping `src` does not wait for it. `src` must not be
+    /// dropped (and its memory must not be freed) before the wait is satisfied, otherwise
+    /// this stream polls freed memory that may have been reused.
+    pub unsafe fn wait_value<T: StreamValue, Src: DevicePtr<T>>(
+        &self,
+        src: &Src,
+        value: T,
+        cond: WaitCond,
+    ) -> Result<(), DriverError> {
+        if src.len() == 0 {
+            return Err(DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE));
+        }
+        self.ctx.bind_to_thread()?;
+        let (src, record_src) = src.device_ptr(self);
+        self.ctx.check_err()?;
+        // Don't record the read, see above.
+        std::mem::forget(record_src);
+        let flags: sys::CUstreamWaitValue_flags = cond.into();
+        T::wait(self.cu_stream, src, value, flags as u32)
+    }
+
+    /// Writes `value` to the first element of `dst` once all previous work on this stream
+    /// is done.
+    ///
+    /// If `flush` is true, all memory writes of previous work on this stream are made
+    /// visible before `value` is, so anyone that waits on `value` can safely read them.
+    /// Otherwise the write has no memory barrier, which is faster when `value`
+    /// does not guard other data.
+    ///
+    /// Fails with [sys::CUresult::CUDA_ERROR_INVALID_VALUE] if `dst` is empty.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEMOP.html)
+    pub fn write_value<T: StreamValue, Dst: DevicePtrMut<T>>(
+        &self,
+        dst: &mut Dst,
+        value: T,
+        flush: bool,
+    ) -> Result<(), DriverError> {
+        if dst.len() == 0 {
+            return Err(DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE));
+        }
+        self.ctx.bind_to_thread()?;
+        let (dst, _record_dst) = dst.device_ptr_mut(self);
+        self.ctx.check_err()?;
+        let flags = if flush {
+            sys::CUstreamWriteValue_flags::CU_STREAM_WRITE_VALUE_DEFAULT
+        } else {
+            sys::CUstreamWriteValue_flags::CU_STREAM_WRITE_VALUE_NO_MEMORY_BARRIER
+        };
+        unsafe { T::write(self.cu_stream, dst, value, flags as u32) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::CudaContext;
+
+    use super::*;
+
+    #[test]
+    fn test_write_value() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+
+        let mut a = stream.alloc_zeros::<u32>(4).unwrap();
+        stream.write_value(&mut a, 7, true).unwrap();
+        stream
+            .write_value(&mut a.slice_mut(2..3), 9, false)
+            .unwrap();
+        assert_eq!(stream.clone_dtoh(&a).unwrap(), [7, 0, 9, 0]);
+
+        let mut b = stream.alloc_zeros::<u64>(1).unwrap();
+        stream.write_value(&mut b, u64::MAX, true).unwrap();
+        assert_eq!(stream.clone_dtoh(&b).unwrap(), [u64::MAX]);
+    }
+
+    #[test]
+    fn test_write_value_empty() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+
+        let mut empty = stream.alloc_zeros::<u32>(0).unwrap();
+        let err = DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE);
+        assert_eq!(stream.write_value(&mut empty, 1, true), Err(err));
+        assert_eq!(
+            unsafe { stream.wait_value(&empty, 1, WaitCond::Eq) },
+            Err(err)
+        );
+    }
+
+    #[test]
+    fn test_wait_value_across_streams() {
+        let ctx = CudaContext::new(0).unwrap();
+        let producer = ctx.new_stream().unwrap();
+        let consumer = ctx.new_stream().unwrap();
+
+        let mut flag = producer.alloc_zeros::<u32>(1).unwrap();
+        let mut data = producer.alloc_zeros::<f32>(8).unwrap();
+        let mut out = consumer.alloc_zeros::<f32>(8).unwrap();
+
+        // The wait is submitted before the write that satisfies it.
+        unsafe { consumer.wait_value(&flag, 1, WaitCond::Geq) }.unwrap();
+        producer.memcpy_htod(&[2.0f32; 8], &mut data).unwrap();
+        producer.write_value(&mut flag, 1, true).unwrap();
+        consumer.memcpy_dtod(&data, &mut out).unwrap();
+
+        assert_eq!(consumer.clone_dtoh(&out).unwrap(), [2.0; 8]);
+    }
+
+    #[test]
+    fn test_wait_value_guards_data() {
+        let ctx = CudaContext::new(0).unwrap();
+        // `data` is read before it is written, which event tracking would order the other
+        // way around, see [CudaStream::wait_value()].
+        unsafe { ctx.disable_event_tracking() };
+        let producer = ctx.new_stream().unwrap();
+        let consumer = ctx.new_stream().unwrap();
+
+        let mut flag = producer.alloc_zeros::<u32>(1).unwrap();
+        let mut data = producer.alloc_zeros::<f32>(8).unwrap();
+        let mut out = consumer.alloc_zeros::<f32>(8).unwrap();
+        producer.synchronize().unwrap();
+
+        unsafe { consumer.wait_value(&flag, 1, WaitCond::Geq) }.unwrap();
+        consumer.memcpy_dtod(&data, &mut out).unwrap();
+
+        producer.memcpy_htod(&[2.0f32; 8], &mut data).unwrap();
+        producer.write_value(&mut flag, 1, true).unwrap();
+
+        assert_eq!(consumer.clone_dtoh(&out).unwrap(), [2.0; 8]);
+    }
+}
@@ -0,0 +1,282 @@
+use std::{boxed::Box, marker::PhantomData, sync::Arc, vec::Vec};
+
+use crate::driver::{result, sys};
+
+use super::{CudaStream, DevicePtr, DevicePtrMut, DeviceRepr, DriverError, HostSlice, SyncOnDrop};
+
+/// A single copy of a [CudaStream::memcpy_batch()]. Create with [CopyOp::htod()],
+/// [CopyOp::dtoh()] or [CopyOp::dtod()].
+///
+/// Each op copies all of its source, so the destination must be at least as long.
+/// Ops of a batch can have different element types.
+pub struct CopyOp<'a> {
+    src: Box<dyn CopySrc + 'a>,
+    dst: Box<dyn CopyDst + 'a>,
+}
+
+impl core::fmt::Debug for CopyOp<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CopyOp")
+            .field("src_len", &self.src.len())
+            .field("dst_len", &self.dst.len())
+            .field("num_bytes", &self.src.num_bytes())
+            .finish()
+    }
+}
+
+impl<'a> CopyOp<'a> {
+    /// Copy a `[T]`/`Vec<T>`/[super::PinnedHostSlice] to a [super::CudaSlice]/[super::CudaViewMut].
+    pub fn htod<T, Src, Dst>(src: &'a Src, dst: &'a mut Dst) -> Self
+    where
+        T: DeviceRepr + 'a,
+        Src: HostSlice<T> + ?Sized,
+        Dst: DevicePtrMut<T>,
+    {
+        Self {
+            src: Box::new(Host(src, PhantomData)),
+            dst: Box::new(Device(dst, PhantomData)),
+        }
+    }
+
+    /// Copy a [super::CudaSlice]/[super::CudaView] to a `[T]`/`Vec<T>`/[super::PinnedHostSlice].
+    pub fn dtoh<T, Src, Dst>(src: &'a Src, dst: &'a mut Dst) -> Self
+    where
+        T: DeviceRepr + 'a,
+        Src: DevicePtr<T>,
+        Dst: HostSlice<T> + ?Sized,
+    {
+        Self {
+            src: Box::new(Device(src, PhantomData)),
+            dst: Box::new(Host(dst, PhantomData)),
+        }
+    }
+
+    /// Copy a [super::CudaSlice]/[super::CudaView] to a [super::CudaSlice]/[super::CudaViewMut].
+    pub fn dtod<T, Src, Dst>(src: &'a Src, dst: &'a mut Dst) -> Self
+    where
+        T: DeviceRepr + 'a,
+        Src: DevicePtr<T>,
+        Dst: DevicePtrMut<T>,
+    {
+        Self {
+            src: Box::new(Device(src, PhantomData)),
+            dst: Box::new(Device(dst, PhantomData)),
+        }
+    }
+}
+
+/// Type erased source of a [CopyOp].
+trait CopySrc {
+    fn len(&self) -> usize;
+    fn num_bytes(&self) -> usize;
+    /// # Safety
+    /// The address is only valid while the returned [SyncOnDrop] is alive.
+    unsafe fn addr<'s>(&'s self, stream: &'s CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'s>);
+}
+
+/// Type erased destination of a [CopyOp].
+trait CopyDst {
+    fn len(&self) -> usize;
+    /// # Safety
+    /// The address is only valid while the returned [SyncOnDrop] is alive.
+    unsafe fn addr_mut<'s>(
+        &'s mut self,
+        stream: &'s CudaStream,
+    ) -> (sys::CUdeviceptr, SyncOnDrop<'s>);
+}
+
+struct Host<R, T>(R, PhantomData<T>);
+struct Device<R, T>(R, PhantomData<T>);
+
+impl<T, S: HostSlice<T> + ?Sized> CopySrc for Host<&S, T> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    fn num_bytes(&self) -> usize {
+        self.0.len() * std::mem::size_of::<T>()
+    }
+    unsafe fn addr<'s>(&'s self, stream: &'s CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'s>) {
+        let (slice, record) = self.0.stream_synced_slice(stream);
+        (slice.as_ptr() as sys::CUdeviceptr, record)
+    }
+}
+
+impl<T, S: HostSlice<T> + ?Sized> CopyDst for Host<&mut S, T> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    unsafe fn addr_mut<'s>(
+        &'s mut self,
+        stream: &'s CudaStream,
+    ) -> (sys::CUdeviceptr, SyncOnDrop<'s>) {
+        let (slice, record) = self.0.stream_synced_mut_slice(stream);
+        (slice.as_mut_ptr() as sys::CUdeviceptr, record)
+    }
+}
+
+impl<T, S: DevicePtr<T>> CopySrc for Device<&S, T> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    fn num_bytes(&self) -> usize {
+        self.0.num_bytes()
+    }
+    unsafe fn addr<'s>(&'s self, stream: &'s CudaStream) -> (sys::CUdeviceptr, SyncOnDrop<'s>) {
+        self.0.device_ptr(stream)
+    }
+}
+
+impl<T, S: DevicePtrMut<T>> CopyDst for Device<&mut S, T> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    unsafe fn addr_mut<'s>(
+        &'s mut self,
+        stream: &'s CudaStream,
+    ) -> (sys::CUdeviceptr, SyncOnDrop<'s>) {
+        self.0.device_ptr_mut(stream)
+    }
+}
+
+impl CudaStream {
+    /// Performs all copies in `ops` on this stream, e.g. to scatter many small host buffers
+    /// into device memory.
+    ///
+    /// When built for cuda 12.8 or newer and the installed driver supports it too, this is a
+    /// single `cuMemcpyBatchAsync` call, otherwise (and on the legacy default stream, which the
+    /// batch API does not support) each op is submitted separately. Event tracking applies to every slice involved, as with [CudaStream::memcpy_htod()].
+    ///
+    /// Like [CudaStream::memcpy_htod()]/[CudaStream::memcpy_dtoh()] with pageable host memory
+    /// (a `[T]` or `Vec<T>` rather than a [super::PinnedHostSlice]), this blocks until those
+    /// copies are done, so the batch call synchronizes the stream before returning if any op
+    /// uses pageable memory.
+    ///
+    /// Fails with [sys::CUresult::CUDA_ERROR_INVALID_VALUE] before anything is submitted if
+    /// any destination is shorter than its source.
+    ///
+    /// Example:
+    /// ```ignore
+    /// stream.memcpy_batch(&mut [
+    ///     CopyOp::htod(&weights, &mut weights_dev),
+    ///     CopyOp::htod(&bias, &mut bias_dev),
+    ///     CopyOp::dtoh(&stats_dev, &mut stats),
+    /// ])?;
+    /// ```
+    pub fn memcpy_batch(self: &Arc<Self>, ops: &mut [CopyOp<'_>]) -> Result<(), DriverError> {
+        if ops.iter().any(|op| op.dst.len() < op.src.len()) {
+            return Err(DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE));
+        }
+        self.ctx.bind_to_thread()?;
+
+        let mut dsts = Vec::with_capacity(ops.len());
+        let mut srcs = Vec::with_capacity(ops.len());
+        let mut sizes = Vec::with_capacity(ops.len());
+        // Held until everything is submitted, then records all events.
+        let mut records = Vec::with_capacity(2 * ops.len());
+        for op in ops.iter_mut() {
+            let num_bytes = op.src.num_bytes();
+            let (src, record_src) = unsafe { op.src.addr(self) };
+            let (dst, record_dst) = unsafe { op.dst.addr_mut(self) };
+            records.push(record_src);
+            records.push(record_dst);
+            if num_bytes > 0 {
+                dsts.push(dst);
+                srcs.push(src);
+                sizes.push(num_bytes);
+            }
+        }
+        // E.g. memory of a reset context.
+        self.ctx.check_err()?;
+        if sizes.is_empty() {
+            return Ok(());
+        }
+
+        #[cfg(any(
+            feature = "cuda-12080",
+            feature = "cuda-12090",
+            feature = "cuda-13000",
+            feature = "cuda-13010",
+            feature = "cuda-13020"
+        ))]
+        if !self.cu_stream.is_null() && result::version()? >= 12080 {
+            // Pageable host memory has no events, and individual copies of it don't return
+            // before the host side is done. The batched copy does, so wait for it instead
+            // before the borrows end.
+            for record in records.iter_mut() {
+                if matches!(record, SyncOnDrop::Sync(None)) {
+                    *record = SyncOnDrop::sync_stream(self);
+                }
+            }
+            let mut attrs = [unsafe { std::mem::zeroed::<sys::CUmemcpyAttributes>() }];
+            attrs[0].srcAccessOrder =
+                sys::CUmemcpySrcAccessOrder::CU_MEMCPY_SRC_ACCESS_ORDER_STREAM;
+            let mut attr_idxs = [0];
+            return unsafe {
+                result::memcpy_batch_async(
+                    &mut dsts,
+                    &mut srcs,
+                    &mut sizes,
+                    &mut attrs,
+                    &mut attr_idxs,
+                    self.cu_stream,
+                )
+            };
+        }
+
+        for ((dst, src), num_bytes) in dsts.into_iter().zip(srcs).zip(sizes) {
+            unsafe { result::memcpy_async(dst, src, num_bytes, self.cu_stream) }?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::CudaContext;
+
+    use super::*;
+
+    #[test]
+    fn test_memcpy_batch() {
+        let ctx = CudaContext::new(0).unwrap();
+        for stream in [ctx.default_stream(), ctx.new_stream().unwrap()] {
+            let a_host = std::vec![1.0f32, 2.0, 3.0];
+            let b_host = [4u8, 5, 6, 7, 8];
+            let mut a = stream.alloc_zeros::<f32>(3).unwrap();
+            let mut b = stream.alloc_zeros::<u8>(8).unwrap();
+            let mut c = stream.alloc_zeros::<f32>(3).unwrap();
+            let mut b_back = [0u8; 5];
+            stream
+                .memcpy_batch(&mut [CopyOp::htod(&a_host, &mut a), CopyOp::htod(&b_host, &mut b)])
+                .unwrap();
+            stream
+                .memcpy_batch(&mut [
+                    CopyOp::dtod(&a, &mut c),
+                    CopyOp::dtoh(&b.slice(0..5), &mut b_back),
+                ])
+                .unwrap();
+            // No synchronize, `b_back` has to be written once the batch returns.
+            assert_eq!(b_back, b_host);
+            assert_eq!(stream.clone_dtoh(&c).unwrap(), a_host);
+            assert_eq!(stream.clone_dtoh(&b).unwrap(), [4, 5, 6, 7, 8, 0, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn test_memcpy_batch_validates_lengths() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+        let mut a = stream.alloc_zeros::<f32>(2).unwrap();
+        let mut b = stream.alloc_zeros::<f32>(4).unwrap();
+        let result = stream.memcpy_batch(&mut [
+            CopyOp::htod(&[1.0f32; 4], &mut b),
+            CopyOp::htod(&[1.0f32; 4], &mut a),
+        ]);
+        assert_eq!(
+            result,
+            Err(DriverError(sys::CUresult::CUDA_ERROR_INVALID_VALUE))
+        );
+        // Nothing was submitted.
+        assert_eq!(stream.clone_dtoh(&b).unwrap(), [0.0; 4]);
+    }
+}
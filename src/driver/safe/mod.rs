@@ -1,20 +1,73 @@
 //! Safe abstractions over [crate::driver::result] provided by [CudaSlice], [CudaContext], [CudaStream], and more.
 
+#[cfg(feature = "alloc-tracking")]
+pub(crate) mod alloc_tracking;
 pub(crate) mod core;
+#[cfg(any(
+    feature = "cuda-12010",
+    feature = "cuda-12020",
+    feature = "cuda-12030",
+    feature = "cuda-12040",
+    feature = "cuda-12050",
+    feature = "cuda-12060",
+    feature = "cuda-12080",
+    feature = "cuda-12090",
+    feature = "cuda-13000",
+    feature = "cuda-13010",
+    feature = "cuda-13020"
+))]
+pub(crate) mod coredump;
+#[cfg(feature = "dlpack")]
+pub(crate) mod dlpack;
 pub(crate) mod external_memory;
+pub(crate) mod global;
 pub(crate) mod graph;
+pub(crate) mod graph_info;
 pub(crate) mod launch;
+pub(crate) mod mem_ops;
+pub(crate) mod memcpy_batch;
 pub(crate) mod profile;
+pub(crate) mod registered_memory;
+pub(crate) mod sharded;
 pub(crate) mod unified_memory;
 
+#[cfg(feature = "alloc-tracking")]
+pub use self::alloc_tracking::{report_live_allocations, AllocationInfo, AllocationKind};
 pub use self::core::{
-    CudaContext, CudaEvent, CudaFunction, CudaModule, CudaSlice, CudaStream, CudaView, CudaViewMut,
-    DevicePtr, DevicePtrMut, DeviceRepr, DeviceSlice, HostSlice, PinnedHostSlice, SyncOnDrop,
-    ValidAsZeroBits,
+    ContextHealth, CudaContext, CudaEvent, CudaFunction, CudaModule, CudaSlice, CudaStream,
+    CudaView, CudaViewMut, DevicePtr, DevicePtrMut, DeviceRepr, DeviceSlice, HostSlice,
+    PinnedHostSlice, SyncOnDrop, ValidAsZeroBits,
+};
+#[cfg(any(
+    feature = "cuda-12010",
+    feature = "cuda-12020",
+    feature = "cuda-12030",
+    feature = "cuda-12040",
+    feature = "cuda-12050",
+    feature = "cuda-12060",
+    feature = "cuda-12080",
+    feature = "cuda-12090",
+    feature = "cuda-13000",
+    feature = "cuda-13010",
+    feature = "cuda-13020"
+))]
+pub use self::coredump::CoredumpConfig;
+#[cfg(feature = "dlpack")]
+pub use self::dlpack::{
+    DLDataType, DLDevice, DLDeviceType, DLManagedTensor, DLPackError, DLPackExportError,
+    DLPackTensor, DLPackType, DLTensor,
 };
 pub use self::external_memory::{ExternalMemory, MappedBuffer};
-pub use self::graph::CudaGraph;
+pub use self::global::{CudaGlobal, GlobalError};
+pub use self::graph::{CaptureGuard, CaptureInfo, CudaGraph, GraphMemUsage, GraphSlice};
+pub use self::graph_info::{GraphInfo, GraphNodeInfo, KernelNodeInfo};
 pub use self::launch::{LaunchArgs, LaunchConfig, PushKernelArg};
+pub use self::mem_ops::{StreamValue, WaitCond};
+pub use self::memcpy_batch::CopyOp;
 pub use self::profile::{profiler_start, profiler_stop, Profiler};
+pub use self::registered_memory::RegisteredHostSlice;
+pub use self::sharded::{ShardSpec, ShardedSlice};
 pub use self::unified_memory::{UnifiedSlice, UnifiedView, UnifiedViewMut};
 pub use crate::driver::result::DriverError;
+#[cfg(feature = "derive")]
+pub use cudarc_derive::{DeviceRepr, ValidAsZeroBits};
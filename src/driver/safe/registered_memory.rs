@@ -0,0 +1,338 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use crate::driver::{result, sys, DriverError};
+
+use super::{
+    CudaContext, CudaEvent, CudaStream, CudaView, CudaViewMut, DeviceRepr, HostSlice,
+    PinnedHostSlice, SyncOnDrop,
+};
+
+/// Existing host memory (e.g. a `Vec<T>` or a memory mapped file) that has been page locked
+/// in place with `cuMemHostRegister`. The memory is unregistered on drop, and the borrow
+/// of the original buffer ends.
+///
+/// Create with [CudaContext::register_host()] or [CudaContext::register_host_with_flags()],
+/// and do device copies with [CudaStream::clone_htod()]/[CudaStream::memcpy_htod()]/[CudaStream::memcpy_dtoh()].
+///
+/// If registered with [sys::CU_MEMHOSTREGISTER_DEVICEMAP], kernels can access the memory
+/// directly through [RegisteredHostSlice::device_view()]/[RegisteredHostSlice::device_view_mut()].
+#[derive(Debug)]
+pub struct RegisteredHostSlice<'a, T> {
+    pub(crate) ptr: *mut T,
+    pub(crate) len: usize,
+    /// Always `Some`. Stored as an option so device views can borrow it.
+    pub(crate) event: Option<CudaEvent>,
+    pub(crate) ctx: Arc<CudaContext>,
+    marker: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for RegisteredHostSlice<'_, T> {}
+unsafe impl<T: Sync> Sync for RegisteredHostSlice<'_, T> {}
+
+impl<T> Drop for RegisteredHostSlice<'_, T> {
+    fn drop(&mut self) {
+        // The reset already destroyed this.
+        if self.ctx.is_invalidated() {
+            return;
+        }
+        self.ctx.record_err(self.ctx.bind_to_thread());
+        if let Some(event) = self.event.as_ref() {
+            self.ctx.record_err(event.synchronize());
+        }
+        self.ctx
+            .record_err(unsafe { result::host_unregister(self.ptr as _) });
+    }
+}
+
+impl CudaContext {
+    /// Page locks `buf` in place, without copying it. Equivalent to
+    /// [CudaContext::register_host_with_flags()] with [sys::CU_MEMHOSTREGISTER_PORTABLE].
+    pub fn register_host<'a, T: DeviceRepr>(
+        self: &Arc<Self>,
+        buf: &'a mut [T],
+    ) -> Result<RegisteredHostSlice<'a, T>, DriverError> {
+        self.register_host_with_flags(buf, sys::CU_MEMHOSTREGISTER_PORTABLE)
+    }
+
+    /// Page locks `buf` in place, without copying it. `flags` is a combination of
+    /// [sys::CU_MEMHOSTREGISTER_PORTABLE], [sys::CU_MEMHOSTREGISTER_DEVICEMAP],
+    /// [sys::CU_MEMHOSTREGISTER_IOMEMORY] and [sys::CU_MEMHOSTREGISTER_READ_ONLY].
+    ///
+    /// Registering is expensive relative to a copy, so it pays off for large buffers
+    /// that are transferred repeatedly, or that should not be duplicated in memory.
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1gf0a9fe11544326dabd743b7aa6b54223)
+    pub fn register_host_with_flags<'a, T: DeviceRepr>(
+        self: &Arc<Self>,
+        buf: &'a mut [T],
+        flags: u32,
+    ) -> Result<RegisteredHostSlice<'a, T>, DriverError> {
+        self.bind_to_thread()?;
+        let ptr = buf.as_mut_ptr();
+        let len = buf.len();
+        unsafe { result::host_register(ptr as _, std::mem::size_of_val(buf), flags) }?;
+        let event = match self.new_event(Some(sys::CUevent_flags::CU_EVENT_BLOCKING_SYNC)) {
+            Ok(event) => event,
+            Err(err) => {
+                self.record_err(unsafe { result::host_unregister(ptr as _) });
+                return Err(err);
+            }
+        };
+        Ok(RegisteredHostSlice {
+            ptr,
+            len,
+            event: Some(event),
+            ctx: self.clone(),
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<T> RegisteredHostSlice<'_, T> {
+    /// The context this was registered in.
+    pub fn context(&self) -> &Arc<CudaContext> {
+        &self.ctx
+    }
+
+    /// The number of elements `T` in this slice.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of bytes in this slice.
+    pub fn num_bytes(&self) -> usize {
+        self.len * std::mem::size_of::<T>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Waits for any scheduled work to complete and then returns a reference
+    /// to the host side data.
+    pub fn as_slice(&self) -> Result<&[T], DriverError> {
+        self.synchronize()?;
+        Ok(unsafe { std::slice::from_raw_parts(self.ptr, self.len) })
+    }
+
+    /// Waits for any scheduled work to complete and then returns a reference
+    /// to the host side data.
+    pub fn as_mut_slice(&mut self) -> Result<&mut [T], DriverError> {
+        self.synchronize()?;
+        Ok(unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) })
+    }
+
+    /// A device side view of the host memory, that kernels can read from directly.
+    ///
+    /// Fails with [sys::cudaError_enum::CUDA_ERROR_INVALID_VALUE] if the memory was not
+    /// registered with [sys::CU_MEMHOSTREGISTER_DEVICEMAP].
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1g57a39e5cba26af4d06be67fc77cc62f0)
+    pub fn device_view<'b>(
+        &'b self,
+        stream: &'b Arc<CudaStream>,
+    ) -> Result<CudaView<'b, T>, DriverError> {
+        let ptr = self.device_ptr(stream)?;
+        Ok(CudaView {
+            ptr,
+            len: self.len,
+            read: &self.event,
+            write: &self.event,
+            stream,
+            marker: PhantomData,
+        })
+    }
+
+    /// A mutable device side view of the host memory, that kernels can write to directly.
+    ///
+    /// See [RegisteredHostSlice::device_view()].
+    pub fn device_view_mut<'b>(
+        &'b mut self,
+        stream: &'b Arc<CudaStream>,
+    ) -> Result<CudaViewMut<'b, T>, DriverError> {
+        let ptr = self.device_ptr(stream)?;
+        Ok(CudaViewMut {
+            ptr,
+            len: self.len,
+            read: &self.event,
+            write: &self.event,
+            stream,
+            marker: PhantomData,
+        })
+    }
+
+    fn device_ptr(&self, stream: &CudaStream) -> Result<sys::CUdeviceptr, DriverError> {
+        mapped_device_ptr(&self.ctx, self.ptr, stream)
+    }
+
+    fn synchronize(&self) -> Result<(), DriverError> {
+        match self.event.as_ref() {
+            Some(event) => event.synchronize(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> PinnedHostSlice<T> {
+    /// A device side view of the host memory, that kernels can read from directly.
+    ///
+    /// Fails with [sys::cudaError_enum::CUDA_ERROR_INVALID_VALUE] if the memory was not
+    /// allocated with [sys::CU_MEMHOSTALLOC_DEVICEMAP], see
+    /// [CudaContext::alloc_pinned_with_flags()].
+    ///
+    /// See [cuda docs](https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html#group__CUDA__MEM_1g57a39e5cba26af4d06be67fc77cc62f0)
+    pub fn device_view<'b>(
+        &'b self,
+        stream: &'b Arc<CudaStream>,
+    ) -> Result<CudaView<'b, T>, DriverError> {
+        let ptr = mapped_device_ptr(self.context(), self.ptr, stream)?;
+        Ok(CudaView {
+            ptr,
+            len: self.len,
+            read: &self.event,
+            write: &self.event,
+            stream,
+            marker: PhantomData,
+        })
+    }
+
+    /// A mutable device side view of the host memory, that kernels can write to directly.
+    ///
+    /// See [PinnedHostSlice::device_view()].
+    pub fn device_view_mut<'b>(
+        &'b mut self,
+        stream: &'b Arc<CudaStream>,
+    ) -> Result<CudaViewMut<'b, T>, DriverError> {
+        let ptr = mapped_device_ptr(self.context(), self.ptr, stream)?;
+        Ok(CudaViewMut {
+            ptr,
+            len: self.len,
+            read: &self.event,
+            write: &self.event,
+            stream,
+            marker: PhantomData,
+        })
+    }
+}
+
+/// The device pointer of mapped host memory at `ptr`, for use on `stream`.
+fn mapped_device_ptr<T>(
+    ctx: &Arc<CudaContext>,
+    ptr: *mut T,
+    stream: &CudaStream,
+) -> Result<sys::CUdeviceptr, DriverError> {
+    if *ctx != stream.ctx {
+        return Err(DriverError(sys::cudaError_enum::CUDA_ERROR_INVALID_CONTEXT));
+    }
+    ctx.bind_to_thread()?;
+    unsafe { result::host_get_device_pointer(ptr as _) }
+}
+
+impl<T> HostSlice<T> for RegisteredHostSlice<'_, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    unsafe fn stream_synced_slice<'a>(
+        &'a self,
+        stream: &'a CudaStream,
+    ) -> (&'a [T], SyncOnDrop<'a>) {
+        if let Some(event) = self.event.as_ref() {
+            stream.ctx.record_err(stream.wait(event));
+        }
+        (
+            std::slice::from_raw_parts(self.ptr, self.len),
+            SyncOnDrop::record_event(&self.event, stream),
+        )
+    }
+
+    unsafe fn stream_synced_mut_slice<'a>(
+        &'a mut self,
+        stream: &'a CudaStream,
+    ) -> (&'a mut [T], SyncOnDrop<'a>) {
+        if let Some(event) = self.event.as_ref() {
+            stream.ctx.record_err(stream.wait(event));
+        }
+        (
+            std::slice::from_raw_parts_mut(self.ptr, self.len),
+            SyncOnDrop::record_event(&self.event, stream),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_host_roundtrip() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+
+        let mut buf = std::vec![1.0f32, 2.0, 3.0, 4.0];
+        let mut registered = ctx.register_host(&mut buf).unwrap();
+        let dev = stream.clone_htod(&registered).unwrap();
+        registered.as_mut_slice().unwrap().fill(0.0);
+        stream.memcpy_dtoh(&dev, &mut registered).unwrap();
+        assert_eq!(registered.as_slice().unwrap(), &[1.0, 2.0, 3.0, 4.0]);
+        drop(registered);
+        assert_eq!(buf, [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_register_host_device_view() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+
+        let mut buf = std::vec![5u32; 16];
+        let mut registered = ctx
+            .register_host_with_flags(&mut buf, sys::CU_MEMHOSTREGISTER_DEVICEMAP)
+            .unwrap();
+        let dev = stream
+            .clone_dtod(&registered.device_view(&stream).unwrap())
+            .unwrap();
+        assert_eq!(stream.clone_dtoh(&dev).unwrap(), [5u32; 16]);
+
+        let zeros = stream.alloc_zeros::<u32>(16).unwrap();
+        stream
+            .memcpy_dtod(&zeros, &mut registered.device_view_mut(&stream).unwrap())
+            .unwrap();
+        assert_eq!(registered.as_slice().unwrap(), &[0u32; 16]);
+    }
+
+    #[test]
+    fn test_register_host_without_devicemap_has_no_view() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+
+        let mut buf = std::vec![0u8; 16];
+        let registered = ctx.register_host_with_flags(&mut buf, 0).unwrap();
+        // On systems with unified addressing all registered memory is mapped.
+        let unified = ctx
+            .attribute(sys::CUdevice_attribute::CU_DEVICE_ATTRIBUTE_UNIFIED_ADDRESSING)
+            .unwrap()
+            != 0;
+        assert_eq!(registered.device_view(&stream).is_ok(), unified);
+    }
+
+    #[test]
+    fn test_pinned_device_view() {
+        let ctx = CudaContext::new(0).unwrap();
+        let stream = ctx.default_stream();
+
+        let mut pinned =
+            unsafe { ctx.alloc_pinned_with_flags::<u32>(16, sys::CU_MEMHOSTALLOC_DEVICEMAP) }
+                .unwrap();
+        pinned.as_mut_slice().unwrap().fill(7);
+        let dev = stream
+            .clone_dtod(&pinned.device_view(&stream).unwrap())
+            .unwrap();
+        assert_eq!(stream.clone_dtoh(&dev).unwrap(), [7u32; 16]);
+
+        let zeros = stream.alloc_zeros::<u32>(16).unwrap();
+        stream
+            .memcpy_dtod(&zeros, &mut pinned.device_view_mut(&stream).unwrap())
+            .unwrap();
+        assert_eq!(pinned.as_slice().unwrap(), &[0u32; 16]);
+    }
+}
@@ -0,0 +1,498 @@
+//! A logical buffer split over several devices, see [ShardedSlice].
+
+use core::ops::Range;
+use std::{sync::Arc, vec::Vec};
+
+use super::{CudaSlice, CudaStream, DeviceRepr, DriverError, ValidAsZeroBits};
+
+/// How the elements of a [ShardedSlice] are distributed over its shards.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShardSpec {
+    /// Contiguous chunks of (almost) equal size. When the length doesn't divide evenly,
+    /// the first `len % num_shards` shards hold one extra element.
+    Even,
+    /// Explicit contiguous ranges, one per shard. They must be in order and cover `0..len`
+    /// without gaps or overlaps. Empty ranges are allowed.
+    Ranges(Vec<Range<usize>>),
+    /// Every shard holds a full copy.
+    Replicated,
+}
+
+impl ShardSpec {
+    /// The range of the logical buffer that each shard holds.
+    ///
+    /// Panics if this is [ShardSpec::Ranges] and they don't describe `num_shards` contiguous
+    /// ranges covering `0..len`, or if `num_shards` is 0.
+    pub fn ranges(&self, len: usize, num_shards: usize) -> Vec<Range<usize>> {
+        assert!(num_shards > 0, "a sharded slice needs at least one shard");
+        match self {
+            ShardSpec::Even => {
+                let base = len / num_shards;
+                let rem = len % num_shards;
+                let mut start = 0;
+                (0..num_shards)
+                    .map(|i| {
+                        let end = start + base + usize::from(i < rem);
+                        let range = start..end;
+                        start = end;
+                        range
+                    })
+                    .collect()
+            }
+            ShardSpec::Ranges(ranges) => {
+                assert_eq!(
+                    ranges.len(),
+                    num_shards,
+                    "ShardSpec::Ranges has {} ranges for {num_shards} shards",
+                    ranges.len()
+                );
+                let mut expected_start = 0;
+                for range in ranges.iter() {
+                    assert!(
+                        range.start == expected_start && range.start <= range.end,
+                        "ShardSpec::Ranges {ranges:?} are not contiguous from 0"
+                    );
+                    expected_start = range.end;
+                }
+                assert_eq!(
+                    expected_start, len,
+                    "ShardSpec::Ranges {ranges:?} do not cover 0..{len}"
+                );
+                ranges.clone()
+            }
+            ShardSpec::Replicated => std::vec![0..len; num_shards],
+        }
+    }
+}
+
+/// A single device to device copy of [ShardedSlice::reshard()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ShardCopy {
+    pub(crate) src_shard: usize,
+    /// Range within the source shard.
+    pub(crate) src: Range<usize>,
+    pub(crate) dst_shard: usize,
+    /// Range within the destination shard.
+    pub(crate) dst: Range<usize>,
+}
+
+/// The copies needed to move data from shards holding `src` ranges to shards holding `dst`
+/// ranges. If `src_replicated`, every source shard holds everything, and each destination
+/// shard copies from its own device's source shard.
+pub(crate) fn reshard_plan(
+    src: &[Range<usize>],
+    src_replicated: bool,
+    dst: &[Range<usize>],
+) -> Vec<ShardCopy> {
+    let mut copies = Vec::new();
+    for (dst_shard, dst_range) in dst.iter().enumerate() {
+        if dst_range.is_empty() {
+            continue;
+        }
+        if src_replicated {
+            let src_shard = dst_shard.min(src.len() - 1);
+            copies.push(ShardCopy {
+                src_shard,
+                src: dst_range.clone(),
+                dst_shard,
+                dst: 0..dst_range.len(),
+            });
+            continue;
+        }
+        for (src_shard, src_range) in src.iter().enumerate() {
+            let start = src_range.start.max(dst_range.start);
+            let end = src_range.end.min(dst_range.end);
+            if start < end {
+                copies.push(ShardCopy {
+                    src_shard,
+                    src: start - src_range.start..end - src_range.start,
+                    dst_shard,
+                    dst: start - dst_range.start..end - dst_range.start,
+                });
+            }
+        }
+    }
+    copies
+}
+
+/// A logical buffer of `len` elements of `T` split over several devices (or streams), with
+/// one [CudaSlice] per shard. The layout is described by a [ShardSpec].
+///
+/// Each shard is allocated on, and all work for it is submitted to, its own stream, so
+/// copies to/from different devices run concurrently. Device to device transfers use
+/// [CudaStream::memcpy_dtod()], which does peer copies between contexts.
+///
+/// Example:
+/// ```ignore
+/// let streams = [CudaContext::new(0)?.new_stream()?, CudaContext::new(1)?.new_stream()?];
+/// let mut x = ShardedSlice::from_host(&streams, &data, ShardSpec::Even)?;
+/// // ... launch one kernel per shard on x.shards_mut() ...
+/// let y = x.reshard(ShardSpec::Replicated)?;
+/// ```
+#[derive(Debug)]
+pub struct ShardedSlice<T> {
+    pub(crate) shards: Vec<CudaSlice<T>>,
+    pub(crate) ranges: Vec<Range<usize>>,
+    pub(crate) spec: ShardSpec,
+    pub(crate) len: usize,
+}
+
+impl<T: DeviceRepr> ShardedSlice<T> {
+    /// Allocates a sharded buffer with one shard per stream in `streams`.
+    ///
+    /// # Safety
+    /// This is unsafe because the memory is unset.
+    pub unsafe fn alloc(
+        streams: &[Arc<CudaStream>],
+        len: usize,
+        spec: ShardSpec,
+    ) -> Result<Self, DriverError> {
+        let ranges = spec.ranges(len, streams.len());
+        let shards = streams
+            .iter()
+            .zip(ranges.iter())
+            .map(|(stream, range)| stream.alloc::<T>(range.len()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            shards,
+            ranges,
+            spec,
+            len,
+        })
+    }
+
+    /// Allocates a sharded buffer with all values zero'd out. See [ShardedSlice::alloc()].
+    pub fn alloc_zeros(
+        streams: &[Arc<CudaStream>],
+        len: usize,
+        spec: ShardSpec,
+    ) -> Result<Self, DriverError>
+    where
+        T: ValidAsZeroBits,
+    {
+        let mut slice = unsafe { Self::alloc(streams, len, spec) }?;
+        for shard in slice.shards.iter_mut() {
+            let stream = shard.stream().clone();
+            stream.memset_zeros(shard)?;
+        }
+        Ok(slice)
+    }
+
+    /// Allocates a sharded buffer and scatters `src` into it.
+    pub fn from_host(
+        streams: &[Arc<CudaStream>],
+        src: &[T],
+        spec: ShardSpec,
+    ) -> Result<Self, DriverError> {
+        let mut slice = unsafe { Self::alloc(streams, src.len(), spec) }?;
+        slice.scatter_from_host(src)?;
+        Ok(slice)
+    }
+
+    /// Copies each shard's part of `src` into it. Panics if `src.len() != self.len()`.
+    ///
+    /// Each part is staged through page locked memory, so the copies to different devices
+    /// run concurrently. Returns once all of them are done.
+    pub fn scatter_from_host(&mut self, src: &[T]) -> Result<(), DriverError> {
+        assert_eq!(src.len(), self.len);
+        let mut staging = Vec::with_capacity(self.shards.len());
+        for (shard, range) in self.shards.iter_mut().zip(self.ranges.iter()) {
+            if range.is_empty() {
+                continue;
+            }
+            let stream = shard.stream().clone();
+            let pinned = unsafe { stream.context().alloc_pinned::<T>(range.len()) }?;
+            unsafe {
+                std::ptr::copy_nonoverlapping(src[range.clone()].as_ptr(), pinned.ptr, range.len())
+            };
+            stream.memcpy_htod(&pinned, shard)?;
+            staging.push(pinned);
+        }
+        for pinned in staging.iter() {
+            pinned.event().synchronize()?;
+        }
+        Ok(())
+    }
+
+    /// Copies the logical buffer into `dst`. For [ShardSpec::Replicated] the first shard is
+    /// used. Panics if `dst.len() != self.len()`.
+    ///
+    /// Like [ShardedSlice::scatter_from_host()], the copies from all shards are issued before
+    /// waiting on any of them.
+    pub fn gather_into_host(&self, dst: &mut [T]) -> Result<(), DriverError> {
+        assert_eq!(dst.len(), self.len);
+        let parts = if self.spec == ShardSpec::Replicated {
+            &self.ranges[..1]
+        } else {
+            &self.ranges[..]
+        };
+        let mut staging = Vec::with_capacity(parts.len());
+        for (shard, range) in self.shards.iter().zip(parts.iter()) {
+            if range.is_empty() {
+                continue;
+            }
+            let stream = shard.stream();
+            let mut pinned = unsafe {
+                stream
+                    .context()
+                    .alloc_pinned_with_flags::<T>(range.len(), 0)
+            }?;
+            stream.memcpy_dtoh(shard, &mut pinned)?;
+            staging.push((range.clone(), pinned));
+        }
+        for (range, pinned) in staging.iter() {
+            pinned.event().synchronize()?;
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    pinned.ptr,
+                    dst[range.clone()].as_mut_ptr(),
+                    range.len(),
+                )
+            };
+        }
+        Ok(())
+    }
+
+    /// Copies the logical buffer to a new [`Vec<T>`]. See [ShardedSlice::gather_into_host()].
+    pub fn gather_to_host(&self) -> Result<Vec<T>, DriverError> {
+        let mut dst = Vec::with_capacity(self.len);
+        #[allow(clippy::uninit_vec)]
+        unsafe {
+            dst.set_len(self.len)
+        };
+        self.gather_into_host(&mut dst)?;
+        Ok(dst)
+    }
+}
+
+impl<T> ShardedSlice<T> {
+    /// The number of elements in the logical buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn spec(&self) -> &ShardSpec {
+        &self.spec
+    }
+
+    /// The range of the logical buffer that each shard holds.
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    pub fn shards(&self) -> &[CudaSlice<T>] {
+        &self.shards
+    }
+
+    pub fn shards_mut(&mut self) -> &mut [CudaSlice<T>] {
+        &mut self.shards
+    }
+
+    /// The stream of each shard.
+    pub fn streams(&self) -> Vec<Arc<CudaStream>> {
+        self.shards.iter().map(|s| s.stream().clone()).collect()
+    }
+
+    /// Splits this into its shards.
+    pub fn into_shards(self) -> Vec<CudaSlice<T>> {
+        self.shards
+    }
+
+    /// Waits for all work on every shard's stream to complete.
+    pub fn synchronize(&self) -> Result<(), DriverError> {
+        for shard in self.shards.iter() {
+            shard.stream().synchronize()?;
+        }
+        Ok(())
+    }
+
+    /// Copies shard `src` to every other shard. Panics unless this is [ShardSpec::Replicated].
+    ///
+    /// The copies run concurrently on the destination shards' streams. The stream of shard
+    /// `src` then waits for all of them, so later work on it can't overwrite the data while
+    /// it is being copied.
+    pub fn replicate_from(&mut self, src: usize) -> Result<(), DriverError> {
+        assert_eq!(
+            self.spec,
+            ShardSpec::Replicated,
+            "replicate_from() requires ShardSpec::Replicated"
+        );
+        let (before, rest) = self.shards.split_at_mut(src);
+        let (src_shard, after) = rest.split_first_mut().unwrap();
+        for dst in before.iter_mut().chain(after.iter_mut()) {
+            let stream = dst.stream().clone();
+            stream.memcpy_dtod(src_shard, dst)?;
+        }
+        for dst in before.iter().chain(after.iter()) {
+            src_shard.stream().join(dst.stream())?;
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of this buffer laid out according to `spec`, with shards on the same
+    /// streams. Data moves directly between devices.
+    ///
+    /// All copies are issued on the destination shards' streams before any source shard's
+    /// stream waits for the copies reading from it, so transfers between different devices
+    /// run concurrently.
+    pub fn reshard(&self, spec: ShardSpec) -> Result<Self, DriverError>
+    where
+        T: DeviceRepr,
+    {
+        let streams = self.streams();
+        let mut dst = unsafe { Self::alloc(&streams, self.len, spec) }?;
+        let plan = reshard_plan(
+            &self.ranges,
+            self.spec == ShardSpec::Replicated,
+            &dst.ranges,
+        );
+        let mut joins = Vec::with_capacity(plan.len());
+        for copy in plan {
+            let src = self.shards[copy.src_shard].slice(copy.src);
+            let dst_shard = &mut dst.shards[copy.dst_shard];
+            let stream = dst_shard.stream().clone();
+            stream.memcpy_dtod(&src, &mut dst_shard.slice_mut(copy.dst))?;
+            joins.push((copy.src_shard, copy.dst_shard));
+        }
+        joins.sort_unstable();
+        joins.dedup();
+        for (src_shard, dst_shard) in joins {
+            self.shards[src_shard]
+                .stream()
+                .join(dst.shards[dst_shard].stream())?;
+        }
+        Ok(dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::CudaContext;
+
+    use super::*;
+
+    #[test]
+    fn test_even_ranges() {
+        assert_eq!(ShardSpec::Even.ranges(10, 3), [0..4, 4..7, 7..10]);
+        assert_eq!(ShardSpec::Even.ranges(9, 3), [0..3, 3..6, 6..9]);
+        assert_eq!(ShardSpec::Even.ranges(2, 4), [0..1, 1..2, 2..2, 2..2]);
+        assert_eq!(ShardSpec::Even.ranges(0, 2), [0..0, 0..0]);
+        assert_eq!(ShardSpec::Replicated.ranges(5, 2), [0..5, 0..5]);
+    }
+
+    #[test]
+    fn test_explicit_ranges() {
+        let spec = ShardSpec::Ranges(std::vec![0..2, 2..2, 2..7]);
+        assert_eq!(spec.ranges(7, 3), [0..2, 2..2, 2..7]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_explicit_ranges_with_gap() {
+        ShardSpec::Ranges(std::vec![0..2, 3..7]).ranges(7, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_explicit_ranges_too_short() {
+        ShardSpec::Ranges(std::vec![0..2, 2..6]).ranges(7, 2);
+    }
+
+    #[test]
+    fn test_reshard_plan() {
+        let plan = reshard_plan(&[0..4, 4..8], false, &[0..2, 2..6, 6..8]);
+        assert_eq!(
+            plan,
+            [
+                ShardCopy {
+                    src_shard: 0,
+                    src: 0..2,
+                    dst_shard: 0,
+                    dst: 0..2
+                },
+                ShardCopy {
+                    src_shard: 0,
+                    src: 2..4,
+                    dst_shard: 1,
+                    dst: 0..2
+                },
+                ShardCopy {
+                    src_shard: 1,
+                    src: 0..2,
+                    dst_shard: 1,
+                    dst: 2..4
+                },
+                ShardCopy {
+                    src_shard: 1,
+                    src: 2..4,
+                    dst_shard: 2,
+                    dst: 0..2
+                },
+            ]
+        );
+
+        // From replicated, every shard copies locally.
+        let plan = reshard_plan(&[0..8, 0..8], true, &[0..3, 3..8]);
+        assert_eq!(
+            plan,
+            [
+                ShardCopy {
+                    src_shard: 0,
+                    src: 0..3,
+                    dst_shard: 0,
+                    dst: 0..3
+                },
+                ShardCopy {
+                    src_shard: 1,
+                    src: 3..8,
+                    dst_shard: 1,
+                    dst: 0..5
+                },
+            ]
+        );
+
+        // To replicated, every shard gathers from all source shards.
+        let plan = reshard_plan(&[0..3, 3..5], false, &[0..5, 0..5]);
+        assert_eq!(plan.len(), 4);
+        assert!(plan.iter().all(|c| c.src.len() == c.dst.len()));
+    }
+
+    #[test]
+    fn test_sharded_slice() {
+        let ctx = CudaContext::new(0).unwrap();
+        let streams = [ctx.new_stream().unwrap(), ctx.new_stream().unwrap()];
+        let data: Vec<u32> = (0..11).collect();
+
+        let even = ShardedSlice::from_host(&streams, &data, ShardSpec::Even).unwrap();
+        assert_eq!(even.ranges(), [0..6, 6..11]);
+        assert_eq!(even.shards()[1].len(), 5);
+        assert_eq!(even.gather_to_host().unwrap(), data);
+
+        let mut replicated = even.reshard(ShardSpec::Replicated).unwrap();
+        for shard in replicated.shards() {
+            assert_eq!(shard.stream().clone_dtoh(shard).unwrap(), data);
+        }
+
+        streams[0]
+            .memset_zeros(&mut replicated.shards_mut()[0])
+            .unwrap();
+        replicated.replicate_from(0).unwrap();
+        assert_eq!(
+            streams[1].clone_dtoh(&replicated.shards()[1]).unwrap(),
+            [0; 11]
+        );
+
+        let explicit = even
+            .reshard(ShardSpec::Ranges(std::vec![0..1, 1..11]))
+            .unwrap();
+        assert_eq!(explicit.gather_to_host().unwrap(), data);
+    }
+}
@@ -37,6 +37,8 @@ pub struct UnifiedSlice<T> {
     pub(crate) event: CudaEvent,
     pub(crate) attach_mode: sys::CUmemAttach_flags,
     pub(crate) concurrent_managed_access: bool,
+    #[cfg(feature = "alloc-tracking")]
+    pub(crate) alloc_id: u64,
     pub(crate) marker: PhantomData<*const T>,
 }
 
@@ -45,6 +47,12 @@ unsafe impl<T> Sync for UnifiedSlice<T> {}
 
 impl<T> Drop for UnifiedSlice<T> {
     fn drop(&mut self) {
+        #[cfg(feature = "alloc-tracking")]
+        self.stream.ctx.allocations.remove(self.alloc_id);
+        // The reset already destroyed this.
+        if self.stream.ctx.is_invalidated() {
+            return;
+        }
         self.stream.ctx.record_err(self.event.synchronize());
         self.stream
             .ctx
@@ -115,6 +123,14 @@ impl CudaContext {
         let stream = self.default_stream();
         let event = self.new_event(Some(sys::CUevent_flags::CU_EVENT_BLOCKING_SYNC))?;
 
+        #[cfg(feature = "alloc-tracking")]
+        let alloc_id = self.allocations.insert::<T>(
+            super::alloc_tracking::AllocationKind::Unified,
+            cu_device_ptr,
+            len,
+            Some(stream.cu_stream),
+        );
+
         Ok(UnifiedSlice {
             cu_device_ptr,
             len,
@@ -122,6 +138,8 @@ impl CudaContext {
             event,
             attach_mode,
             concurrent_managed_access,
+            #[cfg(feature = "alloc-tracking")]
+            alloc_id,
             marker: PhantomData,
         })
     }
@@ -419,6 +437,9 @@ impl<T> DevicePtr<T> for UnifiedSlice<T> {
         &'a self,
         stream: &'a CudaStream,
     ) -> (sys::CUdeviceptr, super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (0, super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(self.check_device_access(stream));
         stream.ctx.record_err(stream.wait(&self.event));
         (
@@ -433,6 +454,9 @@ impl<T> DevicePtrMut<T> for UnifiedSlice<T> {
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (sys::CUdeviceptr, super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (0, super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(self.check_device_access(stream));
         stream.ctx.record_err(stream.wait(&self.event));
         (
@@ -446,6 +470,7 @@ impl<T: ValidAsZeroBits> UnifiedSlice<T> {
     /// Waits for any scheduled work to complete and then returns a refernce
     /// to the host side data.
     pub fn as_slice(&self) -> Result<&[T], DriverError> {
+        self.stream.ctx.check_not_reset()?;
         self.check_host_access()?;
         self.event.synchronize()?;
         Ok(unsafe { std::slice::from_raw_parts(self.cu_device_ptr as *const T, self.len) })
@@ -454,6 +479,7 @@ impl<T: ValidAsZeroBits> UnifiedSlice<T> {
     /// Waits for any scheduled work to complete and then returns a refernce
     /// to the host side data.
     pub fn as_mut_slice(&mut self) -> Result<&mut [T], DriverError> {
+        self.stream.ctx.check_not_reset()?;
         self.check_host_access()?;
         self.event.synchronize()?;
         Ok(unsafe { std::slice::from_raw_parts_mut(self.cu_device_ptr as *mut T, self.len) })
@@ -468,6 +494,9 @@ impl<T> HostSlice<T> for UnifiedSlice<T> {
         &'a self,
         stream: &'a CudaStream,
     ) -> (&'a [T], super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (&[], super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(self.check_device_access(stream));
         stream.ctx.record_err(stream.wait(&self.event));
         (
@@ -480,6 +509,9 @@ impl<T> HostSlice<T> for UnifiedSlice<T> {
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (&'a mut [T], super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (&mut [], super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(self.check_device_access(stream));
         stream.ctx.record_err(stream.wait(&self.event));
         (
@@ -492,13 +524,13 @@ impl<T> HostSlice<T> for UnifiedSlice<T> {
 unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b UnifiedSlice<T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b UnifiedSlice<T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         self.stream
             .ctx
             .record_err(arg.check_device_access(self.stream));
         self.waits.push(&arg.event);
         self.records.push(&arg.event);
-        self.args
-            .push((&arg.cu_device_ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.cu_device_ptr);
         self
     }
 }
@@ -506,13 +538,13 @@ unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b UnifiedSlice<T>> for LaunchArgs<'a>
 unsafe impl<'a, 'b: 'a, T> PushKernelArg<&'b mut UnifiedSlice<T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b mut UnifiedSlice<T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         self.stream
             .ctx
             .record_err(arg.check_device_access(self.stream));
         self.waits.push(&arg.event);
         self.records.push(&arg.event);
-        self.args
-            .push((&arg.cu_device_ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.cu_device_ptr);
         self
     }
 }
@@ -540,6 +572,9 @@ impl<T> DevicePtr<T> for UnifiedView<'_, T> {
         &'a self,
         stream: &'a CudaStream,
     ) -> (sys::CUdeviceptr, super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (0, super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(check_device_access(
             self.attach_mode,
             self.stream,
@@ -559,6 +594,9 @@ impl<T> DevicePtr<T> for UnifiedViewMut<'_, T> {
         &'a self,
         stream: &'a CudaStream,
     ) -> (sys::CUdeviceptr, super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (0, super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(check_device_access(
             self.attach_mode,
             self.stream,
@@ -578,6 +616,9 @@ impl<T> DevicePtrMut<T> for UnifiedViewMut<'_, T> {
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (sys::CUdeviceptr, super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (0, super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(check_device_access(
             self.attach_mode,
             self.stream,
@@ -600,6 +641,9 @@ impl<T> HostSlice<T> for UnifiedView<'_, T> {
         &'a self,
         stream: &'a CudaStream,
     ) -> (&'a [T], super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (&[], super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(check_device_access(
             self.attach_mode,
             self.stream,
@@ -617,6 +661,9 @@ impl<T> HostSlice<T> for UnifiedView<'_, T> {
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (&'a mut [T], super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (&mut [], super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(check_device_access(
             self.attach_mode,
             self.stream,
@@ -639,6 +686,9 @@ impl<T> HostSlice<T> for UnifiedViewMut<'_, T> {
         &'a self,
         stream: &'a CudaStream,
     ) -> (&'a [T], super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (&[], super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(check_device_access(
             self.attach_mode,
             self.stream,
@@ -656,6 +706,9 @@ impl<T> HostSlice<T> for UnifiedViewMut<'_, T> {
         &'a mut self,
         stream: &'a CudaStream,
     ) -> (&'a mut [T], super::SyncOnDrop<'a>) {
+        if super::core::record_if_reset(&self.stream.ctx, stream) {
+            return (&mut [], super::SyncOnDrop::Record(None));
+        }
         stream.ctx.record_err(check_device_access(
             self.attach_mode,
             self.stream,
@@ -673,6 +726,7 @@ impl<T> HostSlice<T> for UnifiedViewMut<'_, T> {
 unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b UnifiedView<'c, T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b UnifiedView<'c, T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         self.stream.ctx.record_err(check_device_access(
             arg.attach_mode,
             arg.stream,
@@ -681,7 +735,7 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b UnifiedView<'c, T>> for Lau
         ));
         self.waits.push(arg.event);
         self.records.push(arg.event);
-        self.args.push((&arg.ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.ptr);
         self
     }
 }
@@ -689,6 +743,7 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b UnifiedView<'c, T>> for Lau
 unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b UnifiedViewMut<'c, T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b UnifiedViewMut<'c, T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         self.stream.ctx.record_err(check_device_access(
             arg.attach_mode,
             arg.stream,
@@ -697,7 +752,7 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b UnifiedViewMut<'c, T>> for
         ));
         self.waits.push(arg.event);
         self.records.push(arg.event);
-        self.args.push((&arg.ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.ptr);
         self
     }
 }
@@ -705,6 +760,7 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b UnifiedViewMut<'c, T>> for
 unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b mut UnifiedViewMut<'c, T>> for LaunchArgs<'a> {
     #[inline(always)]
     fn arg(&mut self, arg: &'b mut UnifiedViewMut<'c, T>) -> &mut Self {
+        self.invalidated |= arg.stream.ctx.is_invalidated();
         self.stream.ctx.record_err(check_device_access(
             arg.attach_mode,
             arg.stream,
@@ -713,7 +769,7 @@ unsafe impl<'a, 'b: 'a, 'c: 'b, T> PushKernelArg<&'b mut UnifiedViewMut<'c, T>>
         ));
         self.waits.push(arg.event);
         self.records.push(arg.event);
-        self.args.push((&arg.ptr) as *const sys::CUdeviceptr as _);
+        self.push_arg(&arg.ptr);
         self
     }
 }
@@ -0,0 +1,384 @@
+//! A single [Error] type that every module's error converts into, so application code can
+//! use `?` across libraries.
+//!
+//! ```ignore
+//! use cudarc::{Error, ErrorContext, ErrorKind};
+//!
+//! fn run() -> Result<(), Error> {
+//!     let ctx = cudarc::driver::CudaContext::new(0)?;
+//!     let blas = cudarc::cublas::CudaBlas::new(ctx.default_stream())?;
+//!     let ptx = cudarc::nvrtc::compile_ptx(SRC).context("compiling kernels")?;
+//!     let module = ctx.load_module(ptx).api("cuModuleLoadData")?;
+//!     Ok(())
+//! }
+//!
+//! match run() {
+//!     Err(e) if e.kind() == ErrorKind::OutOfMemory => { /* retry with a smaller batch */ }
+//!     other => other?,
+//! }
+//! ```
+
+use std::{boxed::Box, string::String};
+
+/// A coarse, stable classification of an [Error], for callers that want to branch on what
+/// went wrong without matching each library's status codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A device or host allocation failed.
+    OutOfMemory,
+    /// An argument was invalid, e.g. a bad size, pointer or layout.
+    InvalidValue,
+    /// The operation is not supported by this device, driver or library version.
+    NotSupported,
+    /// A library or device was not (or no longer) initialized.
+    NotInitialized,
+    /// A handle, context or stream was invalid.
+    InvalidHandle,
+    /// A named symbol, function or file was not found.
+    NotFound,
+    /// A kernel launch or execution failed, e.g. an illegal address. These usually leave
+    /// the context unusable.
+    LaunchFailure,
+    /// Compiling or loading device code failed.
+    Compilation,
+    /// An I/O operation failed.
+    Io,
+    /// Anything else.
+    Other,
+}
+
+macro_rules! errors {
+    ($($(#[$meta:meta])* $Variant:ident($Ty:ty) => $label:literal,)*) => {
+        /// An error from any module of this crate.
+        ///
+        /// Every module's error type converts into this with [From]. Use [Error::kind()] to
+        /// branch on the kind of failure.
+        ///
+        /// API names are not recorded automatically: the module errors are plain status codes
+        /// (e.g. `DriverError(CUresult)`) returned by every result level function, so they have
+        /// no room for one. Attach the failing API's name and a message where the error is
+        /// handled, with [ErrorContext::api()]/[ErrorContext::context()].
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum Error {
+            $(
+                $(#[$meta])*
+                $Variant($Ty),
+            )*
+            /// Another [Error] with the failing API's name and/or a message attached.
+            Context {
+                api: Option<&'static str>,
+                context: Option<String>,
+                source: Box<Error>,
+            },
+        }
+
+        $(
+            $(#[$meta])*
+            impl From<$Ty> for Error {
+                fn from(value: $Ty) -> Self {
+                    Self::$Variant(value)
+                }
+            }
+        )*
+
+        impl Error {
+            /// The kind of failure, see [ErrorKind].
+            pub fn kind(&self) -> ErrorKind {
+                match self {
+                    $(
+                        $(#[$meta])*
+                        Self::$Variant(e) => e.kind(),
+                    )*
+                    Self::Context { source, .. } => source.kind(),
+                }
+            }
+
+            /// The name of the module the error came from, e.g. `"driver"` or `"cublas"`.
+            pub fn module(&self) -> &'static str {
+                match self {
+                    $(
+                        $(#[$meta])*
+                        Self::$Variant(_) => $label,
+                    )*
+                    Self::Context { source, .. } => source.module(),
+                }
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl std::error::Error for Error {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    // Library errors are displayed as is, so they are not a separate source.
+                    $(
+                        $(#[$meta])*
+                        Self::$Variant(e) => std::error::Error::source(e),
+                    )*
+                    Self::Context { source, .. } => Some(source.as_ref()),
+                }
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl std::fmt::Display for Error {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(
+                        $(#[$meta])*
+                        Self::$Variant(e) => write!(f, "{e}"),
+                    )*
+                    Self::Context { api, context, .. } => match (api, context) {
+                        (Some(api), Some(context)) => write!(f, "{context} ({api} failed)"),
+                        (Some(api), None) => write!(f, "{api} failed"),
+                        (None, Some(context)) => write!(f, "{context}"),
+                        (None, None) => write!(f, "error"),
+                    },
+                }
+            }
+        }
+    };
+}
+
+errors! {
+    #[cfg(feature = "driver")]
+    Driver(crate::driver::DriverError) => "driver",
+    #[cfg(feature = "driver")]
+    Global(crate::driver::GlobalError) => "driver",
+    #[cfg(feature = "dlpack")]
+    DLPack(crate::driver::DLPackError) => "dlpack",
+    #[cfg(feature = "runtime")]
+    Runtime(crate::runtime::result::RuntimeError) => "runtime",
+    #[cfg(feature = "nvrtc")]
+    Nvrtc(crate::nvrtc::result::NvrtcError) => "nvrtc",
+    #[cfg(feature = "nvrtc")]
+    Compile(crate::nvrtc::CompileError) => "nvrtc",
+    #[cfg(all(
+        feature = "nvjitlink",
+        not(any(
+            feature = "cuda-11040",
+            feature = "cuda-11050",
+            feature = "cuda-11060",
+            feature = "cuda-11070",
+            feature = "cuda-11080",
+        ))
+    ))]
+    NvJitLink(crate::nvjitlink::result::NvJitLinkError) => "nvjitlink",
+    #[cfg(all(
+        feature = "nvjitlink",
+        not(any(
+            feature = "cuda-11040",
+            feature = "cuda-11050",
+            feature = "cuda-11060",
+            feature = "cuda-11070",
+            feature = "cuda-11080",
+        ))
+    ))]
+    Link(crate::nvjitlink::LinkError) => "nvjitlink",
+    #[cfg(feature = "cublas")]
+    Cublas(crate::cublas::result::CublasError) => "cublas",
+    #[cfg(feature = "cublaslt")]
+    CublasLt(crate::cublaslt::result::CublasError) => "cublaslt",
+    #[cfg(any(
+        feature = "cudnn-08009",
+        feature = "cudnn-09010",
+        feature = "cudnn-09021",
+    ))]
+    Cudnn(crate::cudnn::result::CudnnError) => "cudnn",
+    #[cfg(all(
+        feature = "cufft",
+        not(any(
+            feature = "cuda-11040",
+            feature = "cuda-11050",
+            feature = "cuda-11060",
+            feature = "cuda-11070",
+            feature = "cuda-11080",
+        ))
+    ))]
+    Cufft(crate::cufft::result::CufftError) => "cufft",
+    #[cfg(feature = "cufile")]
+    Cufile(crate::cufile::result::CufileError) => "cufile",
+    #[cfg(feature = "cupti")]
+    Cupti(crate::cupti::result::CuptiError) => "cupti",
+    #[cfg(feature = "curand")]
+    Curand(crate::curand::result::CurandError) => "curand",
+    #[cfg(feature = "cusolver")]
+    Cusolver(crate::cusolver::result::CusolverError) => "cusolver",
+    #[cfg(feature = "cusolvermg")]
+    CusolverMg(crate::cusolvermg::result::CusolverMgError) => "cusolvermg",
+    #[cfg(feature = "cusparse")]
+    Cusparse(crate::cusparse::result::CusparseError) => "cusparse",
+    #[cfg(all(
+        any(
+            feature = "cutensor-02003",
+            feature = "cutensor-02004",
+            feature = "cutensor-02005",
+            feature = "cutensor-02006",
+        ),
+        not(any(
+            feature = "cuda-11040",
+            feature = "cuda-11050",
+            feature = "cuda-11060",
+            feature = "cuda-11070",
+            feature = "cuda-11080",
+        ))
+    ))]
+    Cutensor(crate::cutensor::result::CutensorError) => "cutensor",
+    #[cfg(any(
+        feature = "nccl-02022",
+        feature = "nccl-02024",
+        feature = "nccl-02025",
+        feature = "nccl-02026",
+        feature = "nccl-02027",
+        feature = "nccl-02028",
+        feature = "nccl-02029",
+        feature = "nccl-02030",
+    ))]
+    Nccl(crate::nccl::result::NcclError) => "nccl",
+}
+
+impl Error {
+    /// The name of the failing API, if one was attached with [ErrorContext::api()].
+    ///
+    /// This is always `None` for errors converted straight from a module's error, since those
+    /// don't record which API failed, see [Error].
+    pub fn api(&self) -> Option<&'static str> {
+        match self {
+            Self::Context { api, source, .. } => api.or_else(|| source.api()),
+            _ => None,
+        }
+    }
+
+    /// The outermost message attached with [ErrorContext::context()].
+    pub fn context(&self) -> Option<&str> {
+        match self {
+            Self::Context {
+                context, source, ..
+            } => context.as_deref().or_else(|| source.context()),
+            _ => None,
+        }
+    }
+
+    /// The underlying library error, without any attached api names or messages.
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    /// Attaches the name of the failing API, e.g. `"cuMemAlloc_v2"`.
+    pub fn with_api(self, api: &'static str) -> Self {
+        match self {
+            Self::Context {
+                api: None,
+                context,
+                source,
+            } => Self::Context {
+                api: Some(api),
+                context,
+                source,
+            },
+            _ => Self::Context {
+                api: Some(api),
+                context: None,
+                source: Box::new(self),
+            },
+        }
+    }
+
+    /// Attaches a message describing what was being done when the error happened.
+    pub fn with_context(self, context: impl Into<String>) -> Self {
+        match self {
+            Self::Context {
+                api,
+                context: None,
+                source,
+            } => Self::Context {
+                api,
+                context: Some(context.into()),
+                source,
+            },
+            _ => Self::Context {
+                api: None,
+                context: Some(context.into()),
+                source: Box::new(self),
+            },
+        }
+    }
+}
+
+/// Attaches an API name or message to the error of a [Result], converting it into [Error].
+pub trait ErrorContext<T> {
+    /// See [Error::with_api()].
+    fn api(self, api: &'static str) -> Result<T, Error>;
+
+    /// See [Error::with_context()].
+    fn context(self, context: impl Into<String>) -> Result<T, Error>;
+
+    /// Like [ErrorContext::context()], but only builds the message on error.
+    fn with_context<C: Into<String>, F: FnOnce() -> C>(self, f: F) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> ErrorContext<T> for Result<T, E> {
+    fn api(self, api: &'static str) -> Result<T, Error> {
+        self.map_err(|e| e.into().with_api(api))
+    }
+
+    fn context(self, context: impl Into<String>) -> Result<T, Error> {
+        self.map_err(|e| e.into().with_context(context))
+    }
+
+    fn with_context<C: Into<String>, F: FnOnce() -> C>(self, f: F) -> Result<T, Error> {
+        self.map_err(|e| e.into().with_context(f()))
+    }
+}
+
+#[cfg(all(test, feature = "driver"))]
+mod tests {
+    use super::*;
+    use crate::driver::{sys::CUresult, DriverError};
+
+    #[test]
+    fn test_kind_and_context() {
+        let oom = DriverError(CUresult::CUDA_ERROR_OUT_OF_MEMORY);
+        let err: Error = oom.into();
+        assert_eq!(err.kind(), ErrorKind::OutOfMemory);
+        assert_eq!(err.module(), "driver");
+        assert_eq!(err.api(), None);
+
+        let err = Err::<(), _>(oom)
+            .api("cuMemAlloc_v2")
+            .context("allocating weights")
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::OutOfMemory);
+        assert_eq!(err.api(), Some("cuMemAlloc_v2"));
+        assert_eq!(err.context(), Some("allocating weights"));
+        // Both land in a single layer.
+        assert!(
+            matches!(&err, Error::Context { source, .. } if matches!(**source, Error::Driver(_)))
+        );
+
+        let err = err.with_context("loading model");
+        assert_eq!(err.context(), Some("loading model"));
+        assert_eq!(err.api(), Some("cuMemAlloc_v2"));
+        assert!(matches!(err.root(), Error::Driver(e) if *e == oom));
+
+        let err: Error = DriverError(CUresult::CUDA_ERROR_ILLEGAL_ADDRESS).into();
+        assert_eq!(err.kind(), ErrorKind::LaunchFailure);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_source_chain() {
+        use std::error::Error as _;
+        let err = Err::<(), _>(DriverError(CUresult::CUDA_ERROR_INVALID_VALUE))
+            .api("cuMemcpyHtoDAsync_v2")
+            .unwrap_err();
+        let source = err.source().unwrap();
+        assert!(source.downcast_ref::<Error>().is_some());
+        assert!(source.source().is_none());
+        assert_eq!(err.to_string(), "cuMemcpyHtoDAsync_v2 failed");
+    }
+}
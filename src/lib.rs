@@ -4,6 +4,7 @@
 //! | --- | --- | --- | --- |
 //! | [CUDA driver](https://docs.nvidia.com/cuda/cuda-driver-api/index.html) | ✅ | ✅ | ❌ |
 //! | [NVRTC](https://docs.nvidia.com/cuda/nvrtc/index.html) | ✅ | ✅ | ✅ |
+//! | [nvJitLink](https://docs.nvidia.com/cuda/nvjitlink/index.html) | ✅ | ✅ | ✅ |
 //! | [cuRAND](https://docs.nvidia.com/cuda/curand/index.html) | ✅ | ✅ | ✅ |
 //! | [cuBLAS](https://docs.nvidia.com/cuda/cublas/index.html) | ✅ | ✅ | ✅ |
 //! | [cuBLASLt](https://docs.nvidia.com/cuda/cublas/#using-the-cublaslt-api) | ✅ | ✅ | ✅ |
@@ -85,6 +86,12 @@
 //! [curand::CudaRng] can fill a [`driver::CudaSlice<T>`] with random data, based on
 //! one of its available distributions.
 //!
+//! # Errors
+//!
+//! Each module has its own error type, all of which convert into [Error]. Use [`Error::kind()`]
+//! to branch on the kind of failure. The module errors only carry a status code, so the name
+//! of the failing API is not recorded for you - attach it (or a message) with [ErrorContext].
+//!
 //! # Combining safe/result/sys
 //!
 //! The result and sys levels are very inter-changeable for each API. However,
@@ -107,6 +114,7 @@
 //! | cublas | [cublas::safe] | [cublas::result] | [cublas::sys] |
 //! | cublaslt | [cublaslt::safe] | [cublaslt::result] | [cublaslt::sys] |
 //! | nvrtc | [nvrtc::safe] | [nvrtc::result] | [nvrtc::sys] |
+//! | nvjitlink | [nvjitlink::safe] | [nvjitlink::result] | [nvjitlink::sys] |
 //! | curand | [curand::safe] | [curand::result] | [curand::sys] |
 //! | cudnn | [cudnn::safe] | [cudnn::result] | [cudnn::sys] |
 //! | cufft | [cufft::safe] | [cufft::result] | [cufft::sys] |
@@ -124,6 +132,10 @@ extern crate alloc;
 #[cfg(feature = "no-std")]
 extern crate no_std_compat as std;
 
+// Lets the derive macros' `::cudarc` paths resolve in this crate's own tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as cudarc;
+
 #[cfg(feature = "cublas")]
 pub mod cublas;
 #[cfg(feature = "cublaslt")]
@@ -186,6 +198,17 @@ pub mod driver;
     feature = "nccl-02030",
 ))]
 pub mod nccl;
+#[cfg(all(
+    feature = "nvjitlink",
+    not(any(
+        feature = "cuda-11040",
+        feature = "cuda-11050",
+        feature = "cuda-11060",
+        feature = "cuda-11070",
+        feature = "cuda-11080",
+    ))
+))]
+pub mod nvjitlink;
 #[cfg(feature = "nvrtc")]
 pub mod nvrtc;
 #[cfg(feature = "nvtx")]
@@ -193,8 +216,11 @@ pub mod nvtx;
 #[cfg(feature = "runtime")]
 pub mod runtime;
 
+pub mod error;
 pub mod types;
 
+pub use error::{Error, ErrorContext, ErrorKind};
+
 #[cfg(feature = "dynamic-loading")]
 pub(crate) fn panic_no_lib_found<S: std::fmt::Debug>(lib_name: &str, choices: &[S]) -> ! {
     panic!("Unable to dynamically load the \"{lib_name}\" shared library - searched for library names: {choices:?}. Ensure that `LD_LIBRARY_PATH` has the correct path to the installed library. If the shared library is present on the system under a different name than one of those listed above, please open a GitHub issue.");
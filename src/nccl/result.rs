@@ -1,6 +1,7 @@
 //! A thin wrapper around [sys] providing [Result]s with [NcclError].
 
 use super::sys::{self};
+use crate::ErrorKind;
 use std::mem::MaybeUninit;
 
 /// Wrapper around [sys::ncclResult_t].
@@ -14,6 +15,29 @@ impl std::fmt::Debug for NcclError {
     }
 }
 
+#[cfg(feature = "std")]
+impl std::fmt::Display for NcclError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NcclError({:?})", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NcclError {}
+
+impl NcclError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::ncclResult_t::ncclInvalidArgument | sys::ncclResult_t::ncclInvalidUsage => {
+                ErrorKind::InvalidValue
+            }
+            sys::ncclResult_t::ncclUnhandledCudaError => ErrorKind::LaunchFailure,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub enum NcclStatus {
     Success,
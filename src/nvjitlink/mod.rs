@@ -0,0 +1,12 @@
+//! Wrappers around the [nvJitLink API](https://docs.nvidia.com/cuda/nvjitlink/index.html),
+//! in three levels. See crate documentation for description of each.
+//!
+//! Link LTO-IR from [crate::nvrtc::compile_ltoir()], PTX and cubins with a [Linker].
+
+pub mod result;
+pub mod safe;
+#[allow(warnings)]
+#[rustfmt::skip]
+pub mod sys;
+
+pub use safe::*;
@@ -0,0 +1,200 @@
+//! A thin wrapper around [sys] providing [Result]s with [NvJitLinkError].
+
+use super::sys;
+use crate::ErrorKind;
+use core::{
+    ffi::{c_char, CStr},
+    mem::MaybeUninit,
+};
+use std::{ffi::CString, vec::Vec};
+
+/// Wrapper around [sys::nvJitLinkResult]. See
+/// [nvJitLinkResult docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NvJitLinkError(pub sys::nvJitLinkResult);
+
+impl sys::nvJitLinkResult {
+    /// Transforms into a [Result] of [NvJitLinkError]
+    pub fn result(self) -> Result<(), NvJitLinkError> {
+        match self {
+            sys::nvJitLinkResult::NVJITLINK_SUCCESS => Ok(()),
+            _ => Err(NvJitLinkError(self)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for NvJitLinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NvJitLinkError {}
+
+impl NvJitLinkError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        use sys::nvJitLinkResult::*;
+        match self.0 {
+            NVJITLINK_ERROR_UNRECOGNIZED_OPTION
+            | NVJITLINK_ERROR_MISSING_ARCH
+            | NVJITLINK_ERROR_INVALID_INPUT
+            | NVJITLINK_ERROR_UNRECOGNIZED_INPUT
+            | NVJITLINK_ERROR_NULL_INPUT
+            | NVJITLINK_ERROR_INCOMPATIBLE_OPTIONS
+            | NVJITLINK_ERROR_INCORRECT_INPUT_TYPE
+            | NVJITLINK_ERROR_UNRECOGNIZED_ARCH => ErrorKind::InvalidValue,
+            NVJITLINK_ERROR_PTX_COMPILE
+            | NVJITLINK_ERROR_NVVM_COMPILE
+            | NVJITLINK_ERROR_FINALIZE
+            | NVJITLINK_ERROR_ARCH_MISMATCH
+            | NVJITLINK_ERROR_MISSING_FATBIN => ErrorKind::Compilation,
+            NVJITLINK_ERROR_OUTDATED_LIBRARY
+            | NVJITLINK_ERROR_UNSUPPORTED_ARCH
+            | NVJITLINK_ERROR_LTO_NOT_ENABLED => ErrorKind::NotSupported,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// Creates a linker with the given options, like `-arch=sm_80` (required) or `-lto`.
+///
+/// See [nvJitLinkCreate() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+pub fn create<O: Clone + Into<Vec<u8>>>(
+    options: &[O],
+) -> Result<sys::nvJitLinkHandle, NvJitLinkError> {
+    let c_strings: Vec<CString> = options
+        .iter()
+        .cloned()
+        .map(|o| CString::new(o).unwrap())
+        .collect();
+    let mut opts: Vec<*const c_char> = c_strings.iter().map(|s| s.as_ptr()).collect();
+    let mut handle = MaybeUninit::uninit();
+    unsafe {
+        sys::nvJitLinkCreate(handle.as_mut_ptr(), opts.len() as u32, opts.as_mut_ptr()).result()?;
+        Ok(handle.assume_init())
+    }
+}
+
+/// Releases resources associated with `handle`.
+///
+/// See [nvJitLinkDestroy() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn destroy(handle: sys::nvJitLinkHandle) -> Result<(), NvJitLinkError> {
+    let mut handle = handle;
+    sys::nvJitLinkDestroy(&mut handle as *mut _).result()
+}
+
+/// Adds an input to link. `name` is used in log messages.
+///
+/// See [nvJitLinkAddData() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn add_data(
+    handle: sys::nvJitLinkHandle,
+    input_type: sys::nvJitLinkInputType,
+    data: &[u8],
+    name: &CStr,
+) -> Result<(), NvJitLinkError> {
+    sys::nvJitLinkAddData(
+        handle,
+        input_type,
+        data.as_ptr() as *const _,
+        data.len(),
+        name.as_ptr(),
+    )
+    .result()
+}
+
+/// Adds an input to link from a file.
+///
+/// See [nvJitLinkAddFile() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn add_file(
+    handle: sys::nvJitLinkHandle,
+    input_type: sys::nvJitLinkInputType,
+    file_name: &CStr,
+) -> Result<(), NvJitLinkError> {
+    sys::nvJitLinkAddFile(handle, input_type, file_name.as_ptr()).result()
+}
+
+/// Links all added inputs.
+///
+/// See [nvJitLinkComplete() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn complete(handle: sys::nvJitLinkHandle) -> Result<(), NvJitLinkError> {
+    sys::nvJitLinkComplete(handle).result()
+}
+
+/// The linked cubin. Call [complete()] before this.
+///
+/// See [nvJitLinkGetLinkedCubin() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn get_linked_cubin(handle: sys::nvJitLinkHandle) -> Result<Vec<u8>, NvJitLinkError> {
+    let mut size: usize = 0;
+    sys::nvJitLinkGetLinkedCubinSize(handle, &mut size as *mut _).result()?;
+    let mut cubin: Vec<u8> = std::vec![0; size];
+    sys::nvJitLinkGetLinkedCubin(handle, cubin.as_mut_ptr() as *mut _).result()?;
+    Ok(cubin)
+}
+
+/// The linked ptx, only available when linking with `-lto`. Call [complete()] before this.
+///
+/// See [nvJitLinkGetLinkedPtx() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn get_linked_ptx(handle: sys::nvJitLinkHandle) -> Result<Vec<c_char>, NvJitLinkError> {
+    let mut size: usize = 0;
+    sys::nvJitLinkGetLinkedPtxSize(handle, &mut size as *mut _).result()?;
+    let mut ptx: Vec<c_char> = std::vec![0; size];
+    sys::nvJitLinkGetLinkedPtx(handle, ptx.as_mut_ptr()).result()?;
+    Ok(ptx)
+}
+
+/// The errors of the previous calls.
+///
+/// See [nvJitLinkGetErrorLog() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn get_error_log(handle: sys::nvJitLinkHandle) -> Result<Vec<c_char>, NvJitLinkError> {
+    let mut size: usize = 0;
+    sys::nvJitLinkGetErrorLogSize(handle, &mut size as *mut _).result()?;
+    let mut log: Vec<c_char> = std::vec![0; size];
+    sys::nvJitLinkGetErrorLog(handle, log.as_mut_ptr()).result()?;
+    Ok(log)
+}
+
+/// Information like register usage, with `-verbose`.
+///
+/// See [nvJitLinkGetInfoLog() docs](https://docs.nvidia.com/cuda/nvjitlink/index.html)
+///
+/// # Safety
+///
+/// `handle` must be created from [create()] and not have been freed by [destroy()].
+pub unsafe fn get_info_log(handle: sys::nvJitLinkHandle) -> Result<Vec<c_char>, NvJitLinkError> {
+    let mut size: usize = 0;
+    sys::nvJitLinkGetInfoLogSize(handle, &mut size as *mut _).result()?;
+    let mut log: Vec<c_char> = std::vec![0; size];
+    sys::nvJitLinkGetInfoLog(handle, log.as_mut_ptr()).result()?;
+    Ok(log)
+}
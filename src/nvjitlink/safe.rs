@@ -0,0 +1,364 @@
+//! Safe abstractions around [crate::nvjitlink::result] for linking device code.
+//!
+//! Create a [Linker], add inputs, then call [Linker::link()].
+
+use super::{result, sys};
+use crate::nvrtc::{Ptx, PtxKind};
+
+use core::ffi::{c_char, CStr};
+use std::{
+    ffi::CString,
+    path::Path,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Options for [Linker::new()]. See
+/// <https://docs.nvidia.com/cuda/nvjitlink/index.html#supported-link-options>
+/// for all available options.
+///
+/// Only `arch` is required. Fields that are `None` or `false` are not passed to nvJitLink.
+///
+/// Example:
+/// ```rust
+/// # use cudarc::nvjitlink::*;
+/// // "-arch=sm_80 -lto" will be passed to nvJitLink
+/// let opts = LinkOptions {
+///     arch: "sm_80".into(),
+///     lto: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct LinkOptions {
+    /// The `sm_XX` architecture to link for, e.g. from [crate::nvrtc::sm_arch()].
+    pub arch: String,
+    /// Link time optimization, required to link LTO-IR and to get [Linked::ptx].
+    pub lto: bool,
+    /// Also produce [Linked::ptx]. Requires `lto`.
+    pub ptx: bool,
+    /// Optimization level, `-O<level>`.
+    pub optimization: Option<u32>,
+    pub debug: bool,
+    pub lineinfo: bool,
+    pub maxrregcount: Option<u32>,
+    /// Report information like register usage in [Linked::info_log].
+    pub verbose: bool,
+    pub options: Vec<String>,
+}
+
+impl LinkOptions {
+    pub(crate) fn build(self) -> Vec<String> {
+        let mut options: Vec<String> = Vec::new();
+        options.push(std::format!("-arch={}", self.arch));
+        if self.lto {
+            options.push("-lto".into());
+        }
+        if self.ptx {
+            options.push("-ptx".into());
+        }
+        if let Some(level) = self.optimization {
+            options.push(std::format!("-O{level}"));
+        }
+        if self.debug {
+            options.push("-g".into());
+        }
+        if self.lineinfo {
+            options.push("-lineinfo".into());
+        }
+        if let Some(count) = self.maxrregcount {
+            options.push(std::format!("-maxrregcount={count}"));
+        }
+        if self.verbose {
+            options.push("-verbose".into());
+        }
+        options.extend(self.options);
+        options
+    }
+}
+
+/// Links PTX, LTO-IR, cubin and fatbin inputs into a single cubin, which can be loaded with
+/// [crate::driver::CudaContext::load_module()]. This is needed to call device functions
+/// across separately compiled programs, and to optimize across them with LTO.
+///
+/// Example:
+/// ```ignore
+/// # use cudarc::{nvjitlink::*, nvrtc::*};
+/// let arch = sm_arch(ctx.compute_capability()?)?.unwrap();
+/// let opts = CompileOptions {
+///     arch: Some(arch),
+///     options: vec!["-rdc=true".into()],
+///     ..Default::default()
+/// };
+/// let a = compile_ltoir(SRC_A, opts.clone())?;
+/// let b = compile_ltoir(SRC_B, opts)?;
+///
+/// let mut linker = Linker::new(LinkOptions {
+///     arch: arch.into(),
+///     lto: true,
+///     ..Default::default()
+/// })?;
+/// linker.add_ltoir(&a, "a")?;
+/// linker.add_ltoir(&b, "b")?;
+/// let module = ctx.load_module(linker.link()?.cubin)?;
+/// ```
+#[derive(Debug)]
+pub struct Linker {
+    handle: sys::nvJitLinkHandle,
+}
+
+unsafe impl Send for Linker {}
+
+/// An error from nvJitLink, along with its error log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkError {
+    pub nvjitlink: result::NvJitLinkError,
+    /// Explains what went wrong, empty if nvJitLink didn't say.
+    pub log: String,
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "nvJitLink failed ({:?})", self.nvjitlink.0)?;
+        if !self.log.is_empty() {
+            write!(f, "\n{}", self.log.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LinkError {}
+
+impl LinkError {
+    /// The [crate::ErrorKind] of this error.
+    pub fn kind(&self) -> crate::ErrorKind {
+        self.nvjitlink.kind()
+    }
+}
+
+impl From<result::NvJitLinkError> for LinkError {
+    fn from(nvjitlink: result::NvJitLinkError) -> Self {
+        Self {
+            nvjitlink,
+            log: String::new(),
+        }
+    }
+}
+
+/// The output of [Linker::link()].
+#[derive(Debug, Clone)]
+pub struct Linked {
+    /// The linked cubin, which [crate::driver::CudaContext::load_module()] accepts.
+    pub cubin: Ptx,
+    /// The linked PTX, if [LinkOptions::ptx] was set.
+    pub ptx: Option<Ptx>,
+    /// Information about the link, like register usage with [LinkOptions::verbose].
+    pub info_log: String,
+}
+
+fn log_to_string(log: Vec<c_char>) -> String {
+    let bytes: Vec<u8> = log.into_iter().map(|c| c as u8).collect();
+    CStr::from_bytes_until_nul(&bytes)
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+impl Linker {
+    /// Creates a linker. [LinkOptions::arch] must be set.
+    pub fn new(opts: LinkOptions) -> Result<Self, LinkError> {
+        let handle = result::create(&opts.build())?;
+        Ok(Self { handle })
+    }
+
+    /// Attaches the error log to `err`.
+    fn with_log(&self, nvjitlink: result::NvJitLinkError) -> LinkError {
+        let log = unsafe { result::get_error_log(self.handle) }
+            .map(log_to_string)
+            .unwrap_or_default();
+        LinkError { nvjitlink, log }
+    }
+
+    /// Adds an input of the given type. `name` is used in log messages.
+    pub fn add_data(
+        &mut self,
+        input_type: sys::nvJitLinkInputType,
+        data: &[u8],
+        name: &str,
+    ) -> Result<(), LinkError> {
+        let name = CString::new(name).expect("input name cannot contain null terminators");
+        unsafe { result::add_data(self.handle, input_type, data, &name) }
+            .map_err(|e| self.with_log(e))
+    }
+
+    /// Adds PTX source.
+    pub fn add_ptx_src(&mut self, ptx: &str, name: &str) -> Result<(), LinkError> {
+        let ptx = CString::new(ptx).expect("ptx cannot contain null terminators");
+        self.add_data(
+            sys::nvJitLinkInputType::NVJITLINK_INPUT_PTX,
+            ptx.as_bytes_with_nul(),
+            name,
+        )
+    }
+
+    /// Adds LTO-IR, e.g. from [crate::nvrtc::compile_ltoir()]. Requires [LinkOptions::lto].
+    pub fn add_ltoir(&mut self, ltoir: &[u8], name: &str) -> Result<(), LinkError> {
+        self.add_data(sys::nvJitLinkInputType::NVJITLINK_INPUT_LTOIR, ltoir, name)
+    }
+
+    /// Adds a cubin, e.g. from [crate::nvrtc::compile_cubin()] with `-rdc=true`.
+    pub fn add_cubin(&mut self, cubin: &[u8], name: &str) -> Result<(), LinkError> {
+        self.add_data(sys::nvJitLinkInputType::NVJITLINK_INPUT_CUBIN, cubin, name)
+    }
+
+    /// Adds a fatbin, e.g. from `nvcc -fatbin`.
+    pub fn add_fatbin(&mut self, fatbin: &[u8], name: &str) -> Result<(), LinkError> {
+        self.add_data(
+            sys::nvJitLinkInputType::NVJITLINK_INPUT_FATBIN,
+            fatbin,
+            name,
+        )
+    }
+
+    /// Adds a compiled [Ptx]: PTX as PTX, cubins as cubins, and files by their extension.
+    pub fn add(&mut self, ptx: &Ptx, name: &str) -> Result<(), LinkError> {
+        match &ptx.kind {
+            PtxKind::Image(image) => {
+                let bytes: Vec<u8> = image.iter().map(|&c| c as u8).collect();
+                self.add_data(sys::nvJitLinkInputType::NVJITLINK_INPUT_PTX, &bytes, name)
+            }
+            PtxKind::Src(src) => self.add_ptx_src(src, name),
+            PtxKind::Binary(data) => self.add_cubin(data, name),
+            PtxKind::File(path) => {
+                self.add_file(sys::nvJitLinkInputType::NVJITLINK_INPUT_ANY, path)
+            }
+        }
+    }
+
+    /// Adds an input from a file. With [sys::nvJitLinkInputType::NVJITLINK_INPUT_ANY],
+    /// the type is picked from the file extension.
+    pub fn add_file<P: AsRef<Path>>(
+        &mut self,
+        input_type: sys::nvJitLinkInputType,
+        path: P,
+    ) -> Result<(), LinkError> {
+        let path = CString::new(path.as_ref().to_string_lossy().to_string())
+            .expect("path cannot contain null terminators");
+        unsafe { result::add_file(self.handle, input_type, &path) }.map_err(|e| self.with_log(e))
+    }
+
+    /// Links everything added so far.
+    pub fn link(self) -> Result<Linked, LinkError> {
+        unsafe { result::complete(self.handle) }.map_err(|e| self.with_log(e))?;
+        let cubin =
+            unsafe { result::get_linked_cubin(self.handle) }.map_err(|e| self.with_log(e))?;
+        let ptx = match unsafe { result::get_linked_ptx(self.handle) } {
+            Ok(ptx) if !ptx.is_empty() => Some(Ptx::from_src(log_to_string(ptx))),
+            _ => None,
+        };
+        let info_log = unsafe { result::get_info_log(self.handle) }
+            .map(log_to_string)
+            .unwrap_or_default();
+        Ok(Linked {
+            cubin: Ptx::from_binary(cubin),
+            ptx,
+            info_log,
+        })
+    }
+}
+
+impl Drop for Linker {
+    fn drop(&mut self) {
+        let handle = std::mem::replace(&mut self.handle, std::ptr::null_mut());
+        if !handle.is_null() {
+            unsafe { result::destroy(handle) }.unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvrtc::{compile_ltoir, CompileOptions};
+
+    #[test]
+    fn test_link_options_build() {
+        let opts = LinkOptions {
+            arch: "sm_80".into(),
+            ..Default::default()
+        };
+        assert_eq!(opts.build(), ["-arch=sm_80"]);
+
+        let opts = LinkOptions {
+            arch: "sm_90".into(),
+            lto: true,
+            ptx: true,
+            optimization: Some(3),
+            maxrregcount: Some(64),
+            options: std::vec!["-split-compile=0".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            opts.build(),
+            [
+                "-arch=sm_90",
+                "-lto",
+                "-ptx",
+                "-O3",
+                "-maxrregcount=64",
+                "-split-compile=0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_ltoir() {
+        let opts = CompileOptions {
+            arch: Some("sm_80"),
+            options: std::vec!["-rdc=true".into()],
+            ..Default::default()
+        };
+        let lib = compile_ltoir(
+            "__device__ float twice(float x) { return 2.0f * x; }",
+            opts.clone(),
+        )
+        .unwrap();
+        let kernel = compile_ltoir(
+            "extern __device__ float twice(float x);
+            extern \"C\" __global__ void kernel(float *x) { x[0] = twice(x[0]); }",
+            opts,
+        )
+        .unwrap();
+
+        let mut linker = Linker::new(LinkOptions {
+            arch: "sm_80".into(),
+            lto: true,
+            ptx: true,
+            ..Default::default()
+        })
+        .unwrap();
+        linker.add_ltoir(&lib, "lib").unwrap();
+        linker.add_ltoir(&kernel, "kernel").unwrap();
+        let linked = linker.link().unwrap();
+        assert!(matches!(&linked.cubin.kind, PtxKind::Binary(data) if !data.is_empty()));
+        assert!(linked.ptx.unwrap().to_src().contains(".entry kernel"));
+    }
+
+    #[test]
+    fn test_link_missing_symbol() {
+        let mut linker = Linker::new(LinkOptions {
+            arch: "sm_80".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        linker
+            .add_ptx_src(
+                ".version 7.0\n.target sm_80\n.address_size 64\n.extern .func missing();\n.visible .entry kernel() { call missing, (); ret; }",
+                "kernel",
+            )
+            .unwrap();
+        let err = linker.link().unwrap_err();
+        assert!(!err.log.is_empty());
+    }
+}
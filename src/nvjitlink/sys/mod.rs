@@ -0,0 +1,132 @@
+//! Bindings for `nvJitLink.h`.
+//!
+//! Unlike the other sys modules these are not generated: `nvJitLink.h` maps every function to
+//! a versioned symbol (e.g. `nvJitLinkCreate` to `__nvJitLinkCreate_12_0`), which bindgen
+//! doesn't see. Symbols are resolved by their versioned name first, and their plain name
+//! otherwise.
+#![cfg_attr(feature = "no-std", no_std)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+use std::sync::OnceLock;
+#[cfg(feature = "no-std")]
+extern crate alloc;
+#[cfg(feature = "no-std")]
+extern crate no_std_compat as std;
+#[cfg(feature = "dynamic-loading")]
+fn load<F: Copy>(name: &str) -> F {
+    let versioned = std::format!("__{name}_{}_0", env!("CUDA_MAJOR_VERSION"));
+    unsafe {
+        let lib = culib();
+        lib.get::<F>(versioned.as_bytes())
+            .or_else(|_| lib.get::<F>(name.as_bytes()))
+            .map(|f| *f)
+            .unwrap_or_else(|e| panic!("Missing symbol {name}: {e}"))
+    }
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct nvJitLink {
+    _unused: [u8; 0],
+}
+pub type nvJitLinkHandle = *mut nvJitLink;
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub enum nvJitLinkResult {
+    NVJITLINK_SUCCESS = 0,
+    NVJITLINK_ERROR_UNRECOGNIZED_OPTION = 1,
+    NVJITLINK_ERROR_MISSING_ARCH = 2,
+    NVJITLINK_ERROR_INVALID_INPUT = 3,
+    NVJITLINK_ERROR_PTX_COMPILE = 4,
+    NVJITLINK_ERROR_NVVM_COMPILE = 5,
+    NVJITLINK_ERROR_INTERNAL = 6,
+    NVJITLINK_ERROR_THREADPOOL = 7,
+    NVJITLINK_ERROR_UNRECOGNIZED_INPUT = 8,
+    NVJITLINK_ERROR_FINALIZE = 9,
+    NVJITLINK_ERROR_NULL_INPUT = 10,
+    NVJITLINK_ERROR_INCOMPATIBLE_OPTIONS = 11,
+    NVJITLINK_ERROR_INCORRECT_INPUT_TYPE = 12,
+    NVJITLINK_ERROR_ARCH_MISMATCH = 13,
+    NVJITLINK_ERROR_OUTDATED_LIBRARY = 14,
+    NVJITLINK_ERROR_MISSING_FATBIN = 15,
+    NVJITLINK_ERROR_UNRECOGNIZED_ARCH = 16,
+    NVJITLINK_ERROR_UNSUPPORTED_ARCH = 17,
+    NVJITLINK_ERROR_LTO_NOT_ENABLED = 18,
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub enum nvJitLinkInputType {
+    NVJITLINK_INPUT_NONE = 0,
+    NVJITLINK_INPUT_CUBIN = 1,
+    NVJITLINK_INPUT_PTX = 2,
+    NVJITLINK_INPUT_LTOIR = 3,
+    NVJITLINK_INPUT_FATBIN = 4,
+    NVJITLINK_INPUT_OBJECT = 5,
+    NVJITLINK_INPUT_LIBRARY = 6,
+    NVJITLINK_INPUT_INDEX = 7,
+    NVJITLINK_INPUT_ANY = 10,
+}
+macro_rules! nvjitlink_fns {
+    ($(pub unsafe fn $name:ident($($arg:ident: $ty:ty),*) -> nvJitLinkResult;)*) => {
+        $(
+            pub unsafe fn $name($($arg: $ty),*) -> nvJitLinkResult {
+                #[cfg(feature = "dynamic-loading")]
+                {
+                    type _F = unsafe extern "C" fn($($ty),*) -> nvJitLinkResult;
+                    static _S: OnceLock<_F> = OnceLock::new();
+                    let _f = _S.get_or_init(|| load::<_F>(stringify!($name)));
+                    _f($($arg),*)
+                }
+                #[cfg(not(feature = "dynamic-loading"))]
+                {
+                    extern "C" {
+                        #[cfg_attr(not(any(feature = "cuda-13000", feature = "cuda-13010", feature = "cuda-13020")), link_name = concat!("__", stringify!($name), "_12_0"))]
+                        #[cfg_attr(any(feature = "cuda-13000", feature = "cuda-13010", feature = "cuda-13020"), link_name = concat!("__", stringify!($name), "_13_0"))]
+                        fn $name($($arg: $ty),*) -> nvJitLinkResult;
+                    }
+                    $name($($arg),*)
+                }
+            }
+        )*
+    };
+}
+nvjitlink_fns! {
+    pub unsafe fn nvJitLinkCreate(handle: *mut nvJitLinkHandle, numOptions: u32, options: *mut *const ::core::ffi::c_char) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkDestroy(handle: *mut nvJitLinkHandle) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkAddData(handle: nvJitLinkHandle, inputType: nvJitLinkInputType, data: *const ::core::ffi::c_void, size: usize, name: *const ::core::ffi::c_char) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkAddFile(handle: nvJitLinkHandle, inputType: nvJitLinkInputType, fileName: *const ::core::ffi::c_char) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkComplete(handle: nvJitLinkHandle) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetLinkedCubinSize(handle: nvJitLinkHandle, size: *mut usize) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetLinkedCubin(handle: nvJitLinkHandle, cubin: *mut ::core::ffi::c_void) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetLinkedPtxSize(handle: nvJitLinkHandle, size: *mut usize) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetLinkedPtx(handle: nvJitLinkHandle, ptx: *mut ::core::ffi::c_char) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetErrorLogSize(handle: nvJitLinkHandle, size: *mut usize) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetErrorLog(handle: nvJitLinkHandle, log: *mut ::core::ffi::c_char) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetInfoLogSize(handle: nvJitLinkHandle, size: *mut usize) -> nvJitLinkResult;
+    pub unsafe fn nvJitLinkGetInfoLog(handle: nvJitLinkHandle, log: *mut ::core::ffi::c_char) -> nvJitLinkResult;
+}
+#[cfg(feature = "dynamic-loading")]
+pub unsafe fn is_culib_present() -> bool {
+    let lib_names = ["nvJitLink"];
+    let choices = lib_names.iter().map(|l| crate::get_lib_name_candidates(l)).flatten();
+    for choice in choices {
+        if ::libloading::Library::new(choice).is_ok() {
+            return true;
+        }
+    }
+    false
+}
+#[cfg(feature = "dynamic-loading")]
+pub unsafe fn culib() -> &'static ::libloading::Library {
+    static LIB: OnceLock<::libloading::Library> = OnceLock::new();
+    LIB.get_or_init(|| {
+        let lib_names = std::vec!["nvJitLink"];
+        let choices: std::vec::Vec<_> = lib_names.iter().map(|l| crate::get_lib_name_candidates(l)).flatten().collect();
+        for choice in choices.iter() {
+            if let Ok(lib) = ::libloading::Library::new(choice) {
+                return lib;
+            }
+        }
+        crate::panic_no_lib_found(lib_names[0], &choices);
+    })
+}
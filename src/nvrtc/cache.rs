@@ -0,0 +1,835 @@
+//! An opt-in on-disk cache of compiled kernels, see [KernelCache].
+
+use super::{
+    compile_cubin, compile_ptx_with_opts, result, CompileError, CompileOptions, Diagnostic, Ptx,
+    PtxKind, Severity,
+};
+
+use core::ffi::c_char;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    string::String,
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+    vec::Vec,
+};
+
+/// Bumped whenever the key or file layout changes, so old entries are never misread.
+const FORMAT_VERSION: u32 = 3;
+const MAGIC: &[u8; 8] = b"CUDARCKC";
+const HEADER_LEN: usize = 8 + 4 + 4 + 8 + 8 + 8;
+const EXTENSION: &str = "kc";
+
+/// Distinguishes temporary files written concurrently by the same process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// What a cached compilation produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    /// From [compile_ptx_with_opts()].
+    Ptx,
+    /// From [compile_cubin()].
+    Cubin,
+}
+
+/// A directory of compiled kernels, so processes don't recompile the same source with
+/// nvrtc every time they start.
+///
+/// Entries are keyed by a hash of the source, [CompileOptions] (including headers and name
+/// expressions), the nvrtc version and the [ArtifactKind]. Headers on disk are part of the
+/// key too: the `#include` directives of the source and of every header are followed
+/// through [CompileOptions::include_paths] (and `-I`/`--include-path` in
+/// [CompileOptions::options]), and the contents of the files found are hashed. Includes
+/// whose name is built by a macro can't be followed, so use a [super::HeaderSet] for those.
+/// Headers that are not found, like nvrtc's builtin ones, are skipped.
+///
+/// Entries are written atomically, so
+/// concurrent processes can share a directory, and are checksummed, so truncated or
+/// corrupted entries are recompiled. With [KernelCache::with_max_size()], the least recently
+/// used entries are evicted after each write.
+///
+/// The cache is best effort: if the directory can't be read or written, kernels are compiled
+/// as if there were no cache.
+///
+/// Example:
+/// ```ignore
+/// # use cudarc::nvrtc::*;
+/// let cache = KernelCache::new("/var/cache/my-app/kernels").with_max_size(256 << 20);
+/// let ptx = cache.compile_cached(SRC, CompileOptions::default())?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelCache {
+    dir: PathBuf,
+    max_size: Option<u64>,
+}
+
+impl KernelCache {
+    /// A cache in `dir`, which is created on the first write. The size is unbounded.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size: None,
+        }
+    }
+
+    /// Evicts the least recently used entries once the cache grows past `bytes`.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// [compile_ptx_with_opts()], reusing a previous result if there is one.
+    pub fn compile_cached<S: AsRef<str>>(
+        &self,
+        src: S,
+        opts: CompileOptions,
+    ) -> Result<Ptx, CompileError> {
+        self.compile_with_nvrtc(src.as_ref(), opts, ArtifactKind::Ptx)
+    }
+
+    /// [compile_cubin()], reusing a previous result if there is one.
+    pub fn compile_cubin_cached<S: AsRef<str>>(
+        &self,
+        src: S,
+        opts: CompileOptions,
+    ) -> Result<Ptx, CompileError> {
+        self.compile_with_nvrtc(src.as_ref(), opts, ArtifactKind::Cubin)
+    }
+
+    fn compile_with_nvrtc(
+        &self,
+        src: &str,
+        opts: CompileOptions,
+        kind: ArtifactKind,
+    ) -> Result<Ptx, CompileError> {
+        let compile = |src: &str, opts| match kind {
+            ArtifactKind::Ptx => compile_ptx_with_opts(src, opts),
+            ArtifactKind::Cubin => compile_cubin(src, opts),
+        };
+        match result::version() {
+            Ok(version) => self.get_or_compile(src, opts, kind, version, compile),
+            // Compiling will fail too, with a better error.
+            Err(_) => compile(src, opts),
+        }
+    }
+
+    /// Returns the cached artifact for this compilation if there is one, otherwise calls
+    /// `compile` and caches what it returns. `compiler_version` is part of the key, so a
+    /// new compiler never reuses artifacts of an old one.
+    ///
+    /// [KernelCache::compile_cached()] calls this with nvrtc, other compilers can be plugged in
+    /// here.
+    pub fn get_or_compile<F>(
+        &self,
+        src: &str,
+        opts: CompileOptions,
+        kind: ArtifactKind,
+        compiler_version: (i32, i32),
+        compile: F,
+    ) -> Result<Ptx, CompileError>
+    where
+        F: FnOnce(&str, CompileOptions) -> Result<Ptx, CompileError>,
+    {
+        let key = cache_key(src, &opts, kind, compiler_version);
+        let path = self.entry_path(key);
+        if let Some(ptx) = fs::read(&path).ok().and_then(|data| decode(key, &data)) {
+            // Refresh the modification time, which eviction uses to find unused entries.
+            if let Ok(file) = fs::File::options().append(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            return Ok(ptx);
+        }
+
+        let ptx = compile(src, opts)?;
+        if let Some(data) = encode(key, &ptx) {
+            if self.write_atomic(key, &path, &data).is_ok() {
+                self.evict(&path);
+            }
+        }
+        Ok(ptx)
+    }
+
+    /// The total size of all entries, in bytes.
+    pub fn size(&self) -> u64 {
+        self.entries().iter().map(|e| e.size).sum()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) -> std::io::Result<()> {
+        for entry in self.entries() {
+            fs::remove_file(entry.path)?;
+        }
+        Ok(())
+    }
+
+    fn entry_path(&self, key: u64) -> PathBuf {
+        self.dir.join(std::format!("{key:016x}.{EXTENSION}"))
+    }
+
+    /// Writes to a temporary file and renames it into place, so readers never see a partial
+    /// entry.
+    fn write_atomic(&self, key: u64, path: &Path, data: &[u8]) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let tmp = self.dir.join(std::format!(
+            ".{key:016x}.{}.{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = fs::File::create(&tmp).and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        });
+        let renamed = written.and_then(|_| fs::rename(&tmp, path));
+        if renamed.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        renamed
+    }
+
+    /// Removes the least recently used entries until the cache fits in its max size. `keep`
+    /// was just written and is never removed.
+    fn evict(&self, keep: &Path) {
+        let Some(max_size) = self.max_size else {
+            return;
+        };
+        let mut entries = self.entries();
+        let mut size: u64 = entries.iter().map(|e| e.size).sum();
+        entries.sort_by_key(|e| e.modified);
+        for entry in entries {
+            if size <= max_size {
+                break;
+            }
+            if entry.path != keep && fs::remove_file(&entry.path).is_ok() {
+                size -= entry.size;
+            }
+        }
+    }
+
+    fn entries(&self) -> Vec<Entry> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        dir.filter_map(|e| {
+            let e = e.ok()?;
+            let path = e.path();
+            if path.extension()? != EXTENSION {
+                return None;
+            }
+            let meta = e.metadata().ok()?;
+            Some(Entry {
+                path,
+                size: meta.len(),
+                modified: meta.modified().ok()?,
+            })
+        })
+        .collect()
+    }
+}
+
+struct Entry {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// 64 bit FNV-1a, which unlike [std::hash::DefaultHasher] is stable across rust versions.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    /// Length prefixed, so adjacent fields can't run into each other.
+    fn field(&mut self, bytes: &[u8]) {
+        self.bytes(&(bytes.len() as u64).to_le_bytes());
+        self.bytes(bytes);
+    }
+}
+
+pub(crate) fn cache_key(
+    src: &str,
+    opts: &CompileOptions,
+    kind: ArtifactKind,
+    compiler_version: (i32, i32),
+) -> u64 {
+    let mut h = Fnv::new();
+    h.bytes(&FORMAT_VERSION.to_le_bytes());
+    h.bytes(&[kind as u8]);
+    h.bytes(&compiler_version.0.to_le_bytes());
+    h.bytes(&compiler_version.1.to_le_bytes());
+    h.field(src.as_bytes());
+    h.field(opts.name.as_deref().unwrap_or_default().as_bytes());
+    h.bytes(&(opts.headers.len() as u64).to_le_bytes());
+    for (name, contents) in opts.headers.iter() {
+        h.field(name.as_bytes());
+        h.field(contents.as_bytes());
+    }
+    h.bytes(&(opts.name_expressions.len() as u64).to_le_bytes());
+    for expr in opts.name_expressions.iter() {
+        h.field(expr.as_bytes());
+    }
+    let options = opts.clone().build();
+    h.bytes(&(options.len() as u64).to_le_bytes());
+    for option in options.iter() {
+        h.field(option.as_bytes());
+    }
+    for (path, contents) in included_files(src, opts) {
+        h.field(path.as_os_str().as_encoded_bytes());
+        h.field(&contents);
+    }
+    h.0
+}
+
+/// The names of the `#include "..."`/`#include <...>` directives in `src`, with whether
+/// they are quoted. Conditional compilation is ignored, so this may find more than the
+/// preprocessor does.
+fn include_directives(src: &str) -> impl Iterator<Item = (&str, bool)> {
+    src.lines().filter_map(|line| {
+        let rest = line.trim_start().strip_prefix('#')?;
+        let rest = rest.trim_start().strip_prefix("include")?.trim_start();
+        let (close, quoted) = match rest.chars().next()? {
+            '"' => ('"', true),
+            '<' => ('>', false),
+            _ => return None,
+        };
+        let name = &rest[1..];
+        Some((&name[..name.find(close)?], quoted))
+    })
+}
+
+/// The files on disk that `src` includes, directly or through other headers, with their
+/// contents. See [KernelCache] for how they are searched.
+pub(crate) fn included_files(src: &str, opts: &CompileOptions) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut dirs: Vec<PathBuf> = opts.include_paths.iter().map(PathBuf::from).collect();
+    for option in opts.options.iter() {
+        let dir = option
+            .strip_prefix("--include-path=")
+            .or_else(|| option.strip_prefix("-I"));
+        if let Some(dir) = dir.filter(|d| !d.is_empty()) {
+            dirs.push(PathBuf::from(dir));
+        }
+    }
+
+    let mut files = Vec::new();
+    let mut seen_headers = BTreeSet::new();
+    let mut seen_files = BTreeSet::new();
+    // Sources still to scan, and the directory of the file they are from.
+    let mut pending: Vec<(String, Option<PathBuf>)> = std::vec![(src.into(), None)];
+    while let Some((src, from_dir)) = pending.pop() {
+        for (name, quoted) in include_directives(&src) {
+            if let Some(contents) = opts.headers.get(name) {
+                if seen_headers.insert(name.to_owned()) {
+                    pending.push((contents.into(), None));
+                }
+                continue;
+            }
+            let local = from_dir.as_ref().filter(|_| quoted).map(|d| d.join(name));
+            let found = local
+                .into_iter()
+                .chain(dirs.iter().map(|d| d.join(name)))
+                .find(|path| path.is_file());
+            let Some(path) = found else {
+                continue;
+            };
+            if !seen_files.insert(path.clone()) {
+                continue;
+            }
+            let Ok(contents) = fs::read(&path) else {
+                continue;
+            };
+            pending.push((
+                String::from_utf8_lossy(&contents).into_owned(),
+                path.parent().map(Path::to_path_buf),
+            ));
+            files.push((path, contents));
+        }
+    }
+    files
+}
+
+fn checksum(data: &[u8]) -> u64 {
+    let mut h = Fnv::new();
+    h.bytes(data);
+    h.0
+}
+
+/// Layout: magic, format version, artifact tag, key, checksum and length of the body, then
+/// the body: lowered names, then the [Ptx::diagnostics()] of the compilation (so warnings
+/// don't disappear on a cache hit), followed by the artifact. Returns `None` for artifacts that
+/// can't be cached, i.e. [Ptx::from_file()].
+fn encode(key: u64, ptx: &Ptx) -> Option<Vec<u8>> {
+    let (tag, artifact): (u32, Vec<u8>) = match &ptx.kind {
+        PtxKind::Image(image) => (0, image.iter().map(|&c| c as u8).collect()),
+        PtxKind::Src(src) => {
+            let mut image = src.as_bytes().to_vec();
+            image.push(0);
+            (0, image)
+        }
+        PtxKind::Binary(data) => (1, data.clone()),
+        PtxKind::File(_) => return None,
+    };
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&(ptx.lowered_names.len() as u64).to_le_bytes());
+    for (expr, name) in ptx.lowered_names.iter() {
+        for s in [expr, name] {
+            put_string(&mut body, s);
+        }
+    }
+    body.extend_from_slice(&(ptx.diagnostics.len() as u64).to_le_bytes());
+    for diagnostic in ptx.diagnostics.iter() {
+        put_opt_string(&mut body, diagnostic.file.as_deref());
+        put_opt_u32(&mut body, diagnostic.line);
+        put_opt_u32(&mut body, diagnostic.column);
+        body.push(match diagnostic.severity {
+            Severity::Remark => 0,
+            Severity::Warning => 1,
+            Severity::Error => 2,
+            Severity::Fatal => 3,
+        });
+        put_opt_string(&mut body, diagnostic.code.as_deref());
+        put_string(&mut body, &diagnostic.message);
+        put_opt_string(&mut body, diagnostic.source_line.as_deref());
+    }
+    body.extend_from_slice(&artifact);
+
+    let mut data = Vec::with_capacity(HEADER_LEN + body.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    data.extend_from_slice(&tag.to_le_bytes());
+    data.extend_from_slice(&key.to_le_bytes());
+    data.extend_from_slice(&checksum(&body).to_le_bytes());
+    data.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    data.extend_from_slice(&body);
+    Some(data)
+}
+
+/// The inverse of [encode()]. Returns `None` if `data` is not a valid entry for `key`.
+fn decode(key: u64, data: &[u8]) -> Option<Ptx> {
+    let mut reader = Reader(data);
+    if reader.take(MAGIC.len())? != MAGIC || reader.u32()? != FORMAT_VERSION {
+        return None;
+    }
+    let tag = reader.u32()?;
+    if tag > 1 || reader.u64()? != key {
+        return None;
+    }
+    let expected = reader.u64()?;
+    let body_len = usize::try_from(reader.u64()?).ok()?;
+    if reader.0.len() != body_len || checksum(reader.0) != expected {
+        return None;
+    }
+
+    let mut lowered_names = BTreeMap::new();
+    for _ in 0..reader.u64()? {
+        let expr = reader.string()?;
+        let name = reader.string()?;
+        lowered_names.insert(expr, name);
+    }
+    let mut diagnostics = Vec::new();
+    for _ in 0..reader.u64()? {
+        diagnostics.push(Diagnostic {
+            file: reader.opt_string()?,
+            line: reader.opt_u32()?,
+            column: reader.opt_u32()?,
+            severity: match reader.take(1)?[0] {
+                0 => Severity::Remark,
+                1 => Severity::Warning,
+                2 => Severity::Error,
+                3 => Severity::Fatal,
+                _ => return None,
+            },
+            code: reader.opt_string()?,
+            message: reader.string()?,
+            source_line: reader.opt_string()?,
+        });
+    }
+    let artifact = reader.0.to_vec();
+    let kind = match tag {
+        0 => PtxKind::Image(artifact.into_iter().map(|b| b as c_char).collect()),
+        _ => PtxKind::Binary(artifact),
+    };
+    Some(Ptx {
+        kind,
+        lowered_names,
+        diagnostics,
+    })
+}
+
+fn put_string(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    data.extend_from_slice(s.as_bytes());
+}
+
+fn put_opt_string(data: &mut Vec<u8>, s: Option<&str>) {
+    data.push(s.is_some() as u8);
+    if let Some(s) = s {
+        put_string(data, s);
+    }
+}
+
+fn put_opt_u32(data: &mut Vec<u8>, value: Option<u32>) {
+    data.push(value.is_some() as u8);
+    if let Some(value) = value {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    /// Reads the presence flag written before optional values.
+    fn present(&mut self) -> Option<bool> {
+        match self.take(1)?[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn opt_string(&mut self) -> Option<Option<String>> {
+        if self.present()? {
+            self.string().map(Some)
+        } else {
+            Some(None)
+        }
+    }
+
+    fn opt_u32(&mut self) -> Option<Option<u32>> {
+        if self.present()? {
+            self.u32().map(Some)
+        } else {
+            Some(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvrtc::HeaderSet;
+    use core::cell::Cell;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(std::format!(
+            "cudarc-kernel-cache-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    const WARNING_LOG: &str = r#"a.cu(3): warning #177-D: variable "x" was declared but never referenced
+    int x;
+        ^
+
+kernel.cu(9): remark: note
+"#;
+
+    fn fake_compile(
+        calls: &Cell<usize>,
+    ) -> impl FnOnce(&str, CompileOptions) -> Result<Ptx, CompileError> + '_ {
+        move |src, opts| {
+            calls.set(calls.get() + 1);
+            let mut ptx = Ptx::from_binary(src.as_bytes().to_vec());
+            ptx.diagnostics = Diagnostic::parse_log(WARNING_LOG);
+            for expr in opts.name_expressions {
+                let lowered = std::format!("_Z{}", expr.len());
+                ptx.lowered_names.insert(expr, lowered);
+            }
+            Ok(ptx)
+        }
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let opts = CompileOptions::default();
+        let key = cache_key("src", &opts, ArtifactKind::Ptx, (12, 8));
+        assert_eq!(key, cache_key("src", &opts, ArtifactKind::Ptx, (12, 8)));
+        assert_ne!(key, cache_key("src2", &opts, ArtifactKind::Ptx, (12, 8)));
+        assert_ne!(key, cache_key("src", &opts, ArtifactKind::Cubin, (12, 8)));
+        assert_ne!(key, cache_key("src", &opts, ArtifactKind::Ptx, (12, 9)));
+
+        let changed = [
+            CompileOptions {
+                arch: Some("sm_80"),
+                ..Default::default()
+            },
+            CompileOptions {
+                name_expressions: std::vec!["f<int>".into()],
+                ..Default::default()
+            },
+            CompileOptions {
+                headers: HeaderSet::new().with("a.h", ""),
+                ..Default::default()
+            },
+            CompileOptions {
+                name: Some("a.cu".into()),
+                ..Default::default()
+            },
+        ];
+        for opts in changed.iter() {
+            assert_ne!(key, cache_key("src", opts, ArtifactKind::Ptx, (12, 8)));
+        }
+        // Fields are length prefixed.
+        let a = CompileOptions {
+            headers: HeaderSet::new().with("ab", "c"),
+            ..Default::default()
+        };
+        let b = CompileOptions {
+            headers: HeaderSet::new().with("a", "bc"),
+            ..Default::default()
+        };
+        assert_ne!(
+            cache_key("src", &a, ArtifactKind::Ptx, (12, 8)),
+            cache_key("src", &b, ArtifactKind::Ptx, (12, 8))
+        );
+    }
+
+    #[test]
+    fn test_cache_key_includes_headers_on_disk() {
+        let dir = test_dir("includes");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.cuh"), "#include \"nested/b.cuh\"\n").unwrap();
+        fs::write(dir.join("nested/b.cuh"), "#include \"c.cuh\"\n").unwrap();
+        fs::write(dir.join("nested/c.cuh"), "// c").unwrap();
+
+        let src = "#include <cuda_fp16.h>\n  #  include \"a.cuh\"\n";
+        let opts = CompileOptions {
+            include_paths: std::vec![dir.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let mut found: Vec<_> = included_files(src, &opts)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                dir.join("a.cuh"),
+                dir.join("nested/b.cuh"),
+                dir.join("nested/c.cuh")
+            ]
+        );
+
+        // Changing a nested header changes the key.
+        let key = cache_key(src, &opts, ArtifactKind::Ptx, (12, 8));
+        assert_eq!(key, cache_key(src, &opts, ArtifactKind::Ptx, (12, 8)));
+        fs::write(dir.join("nested/c.cuh"), "// changed").unwrap();
+        assert_ne!(key, cache_key(src, &opts, ArtifactKind::Ptx, (12, 8)));
+
+        // In memory headers take precedence, and are followed too.
+        let opts = CompileOptions {
+            options: std::vec![std::format!("-I{}", dir.join("nested").display())],
+            headers: HeaderSet::new().with("a.cuh", "#include \"c.cuh\""),
+            ..Default::default()
+        };
+        let found: Vec<_> = included_files(src, &opts)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(found, [dir.join("nested").join("c.cuh")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_hit_and_miss() {
+        let dir = test_dir("hit");
+        let cache = KernelCache::new(&dir);
+        let calls = Cell::new(0);
+        let opts = CompileOptions {
+            name_expressions: std::vec!["kernel<float>".into()],
+            ..Default::default()
+        };
+
+        for _ in 0..2 {
+            let ptx = cache
+                .get_or_compile(
+                    "src",
+                    opts.clone(),
+                    ArtifactKind::Cubin,
+                    (12, 8),
+                    fake_compile(&calls),
+                )
+                .unwrap();
+            assert!(matches!(&ptx.kind, PtxKind::Binary(data) if data == b"src"));
+            assert_eq!(ptx.lowered_name("kernel<float>"), Some("_Z13"));
+            // Warnings survive a cache hit.
+            assert_eq!(ptx.diagnostics(), Diagnostic::parse_log(WARNING_LOG));
+            assert_eq!(ptx.diagnostics().len(), 2);
+            assert_eq!(ptx.diagnostics()[0].severity, Severity::Warning);
+        }
+        assert_eq!(calls.get(), 1);
+
+        cache
+            .get_or_compile(
+                "src",
+                opts,
+                ArtifactKind::Cubin,
+                (12, 9),
+                fake_compile(&calls),
+            )
+            .unwrap();
+        assert_eq!(calls.get(), 2);
+
+        let ptx = cache
+            .get_or_compile(
+                "ptx",
+                CompileOptions::default(),
+                ArtifactKind::Ptx,
+                (12, 8),
+                |_, _| Ok(Ptx::from_src("ptx")),
+            )
+            .unwrap();
+        assert_eq!(ptx.to_src(), "ptx");
+        let ptx = cache
+            .get_or_compile(
+                "ptx",
+                CompileOptions::default(),
+                ArtifactKind::Ptx,
+                (12, 8),
+                |_, _| unreachable!(),
+            )
+            .unwrap();
+        assert_eq!(ptx.to_src(), "ptx");
+
+        assert_eq!(cache.entries().len(), 3);
+        cache.clear().unwrap();
+        assert_eq!(cache.size(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_corruption() {
+        let dir = test_dir("corruption");
+        let cache = KernelCache::new(&dir);
+        let calls = Cell::new(0);
+        let opts = CompileOptions::default();
+        let key = cache_key("src", &opts, ArtifactKind::Cubin, (12, 8));
+        let path = cache.entry_path(key);
+
+        cache
+            .get_or_compile(
+                "src",
+                opts.clone(),
+                ArtifactKind::Cubin,
+                (12, 8),
+                fake_compile(&calls),
+            )
+            .unwrap();
+        let data = fs::read(&path).unwrap();
+        assert!(decode(key, &data).is_some());
+        assert!(decode(key + 1, &data).is_none());
+        assert!(decode(key, &data[..data.len() - 1]).is_none());
+        let mut flipped = data.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert!(decode(key, &flipped).is_none());
+
+        fs::write(&path, &flipped).unwrap();
+        cache
+            .get_or_compile(
+                "src",
+                opts.clone(),
+                ArtifactKind::Cubin,
+                (12, 8),
+                fake_compile(&calls),
+            )
+            .unwrap();
+        assert_eq!(calls.get(), 2);
+        // The corrupted entry was replaced.
+        assert_eq!(fs::read(&path).unwrap(), data);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let dir = test_dir("eviction");
+        let calls = Cell::new(0);
+        let unbounded = KernelCache::new(&dir);
+        let opts = CompileOptions::default();
+        for src in ["a", "b"] {
+            unbounded
+                .get_or_compile(
+                    src,
+                    opts.clone(),
+                    ArtifactKind::Cubin,
+                    (12, 8),
+                    fake_compile(&calls),
+                )
+                .unwrap();
+        }
+        let entry_size = unbounded.size() / 2;
+
+        // Make "a" the least recently used.
+        let now = SystemTime::now();
+        for (src, age) in [("a", 100), ("b", 50)] {
+            let path = unbounded.entry_path(cache_key(src, &opts, ArtifactKind::Cubin, (12, 8)));
+            fs::File::options()
+                .append(true)
+                .open(path)
+                .unwrap()
+                .set_modified(now - std::time::Duration::from_secs(age))
+                .unwrap();
+        }
+
+        let bounded = KernelCache::new(&dir).with_max_size(2 * entry_size);
+        bounded
+            .get_or_compile(
+                "c",
+                opts.clone(),
+                ArtifactKind::Cubin,
+                (12, 8),
+                fake_compile(&calls),
+            )
+            .unwrap();
+        assert_eq!(bounded.size(), 2 * entry_size);
+        let exists = |src| {
+            bounded
+                .entry_path(cache_key(src, &opts, ArtifactKind::Cubin, (12, 8)))
+                .exists()
+        };
+        assert!(!exists("a"));
+        assert!(exists("b"));
+        assert!(exists("c"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
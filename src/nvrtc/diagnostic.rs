@@ -0,0 +1,242 @@
+//! Parses nvrtc's program log into [Diagnostic]s.
+
+use core::fmt;
+use std::{borrow::ToOwned, string::String, vec::Vec};
+
+/// How severe a [Diagnostic] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Remark,
+    Warning,
+    Error,
+    /// An error that stopped compilation, e.g. a missing `#include`.
+    Fatal,
+}
+
+impl Severity {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "remark" => Some(Self::Remark),
+            "warning" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            "catastrophic error" | "fatal error" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Remark => "remark",
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Fatal => "fatal error",
+        })
+    }
+}
+
+/// A single error, warning or remark from the nvrtc log. Get them from
+/// [super::Ptx::diagnostics()] or [super::CompileError::diagnostics()].
+///
+/// Displays like a compiler error, with the offending source line and a caret under the
+/// column if nvrtc reported them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// The file name, e.g. [super::CompileOptions::name] (`default_program` if unset), or
+    /// `None` for diagnostics about the options.
+    pub file: Option<String>,
+    /// 1-based line number.
+    pub line: Option<u32>,
+    /// 1-based column, from the caret nvrtc prints under [Diagnostic::source_line].
+    pub column: Option<u32>,
+    pub severity: Severity,
+    /// The diagnostic number, e.g. `177-D` for an unused variable. Pass these to
+    /// `-diag-suppress` to silence warnings.
+    pub code: Option<String>,
+    pub message: String,
+    /// The line of source code nvrtc printed with this diagnostic.
+    pub source_line: Option<String>,
+}
+
+/// nvrtc indents the quoted source line and the caret below it by this much.
+const SOURCE_INDENT: &str = "  ";
+
+impl Diagnostic {
+    /// Parses an nvrtc program log. Lines that aren't part of a diagnostic, like the
+    /// `1 error detected in the compilation of ...` summary, are skipped.
+    pub fn parse_log(log: &str) -> Vec<Self> {
+        let mut diagnostics: Vec<Self> = Vec::new();
+        let mut lines = log.lines().peekable();
+        while let Some(line) = lines.next() {
+            let Some(mut diagnostic) = Self::parse_header(line) else {
+                continue;
+            };
+            // The source line and caret, if there are any.
+            if let Some(source) = lines.peek().and_then(|l| l.strip_prefix(SOURCE_INDENT)) {
+                if Self::parse_header(source).is_none() {
+                    diagnostic.source_line = Some(source.to_owned());
+                    lines.next();
+                    if let Some(caret) = lines.peek().and_then(|l| l.strip_prefix(SOURCE_INDENT)) {
+                        if caret.trim() == "^" {
+                            diagnostic.column = Some(caret.find('^').unwrap() as u32 + 1);
+                            lines.next();
+                        }
+                    }
+                }
+            }
+            diagnostics.push(diagnostic);
+        }
+        diagnostics
+    }
+
+    /// Parses `file(line): severity #code: message` or `prefix: severity: message`.
+    fn parse_header(line: &str) -> Option<Self> {
+        // The first severity in the line, since messages may contain another.
+        let (at, severity) = [
+            "catastrophic error",
+            "fatal error",
+            "error",
+            "warning",
+            "remark",
+        ]
+        .iter()
+        .filter_map(|&severity| Some((line.find(&std::format!(": {severity}"))?, severity)))
+        .min_by_key(|(at, _)| *at)?;
+        let (severity, code, head, message) = {
+            let head = &line[..at];
+            let rest = &line[at + 2 + severity.len()..];
+            let (code, message) = match rest.strip_prefix(" #") {
+                Some(rest) => {
+                    let (code, message) = rest.split_once(": ")?;
+                    (Some(code), message)
+                }
+                None => (None, rest.strip_prefix(": ")?),
+            };
+            (Severity::parse(severity)?, code, head, message)
+        };
+
+        let (file, line) = match head.strip_suffix(')').and_then(|h| h.rsplit_once('(')) {
+            Some((file, line)) => (Some(file.to_owned()), Some(line.parse().ok()?)),
+            // e.g. `nvrtc: error: invalid value for --gpu-architecture`
+            None if !head.contains(' ') => (None, None),
+            None => return None,
+        };
+        Some(Self {
+            file,
+            line,
+            column: None,
+            severity,
+            code: code.map(ToOwned::to_owned),
+            message: message.trim().to_owned(),
+            source_line: None,
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = self.file.as_ref() {
+            write!(f, "{file}:")?;
+            if let Some(line) = self.line {
+                write!(f, "{line}:")?;
+                if let Some(column) = self.column {
+                    write!(f, "{column}:")?;
+                }
+            }
+            write!(f, " ")?;
+        }
+        write!(f, "{}", self.severity)?;
+        if let Some(code) = self.code.as_ref() {
+            write!(f, " #{code}")?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(source) = self.source_line.as_ref() {
+            write!(f, "\n{SOURCE_INDENT}{source}")?;
+            if let Some(column) = self.column {
+                write!(
+                    f,
+                    "\n{SOURCE_INDENT}{:>width$}",
+                    "^",
+                    width = column as usize
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = r#"default_program(3): error: identifier "foo" is undefined
+      foo(x);
+      ^
+
+default_program(5): warning #177-D: variable "y" was declared but never referenced
+      int y;
+          ^
+
+Remark: The warnings can be suppressed with "-diag-suppress <warning-number>"
+
+kernel.cu(1): catastrophic error: cannot open source file "missing.h"
+  #include "missing.h"
+                      ^
+
+nvrtc: error: invalid value for --gpu-architecture (-arch)
+
+2 errors detected in the compilation of "default_program".
+"#;
+
+    #[test]
+    fn test_parse_log() {
+        let diagnostics = Diagnostic::parse_log(LOG);
+        assert_eq!(diagnostics.len(), 4);
+
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                file: Some("default_program".into()),
+                line: Some(3),
+                column: Some(5),
+                severity: Severity::Error,
+                code: None,
+                message: "identifier \"foo\" is undefined".into(),
+                source_line: Some("    foo(x);".into()),
+            }
+        );
+
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].code.as_deref(), Some("177-D"));
+        assert_eq!(diagnostics[1].column, Some(9));
+        assert_eq!(
+            diagnostics[1].message,
+            "variable \"y\" was declared but never referenced"
+        );
+
+        assert_eq!(diagnostics[2].file.as_deref(), Some("kernel.cu"));
+        assert_eq!(diagnostics[2].severity, Severity::Fatal);
+        assert_eq!(diagnostics[2].column, Some(21));
+
+        assert_eq!(diagnostics[3].file, None);
+        assert_eq!(diagnostics[3].line, None);
+        assert_eq!(diagnostics[3].severity, Severity::Error);
+        assert_eq!(diagnostics[3].source_line, None);
+
+        assert!(Diagnostic::parse_log("").is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let diagnostics = Diagnostic::parse_log(LOG);
+        assert_eq!(
+            std::format!("{}", diagnostics[0]),
+            "default_program:3:5: error: identifier \"foo\" is undefined\n      foo(x);\n      ^"
+        );
+        assert_eq!(
+            std::format!("{}", diagnostics[3]),
+            "error: invalid value for --gpu-architecture (-arch)"
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Compiles many programs in parallel on a bounded pool of threads. See [CompileJobSet].
+
+use super::{
+    compile_cubin, compile_ptx_with_opts, ArtifactKind, CompileError, CompileOptions, KernelCache,
+    Ptx,
+};
+
+use std::{
+    num::NonZeroUsize,
+    string::String,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    vec::Vec,
+};
+
+/// Identifies a job of a [CompileJobSet]: the index it was added at, starting from 0.
+pub type JobId = usize;
+
+#[derive(Debug, Clone)]
+struct Job {
+    src: String,
+    opts: CompileOptions,
+    kind: ArtifactKind,
+}
+
+impl Job {
+    fn compile(self, cache: Option<&KernelCache>) -> Result<Ptx, CompileError> {
+        match (cache, self.kind) {
+            (Some(cache), ArtifactKind::Ptx) => cache.compile_cached(self.src, self.opts),
+            (Some(cache), ArtifactKind::Cubin) => cache.compile_cubin_cached(self.src, self.opts),
+            (None, ArtifactKind::Ptx) => compile_ptx_with_opts(self.src, self.opts),
+            (None, ArtifactKind::Cubin) => compile_cubin(self.src, self.opts),
+        }
+    }
+}
+
+/// A batch of nvrtc compilations, e.g. all the variants of an autotuning sweep, that run on
+/// a bounded pool of threads.
+///
+/// [CompileJobSet::run()] blocks until every job finished. [CompileJobSet::spawn()] compiles
+/// in the background and returns [CompileJobs], which yields results as they finish and can
+/// be cancelled.
+///
+/// Each job may use more threads itself with [CompileOptions::split_compile], so lower
+/// [CompileJobSet::with_threads()] when setting both.
+///
+/// Example:
+/// ```ignore
+/// # use cudarc::nvrtc::*;
+/// let mut jobs = CompileJobSet::new();
+/// for block_size in [64, 128, 256] {
+///     let opts = CompileOptions {
+///         options: vec![format!("-DBLOCK_SIZE={block_size}")],
+///         ..Default::default()
+///     };
+///     jobs.add_ptx(SRC, opts);
+/// }
+/// let ptxs: Vec<Ptx> = jobs.run().into_iter().collect::<Result<_, _>>()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CompileJobSet {
+    jobs: Vec<Job>,
+    threads: Option<NonZeroUsize>,
+    cache: Option<KernelCache>,
+}
+
+impl CompileJobSet {
+    /// An empty set, which compiles on as many threads as there are CPUs.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Compiles on at most `threads` threads. `0` is treated as `1`.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(NonZeroUsize::new(threads).unwrap_or(NonZeroUsize::MIN));
+        self
+    }
+
+    /// Reuses and stores results in `cache`.
+    pub fn with_cache(mut self, cache: KernelCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Adds a [compile_ptx_with_opts()] job.
+    pub fn add_ptx(&mut self, src: impl Into<String>, opts: CompileOptions) -> JobId {
+        self.add(src.into(), opts, ArtifactKind::Ptx)
+    }
+
+    /// Adds a [compile_cubin()] job.
+    pub fn add_cubin(&mut self, src: impl Into<String>, opts: CompileOptions) -> JobId {
+        self.add(src.into(), opts, ArtifactKind::Cubin)
+    }
+
+    /// Adds a [compile_ptx_with_opts()] job for each of `archs`, see
+    /// [CompileOptions::for_archs()].
+    pub fn add_ptx_for_archs(
+        &mut self,
+        src: impl Into<String>,
+        opts: CompileOptions,
+        archs: &[&'static str],
+    ) -> Vec<JobId> {
+        let src = src.into();
+        opts.for_archs(archs)
+            .into_iter()
+            .map(|opts| self.add(src.clone(), opts, ArtifactKind::Ptx))
+            .collect()
+    }
+
+    /// Adds a [compile_cubin()] job for each of `archs`, see [CompileOptions::for_archs()].
+    pub fn add_cubin_for_archs(
+        &mut self,
+        src: impl Into<String>,
+        opts: CompileOptions,
+        archs: &[&'static str],
+    ) -> Vec<JobId> {
+        let src = src.into();
+        opts.for_archs(archs)
+            .into_iter()
+            .map(|opts| self.add(src.clone(), opts, ArtifactKind::Cubin))
+            .collect()
+    }
+
+    fn add(&mut self, src: String, opts: CompileOptions, kind: ArtifactKind) -> JobId {
+        self.jobs.push(Job { src, opts, kind });
+        self.jobs.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Compiles every job and returns the results, indexed by [JobId].
+    pub fn run(self) -> Vec<Result<Ptx, CompileError>> {
+        self.spawn()
+            .wait()
+            .into_iter()
+            .map(|result| result.expect("jobs are only skipped if cancelled"))
+            .collect()
+    }
+
+    /// Starts compiling in the background. Jobs start in the order they were added.
+    pub fn spawn(self) -> CompileJobs {
+        self.spawn_with(|_, job, cache| job.compile(cache))
+    }
+
+    /// [CompileJobSet::spawn()] with `compile` run for each job instead of nvrtc.
+    fn spawn_with<F>(self, compile: F) -> CompileJobs
+    where
+        F: Fn(JobId, Job, Option<&KernelCache>) -> Result<Ptx, CompileError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let compile = Arc::new(compile);
+        let len = self.jobs.len();
+        let threads = self
+            .threads
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+            .min(len);
+        let queue = Arc::new(Mutex::new(self.jobs.into_iter().enumerate()));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, results) = mpsc::channel();
+
+        let workers = (0..threads)
+            .map(|_| {
+                let queue = queue.clone();
+                let cancelled = cancelled.clone();
+                let sender = sender.clone();
+                let cache = self.cache.clone();
+                let compile = compile.clone();
+                thread::spawn(move || loop {
+                    if cancelled.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Some((id, job)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    // The receiver is gone if the [CompileJobs] was dropped.
+                    if sender.send((id, compile(id, job, cache.as_ref()))).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+
+        CompileJobs {
+            results,
+            cancelled,
+            workers,
+            len,
+        }
+    }
+}
+
+/// Cancels [CompileJobs] from another thread. See [CompileJobs::cancel()].
+#[derive(Debug, Clone)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A [CompileJobSet] compiling in the background, from [CompileJobSet::spawn()].
+///
+/// Iterating yields `(JobId, result)` pairs in the order jobs finish, until every job
+/// finished or was cancelled. Dropping it cancels the remaining jobs and blocks until the
+/// jobs that are already compiling finish, so no worker threads outlive it.
+#[derive(Debug)]
+pub struct CompileJobs {
+    results: mpsc::Receiver<(JobId, Result<Ptx, CompileError>)>,
+    cancelled: Arc<AtomicBool>,
+    workers: Vec<thread::JoinHandle<()>>,
+    len: usize,
+}
+
+impl CompileJobs {
+    /// The number of jobs, including finished and cancelled ones.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Stops starting new jobs. nvrtc can't be interrupted, so jobs that are already
+    /// compiling finish and are still reported.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// A handle that can cancel these jobs from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle(self.cancelled.clone())
+    }
+
+    /// The channel results are sent through, e.g. to wait with
+    /// [mpsc::Receiver::recv_timeout()]. It disconnects once all jobs finished or were
+    /// cancelled.
+    pub fn receiver(&self) -> &mpsc::Receiver<(JobId, Result<Ptx, CompileError>)> {
+        &self.results
+    }
+
+    /// Waits for the remaining jobs and returns all results, indexed by [JobId], that were
+    /// not already received. Jobs that were cancelled, or whose result was received, are
+    /// `None`.
+    ///
+    /// # Panics
+    ///
+    /// If a worker thread panicked, the panic is resumed here.
+    pub fn wait(mut self) -> Vec<Option<Result<Ptx, CompileError>>> {
+        let mut results: Vec<_> = (0..self.len).map(|_| None).collect();
+        for (id, result) in self.results.iter() {
+            results[id] = Some(result);
+        }
+        for worker in std::mem::take(&mut self.workers) {
+            if let Err(panic) = worker.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+        results
+    }
+}
+
+impl Iterator for CompileJobs {
+    type Item = (JobId, Result<Ptx, CompileError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.results.recv().ok()
+    }
+}
+
+impl Drop for CompileJobs {
+    fn drop(&mut self) {
+        self.cancel();
+        for worker in std::mem::take(&mut self.workers) {
+            // A panic is only resumed by [CompileJobs::wait()], not while dropping.
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "extern \"C\" __global__ void scale(float *x) { x[0] *= SCALE; }";
+
+    fn opts(scale: &str) -> CompileOptions {
+        CompileOptions {
+            options: std::vec![std::format!("-DSCALE={scale}")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_job_set() {
+        let jobs = CompileJobSet::new().with_threads(0);
+        assert!(jobs.is_empty());
+        assert!(jobs.clone().run().is_empty());
+
+        let mut spawned = jobs.spawn();
+        assert!(spawned.is_empty());
+        assert!(spawned.next().is_none());
+        assert!(spawned.wait().is_empty());
+    }
+
+    #[test]
+    fn test_add_for_archs() {
+        let mut jobs = CompileJobSet::new();
+        jobs.add_ptx(SRC, opts("1.0f"));
+        let ids = jobs.add_cubin_for_archs(SRC, opts("2.0f"), &["sm_80", "sm_90"]);
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(jobs.jobs[1].opts.arch, Some("sm_80"));
+        assert_eq!(jobs.jobs[2].opts.arch, Some("sm_90"));
+        assert_eq!(jobs.jobs[2].kind, ArtifactKind::Cubin);
+    }
+
+    #[test]
+    fn test_run_job_set() {
+        let mut jobs = CompileJobSet::new().with_threads(2);
+        assert_eq!(jobs.add_ptx(SRC, opts("2.0f")), 0);
+        assert_eq!(jobs.add_ptx(SRC, opts("")), 1);
+        assert_eq!(jobs.add_ptx(SRC, opts("3.0f")), 2);
+        assert_eq!(jobs.len(), 3);
+
+        let results = jobs.run();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].as_ref().unwrap().to_src().contains("2.0"));
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn test_stream_and_cancel_job_set() {
+        let mut jobs = CompileJobSet::new().with_threads(1);
+        for i in 0..8 {
+            jobs.add_ptx(SRC, opts(&std::format!("{i}.0f")));
+        }
+
+        // Each job reports that it started, then blocks until the test lets it finish.
+        let (started_tx, started) = mpsc::channel();
+        let (finish, finish_rx) = mpsc::channel::<()>();
+        let finish_rx = Mutex::new(finish_rx);
+        let mut spawned = jobs.spawn_with(move |id, job, _| {
+            started_tx.send(id).unwrap();
+            finish_rx.lock().unwrap().recv().unwrap();
+            Ok(Ptx::from_src(job.src))
+        });
+
+        assert_eq!(started.recv().unwrap(), 0);
+        finish.send(()).unwrap();
+        let (id, result) = spawned.next().unwrap();
+        assert_eq!(id, 0);
+        assert!(result.is_ok());
+
+        // Job 1 is compiling when cancelled, so it still finishes. Nothing else starts.
+        assert_eq!(started.recv().unwrap(), 1);
+        spawned.cancel_handle().cancel();
+        assert!(spawned.is_cancelled());
+        finish.send(()).unwrap();
+        let results = spawned.wait();
+        assert_eq!(results.len(), 8);
+        assert!(results[0].is_none());
+        assert!(results[1].as_ref().unwrap().is_ok());
+        assert!(results[2..].iter().all(Option::is_none));
+        assert!(started.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_drop_joins_workers() {
+        let mut jobs = CompileJobSet::new().with_threads(2);
+        for _ in 0..4 {
+            jobs.add_ptx(SRC, opts("1.0f"));
+        }
+        let finished = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (started_tx, started) = mpsc::channel();
+        let spawned = jobs.spawn_with({
+            let finished = finished.clone();
+            move |_, job, _| {
+                let _ = started_tx.send(());
+                thread::sleep(std::time::Duration::from_millis(50));
+                finished.fetch_add(1, Ordering::SeqCst);
+                Ok(Ptx::from_src(job.src))
+            }
+        });
+        started.recv().unwrap();
+        drop(spawned);
+        // Whatever was compiling finished before the drop returned, and nothing runs after.
+        let count = finished.load(Ordering::SeqCst);
+        assert!(count >= 1);
+        thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(finished.load(Ordering::SeqCst), count);
+    }
+}
@@ -1,12 +1,25 @@
 //! Wrappers around the [Nvidia Runtime Compilation (nvrtc) API](https://docs.nvidia.com/cuda/nvrtc/index.html),
 //! in three levels. See crate documentation for description of each.
 //!
-//! Call [compile_ptx()] or [compile_ptx_with_opts()].
+//! Call [compile_ptx()] or [compile_ptx_with_opts()]. To skip recompiling in every process,
+//! use a [KernelCache]. To compile many programs in parallel, use a [CompileJobSet].
 
+#[cfg(feature = "std")]
+pub mod cache;
+pub mod diagnostic;
+#[cfg(feature = "std")]
+pub mod jobs;
+pub mod ptx_info;
 pub mod result;
 pub mod safe;
 #[allow(warnings)]
 #[rustfmt::skip]
 pub mod sys;
 
+#[cfg(feature = "std")]
+pub use cache::{ArtifactKind, KernelCache};
+pub use diagnostic::{Diagnostic, Severity};
+#[cfg(feature = "std")]
+pub use jobs::{CancelHandle, CompileJobSet, CompileJobs, JobId};
+pub use ptx_info::{PtxEntry, PtxInfo, PtxParam, PtxParseError, PtxVariable, StateSpace};
 pub use safe::*;
@@ -0,0 +1,654 @@
+//! Parses what a PTX module declares, without compiling it: the kernels with their
+//! parameters, module scope variables and the `.version`/`.target` header. See [PtxInfo].
+
+use core::fmt;
+use std::{borrow::ToOwned, string::String, vec::Vec};
+
+/// The declarations of a PTX module, from [PtxInfo::parse()] or [super::Ptx::info()].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PtxInfo {
+    /// The PTX ISA version, e.g. `(7, 3)` for `.version 7.3`.
+    pub version: Option<(u32, u32)>,
+    /// The architecture and modifiers from `.target`, e.g. `["sm_52"]`.
+    pub target: Vec<String>,
+    /// `.address_size`, in bits.
+    pub address_size: Option<u32>,
+    /// Every `.entry`, i.e. kernel, in the order they are declared.
+    pub entries: Vec<PtxEntry>,
+    /// Module scope `.global` and `.const` variables.
+    pub variables: Vec<PtxVariable>,
+}
+
+/// A kernel and its parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PtxEntry {
+    /// The (mangled) name, which [crate::driver::CudaModule::load_function()] accepts.
+    pub name: String,
+    pub params: Vec<PtxParam>,
+}
+
+/// A `.param` of a [PtxEntry].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PtxParam {
+    pub name: String,
+    /// The element type without the leading `.`, e.g. `u64`. Structs passed by value are
+    /// arrays of `b8`.
+    pub ty: String,
+    /// The size in bytes, which the kernel argument must match.
+    pub size: usize,
+    pub align: usize,
+}
+
+/// Where a [PtxVariable] lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateSpace {
+    /// `.global`, e.g. `__device__` variables.
+    Global,
+    /// `.const`, e.g. `__constant__` variables.
+    Const,
+}
+
+/// A module scope variable, which [crate::driver::CudaModule::get_global()] accepts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PtxVariable {
+    pub name: String,
+    pub space: StateSpace,
+    /// The element type without the leading `.`, e.g. `f32` or `texref`.
+    pub ty: String,
+    /// The size in bytes. `None` for `extern` arrays without a length and opaque types
+    /// like `texref`.
+    pub size: Option<usize>,
+    pub align: usize,
+}
+
+/// Why [PtxInfo::parse()] failed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PtxParseError {
+    /// The [super::Ptx] is a cubin, or a file that is not PTX text.
+    NotText,
+    Syntax {
+        /// 1-based line number.
+        line: usize,
+        message: &'static str,
+    },
+}
+
+impl fmt::Display for PtxParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotText => f.write_str("not PTX text"),
+            Self::Syntax { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PtxParseError {}
+
+/// The size in bytes of a fundamental type, without the leading `.`.
+fn type_size(ty: &str) -> Option<usize> {
+    Some(match ty {
+        "b8" | "u8" | "s8" => 1,
+        "b16" | "u16" | "s16" | "f16" | "bf16" => 2,
+        "b32" | "u32" | "s32" | "f32" | "f16x2" | "bf16x2" | "tf32" => 4,
+        "b64" | "u64" | "s64" | "f64" => 8,
+        "b128" => 16,
+        _ => return None,
+    })
+}
+
+/// Directives that may appear in a declaration but don't change its layout.
+const IGNORED: &[&str] = &[
+    ".visible", ".extern", ".weak", ".common", ".param", ".global", ".const", ".shared", ".local",
+    ".generic",
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+fn is_word(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'_' | b'$' | b'%' | b'.')
+}
+
+/// Splits `src` into words, numbers, strings and single punctuation characters, skipping
+/// comments. Directives are split at each `.`, so `.ptr.global` is `.ptr` and `.global`.
+fn tokenize(src: &str) -> Vec<Token<'_>> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !bytes[i..].starts_with(b"*/") {
+                    line += usize::from(bytes[i] == b'\n');
+                    i += 1;
+                }
+                i = (i + 2).min(bytes.len());
+            }
+            b'"' => {
+                let token_line = line;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    line += usize::from(bytes[i] == b'\n');
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                tokens.push(Token {
+                    text: &src[start..i],
+                    line: token_line,
+                });
+            }
+            c if is_word(c) => {
+                while i < bytes.len() && is_word(bytes[i]) {
+                    i += 1;
+                }
+                let word = &src[start..i];
+                if let Some(directives) = word.strip_prefix('.') {
+                    let mut at = start;
+                    for part in directives.split('.') {
+                        let len = part.len() + 1;
+                        tokens.push(Token {
+                            text: &src[at..at + len],
+                            line,
+                        });
+                        at += len;
+                    }
+                } else {
+                    tokens.push(Token { text: word, line });
+                }
+            }
+            _ => {
+                i += src[i..].chars().next().map_or(1, char::len_utf8);
+                tokens.push(Token {
+                    text: &src[start..i],
+                    line,
+                });
+            }
+        }
+    }
+    tokens
+}
+
+/// A declared type, alignment and name, e.g. of `.param .align 8 .b8 meta[16]`.
+struct Declarator<'a> {
+    ty: &'a str,
+    name: &'a str,
+    size: Option<usize>,
+    align: usize,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|t| t.text)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.peek() == Some(text);
+        self.pos += usize::from(found);
+        found
+    }
+
+    fn error(&self, message: &'static str) -> PtxParseError {
+        let line = self
+            .tokens
+            .get(self.pos.min(self.tokens.len().saturating_sub(1)))
+            .map_or(0, |t| t.line);
+        PtxParseError::Syntax { line, message }
+    }
+
+    fn expect(&mut self, text: &str, message: &'static str) -> Result<(), PtxParseError> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn word(&mut self, message: &'static str) -> Result<&'a str, PtxParseError> {
+        match self.peek() {
+            Some(w) if w.bytes().all(is_word) => {
+                self.pos += 1;
+                Ok(w)
+            }
+            _ => Err(self.error(message)),
+        }
+    }
+
+    fn number<T: core::str::FromStr>(&mut self, message: &'static str) -> Result<T, PtxParseError> {
+        let word = self.word(message)?;
+        word.parse().map_err(|_| {
+            self.pos -= 1;
+            self.error(message)
+        })
+    }
+
+    /// Parses the directives, name and array dimensions of a declaration.
+    fn declarator(&mut self) -> Result<Declarator<'a>, PtxParseError> {
+        let mut ty = None;
+        let mut align = None;
+        let mut vector = 1;
+        let mut pointer = false;
+        let name = loop {
+            let word = self.word("expected a name")?;
+            match word {
+                ".align" => {
+                    let n = self.number("expected an alignment")?;
+                    // After `.ptr`, this is the alignment of what is pointed to.
+                    if !pointer {
+                        align = Some(n);
+                    }
+                }
+                ".ptr" => pointer = true,
+                ".v2" => vector = 2,
+                ".v4" => vector = 4,
+                ".v8" => vector = 8,
+                _ if IGNORED.contains(&word) => {}
+                _ if word.starts_with('.') => ty = Some(&word[1..]),
+                _ => break word,
+            }
+        };
+        let ty = ty.ok_or_else(|| self.error("expected a type"))?;
+        let element = type_size(ty).map(|size| size * vector);
+        let mut size = element;
+        while self.eat("[") {
+            if self.eat("]") {
+                size = None;
+                continue;
+            }
+            let len: usize = self.number("expected an array length")?;
+            size = size.and_then(|s| s.checked_mul(len));
+            self.expect("]", "expected `]`")?;
+        }
+        Ok(Declarator {
+            ty,
+            name,
+            size,
+            align: align.or(element).unwrap_or(1),
+        })
+    }
+
+    /// Parses the name and parameters after `.entry`.
+    fn entry(&mut self) -> Result<PtxEntry, PtxParseError> {
+        let name = self.word("expected an entry name")?.to_owned();
+        let mut params = Vec::new();
+        if self.eat("(") && !self.eat(")") {
+            loop {
+                self.expect(".param", "expected `.param`")?;
+                let decl = self.declarator()?;
+                let size = decl
+                    .size
+                    .ok_or_else(|| self.error("parameter of unknown size"))?;
+                params.push(PtxParam {
+                    name: decl.name.to_owned(),
+                    ty: decl.ty.to_owned(),
+                    size,
+                    align: decl.align,
+                });
+                if !self.eat(",") {
+                    self.expect(")", "expected `)` after the parameters")?;
+                    break;
+                }
+            }
+        }
+        Ok(PtxEntry { name, params })
+    }
+
+    /// Parses the rest of a `.global` or `.const` statement, skipping any initializer.
+    fn variables(&mut self, space: StateSpace) -> Result<Vec<PtxVariable>, PtxParseError> {
+        let mut variables = Vec::new();
+        let first = self.declarator()?;
+        let (ty, align) = (first.ty, first.align);
+        let mut decl = Some(first);
+        loop {
+            let d = match decl.take() {
+                Some(d) => d,
+                None => {
+                    let name = self.word("expected a variable name")?;
+                    let mut size = type_size(ty);
+                    while self.eat("[") {
+                        if self.eat("]") {
+                            size = None;
+                            continue;
+                        }
+                        let len: usize = self.number("expected an array length")?;
+                        size = size.and_then(|s| s.checked_mul(len));
+                        self.expect("]", "expected `]`")?;
+                    }
+                    Declarator {
+                        ty,
+                        name,
+                        size,
+                        align,
+                    }
+                }
+            };
+            variables.push(PtxVariable {
+                name: d.name.to_owned(),
+                space,
+                ty: d.ty.to_owned(),
+                size: d.size,
+                align: d.align,
+            });
+            if self.eat("=") {
+                while !self.eat(";") {
+                    self.next().ok_or_else(|| self.error("expected `;`"))?;
+                }
+                break;
+            }
+            if self.eat(";") {
+                break;
+            }
+            self.expect(",", "expected `;`")?;
+        }
+        Ok(variables)
+    }
+}
+
+impl PtxInfo {
+    /// Parses the declarations of PTX text. Instructions and function bodies are skipped, so
+    /// this succeeds on PTX that `ptxas` would reject.
+    pub fn parse(src: &str) -> Result<Self, PtxParseError> {
+        let mut p = Parser {
+            tokens: tokenize(src),
+            pos: 0,
+        };
+        let mut info = Self::default();
+        // Nesting of `{}` and `()`: only top level statements are declarations.
+        let mut braces = 0usize;
+        let mut parens = 0usize;
+        while let Some(token) = p.next() {
+            match token.text {
+                "{" => braces += 1,
+                "}" => {
+                    braces = braces.checked_sub(1).ok_or(PtxParseError::Syntax {
+                        line: token.line,
+                        message: "unmatched `}`",
+                    })?;
+                }
+                "(" => parens += 1,
+                ")" => parens = parens.saturating_sub(1),
+                _ if braces > 0 || parens > 0 => {}
+                ".version" => {
+                    let version = p
+                        .word("expected a version")?
+                        .split_once('.')
+                        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+                        .ok_or(PtxParseError::Syntax {
+                            line: token.line,
+                            message: "expected a version like `7.0`",
+                        })?;
+                    info.version = Some(version);
+                }
+                ".target" => loop {
+                    info.target.push(p.word("expected a target")?.to_owned());
+                    if !p.eat(",") {
+                        break;
+                    }
+                },
+                ".address_size" => info.address_size = Some(p.number("expected an address size")?),
+                ".entry" => info.entries.push(p.entry()?),
+                ".global" => info.variables.extend(p.variables(StateSpace::Global)?),
+                ".const" => info.variables.extend(p.variables(StateSpace::Const)?),
+                _ => {}
+            }
+        }
+        if braces > 0 {
+            return Err(p.error("unterminated `{`"));
+        }
+        Ok(info)
+    }
+
+    /// The entry named `name`.
+    pub fn entry(&self, name: &str) -> Option<&PtxEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// The variable named `name`.
+    pub fn variable(&self, name: &str) -> Option<&PtxVariable> {
+        self.variables.iter().find(|v| v.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIN_PTX: &str = include_str!("../../examples/sin.ptx");
+
+    fn param(name: &str, ty: &str, size: usize, align: usize) -> PtxParam {
+        PtxParam {
+            name: name.into(),
+            ty: ty.into(),
+            size,
+            align,
+        }
+    }
+
+    #[test]
+    fn test_parse_sin_ptx() {
+        let info = PtxInfo::parse(SIN_PTX).unwrap();
+        assert_eq!(info.version, Some((7, 3)));
+        assert_eq!(info.target, ["sm_52"]);
+        assert_eq!(info.address_size, Some(64));
+
+        assert_eq!(info.entries.len(), 1);
+        let entry = info.entry("sin_kernel").unwrap();
+        assert_eq!(
+            entry.params,
+            [
+                param("sin_kernel_param_0", "u64", 8, 8),
+                param("sin_kernel_param_1", "u64", 8, 8),
+                param("sin_kernel_param_2", "u32", 4, 4),
+            ]
+        );
+
+        // The `.local` depot and registers inside the body are not module variables.
+        assert_eq!(
+            info.variables,
+            [PtxVariable {
+                name: "__cudart_i2opi_f".into(),
+                space: StateSpace::Global,
+                ty: "b8".into(),
+                size: Some(24),
+                align: 4,
+            }]
+        );
+        assert_eq!(info.variable("__cudart_i2opi_f").unwrap().size, Some(24));
+        assert!(info.entry("sin").is_none());
+    }
+
+    #[test]
+    fn test_parse_header_only() {
+        // Everything up to the first entry of sin.ptx.
+        let header = SIN_PTX.split(".visible .entry").next().unwrap();
+        let info = PtxInfo::parse(header).unwrap();
+        assert_eq!(info.version, Some((7, 3)));
+        assert!(info.entries.is_empty());
+        assert_eq!(info.variables.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_params() {
+        const PTX: &str = "
+.version 8.0
+.target sm_80, debug
+.address_size 64
+
+.visible .entry kernel(
+    .param .align 8 .b8 kernel_param_0[2056],
+    .param .u64 .ptr .global .align 4 kernel_param_1,
+    .param .u8 kernel_param_2,
+    .param .f16 kernel_param_3,
+    .param .v2 .u32 kernel_param_4,
+    .param .align 16 .b8 kernel_param_5[2][8]
+)
+.maxntid 256, 1, 1
+{
+    ret;
+}
+
+.entry no_params()
+{
+    ret;
+}
+
+.visible .entry no_parens
+{
+    ret;
+}
+";
+        let info = PtxInfo::parse(PTX).unwrap();
+        assert_eq!(info.version, Some((8, 0)));
+        assert_eq!(info.target, ["sm_80", "debug"]);
+        assert_eq!(
+            info.entry("kernel").unwrap().params,
+            [
+                param("kernel_param_0", "b8", 2056, 8),
+                param("kernel_param_1", "u64", 8, 8),
+                param("kernel_param_2", "u8", 1, 1),
+                param("kernel_param_3", "f16", 2, 2),
+                param("kernel_param_4", "u32", 8, 8),
+                param("kernel_param_5", "b8", 16, 16),
+            ]
+        );
+        assert!(info.entry("no_params").unwrap().params.is_empty());
+        assert!(info.entry("no_parens").unwrap().params.is_empty());
+    }
+
+    #[test]
+    fn test_parse_variables_and_functions() {
+        const PTX: &str = r#"
+.version 7.0
+.target sm_70
+.address_size 64
+
+// .entry commented_out(.param .u32 x)
+/* .global .u32 also_commented; */
+.const .align 4 .f32 scales[4] = {0f3F800000, 0f40000000, 0f40400000, 0f40800000};
+.extern .global .align 1 .b8 dynamic[];
+.visible .global .u32 a, b[2];
+.global .texref tex;
+.global .v4 .f32 color;
+
+.func (.param .b32 func_retval0) helper(
+    .param .b64 .ptr .global .align 4 helper_param_0
+)
+{
+    .reg .b32 %r<2>;
+    .pragma "nounroll; {";
+    st.param.b32 [func_retval0+0], %r1;
+    ret;
+}
+
+.visible .entry _Z4fillIfLi128EEvPT_(
+    .param .u64 _Z4fillIfLi128EEvPT__param_0
+)
+{
+    .shared .align 4 .b8 smem[128];
+    ret;
+}
+"#;
+        let info = PtxInfo::parse(PTX).unwrap();
+        assert_eq!(info.entries.len(), 1);
+        assert_eq!(info.entries[0].name, "_Z4fillIfLi128EEvPT_");
+        assert_eq!(info.entries[0].params.len(), 1);
+
+        let variables: Vec<_> = info
+            .variables
+            .iter()
+            .map(|v| (v.name.as_str(), v.space, v.ty.as_str(), v.size, v.align))
+            .collect();
+        assert_eq!(
+            variables,
+            [
+                ("scales", StateSpace::Const, "f32", Some(16), 4),
+                ("dynamic", StateSpace::Global, "b8", None, 1),
+                ("a", StateSpace::Global, "u32", Some(4), 4),
+                ("b", StateSpace::Global, "u32", Some(8), 4),
+                ("tex", StateSpace::Global, "texref", None, 1),
+                ("color", StateSpace::Global, "f32", Some(16), 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let line = |src: &str| match PtxInfo::parse(src) {
+            Err(PtxParseError::Syntax { line, .. }) => line,
+            r => panic!("{r:?}"),
+        };
+        assert_eq!(line(".version 7\n"), 1);
+        assert_eq!(line(".version 7.0\n.entry k(\n.param .u32 a\n"), 3);
+        assert_eq!(line(".entry k(.param .texref t)"), 1);
+        assert_eq!(line(".entry k(.param .u32 a, .u32 b)"), 1);
+        assert_eq!(line(".entry k()\n{\nret;\n"), 3);
+        assert_eq!(line(".entry k()\n}\n"), 2);
+        assert_eq!(line(".global .f32 x[n];"), 1);
+        assert_eq!(line(".global .f32 x"), 1);
+        assert_eq!(line(".global .align 4 x;"), 1);
+
+        assert_eq!(PtxInfo::parse("").unwrap(), PtxInfo::default());
+        assert_eq!(
+            std::format!(
+                "{}",
+                PtxParseError::Syntax {
+                    line: 3,
+                    message: "expected `)` after the parameters"
+                }
+            ),
+            "line 3: expected `)` after the parameters"
+        );
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens: Vec<_> =
+            tokenize(".param .u64 .ptr.global x[2]; // comment\n\"a b\" 0f3F800000")
+                .iter()
+                .map(|t| (t.text, t.line))
+                .collect();
+        assert_eq!(
+            tokens,
+            [
+                (".param", 1),
+                (".u64", 1),
+                (".ptr", 1),
+                (".global", 1),
+                ("x", 1),
+                ("[", 1),
+                ("2", 1),
+                ("]", 1),
+                (";", 1),
+                ("\"a b\"", 2),
+                ("0f3F800000", 2),
+            ]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! A thin wrapper around [sys] providing [Result]s with [NvrtcError].
 
 use super::sys::{self};
+use crate::ErrorKind;
 use core::{
     ffi::{c_char, c_int, CStr},
     mem::MaybeUninit,
@@ -32,6 +33,32 @@ impl std::fmt::Display for NvrtcError {
 #[cfg(feature = "std")]
 impl std::error::Error for NvrtcError {}
 
+impl NvrtcError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::nvrtcResult::NVRTC_ERROR_OUT_OF_MEMORY => ErrorKind::OutOfMemory,
+            sys::nvrtcResult::NVRTC_ERROR_INVALID_INPUT
+            | sys::nvrtcResult::NVRTC_ERROR_INVALID_OPTION
+            | sys::nvrtcResult::NVRTC_ERROR_NAME_EXPRESSION_NOT_VALID => ErrorKind::InvalidValue,
+            sys::nvrtcResult::NVRTC_ERROR_INVALID_PROGRAM => ErrorKind::InvalidHandle,
+            sys::nvrtcResult::NVRTC_ERROR_COMPILATION
+            | sys::nvrtcResult::NVRTC_ERROR_BUILTIN_OPERATION_FAILURE => ErrorKind::Compilation,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// The `(major, minor)` version of nvrtc.
+///
+/// See [nvrtcVersion() docs](https://docs.nvidia.com/cuda/nvrtc/index.html)
+pub fn version() -> Result<(c_int, c_int), NvrtcError> {
+    let mut major: c_int = 0;
+    let mut minor: c_int = 0;
+    unsafe { sys::nvrtcVersion(&mut major as *mut _, &mut minor as *mut _) }.result()?;
+    Ok((major, minor))
+}
+
 /// Creates a program from source code `src`. This should be source code from a .cu file.
 ///
 /// See [nvrtcCreateProgram() docs](https://docs.nvidia.com/cuda/nvrtc/index.html#group__compilation_1g9ae65f68911d1cf0adda2af4ad8cb458)
@@ -46,15 +73,51 @@ impl std::error::Error for NvrtcError {}
 /// references to `src` and `name`. The memory containing the compiled
 /// code and name must not be dropped until the `nvrtcProgram` is destroyed.
 pub fn create_program(src: &CStr, name: Option<&CStr>) -> Result<sys::nvrtcProgram, NvrtcError> {
+    create_program_with_headers(src, name, &[])
+}
+
+/// Like [create_program()], but also provides `headers` as `(include name, contents)` pairs,
+/// so `#include "<include name>"` in `src` resolves without touching the file system.
+///
+/// See [nvrtcCreateProgram() docs](https://docs.nvidia.com/cuda/nvrtc/index.html#group__compilation_1g9ae65f68911d1cf0adda2af4ad8cb458)
+///
+/// Example:
+/// ```rust
+/// # use cudarc::nvrtc::result::*;
+/// let prog = create_program_with_headers(
+///     c"#include \"scale.h\"\nextern \"C\" __global__ void kernel(float *x) { x[0] *= SCALE; }",
+///     None,
+///     &[(c"scale.h", c"#define SCALE 2.0f")],
+/// )
+/// .unwrap();
+/// ```
+///
+/// Like with [create_program()], the memory containing the headers must not be dropped until
+/// the `nvrtcProgram` is destroyed.
+pub fn create_program_with_headers(
+    src: &CStr,
+    name: Option<&CStr>,
+    headers: &[(&CStr, &CStr)],
+) -> Result<sys::nvrtcProgram, NvrtcError> {
+    let include_names: Vec<*const c_char> = headers.iter().map(|(n, _)| n.as_ptr()).collect();
+    let contents: Vec<*const c_char> = headers.iter().map(|(_, c)| c.as_ptr()).collect();
     let mut prog = MaybeUninit::uninit();
     unsafe {
         sys::nvrtcCreateProgram(
             prog.as_mut_ptr(),
             src.as_ptr(),
             name.map(|n| n.as_ptr()).unwrap_or(std::ptr::null()),
-            0,
-            std::ptr::null(),
-            std::ptr::null(),
+            headers.len() as c_int,
+            if headers.is_empty() {
+                std::ptr::null()
+            } else {
+                contents.as_ptr()
+            },
+            if headers.is_empty() {
+                std::ptr::null()
+            } else {
+                include_names.as_ptr()
+            },
         )
         .result()?;
         Ok(prog.assume_init())
@@ -102,6 +165,41 @@ pub unsafe fn destroy_program(prog: sys::nvrtcProgram) -> Result<(), NvrtcError>
     sys::nvrtcDestroyProgram(&prog as *const _ as *mut _).result()
 }
 
+/// Registers a name expression like `my_kernel<float, 128>` (or `&my_var<int>` for a variable)
+/// to be instantiated by [compile_program()]. Call [get_lowered_name()] after compiling to
+/// get the mangled name of the instantiation.
+///
+/// See [nvrtcAddNameExpression() docs](https://docs.nvidia.com/cuda/nvrtc/index.html)
+///
+/// # Safety
+///
+/// `prog` must be created from [create_program()] and not have been freed by [destroy_program()].
+pub unsafe fn add_name_expression(
+    prog: sys::nvrtcProgram,
+    name_expression: &CStr,
+) -> Result<(), NvrtcError> {
+    sys::nvrtcAddNameExpression(prog, name_expression.as_ptr()).result()
+}
+
+/// The mangled name of a name expression registered with [add_name_expression()].
+///
+/// See [nvrtcGetLoweredName() docs](https://docs.nvidia.com/cuda/nvrtc/index.html)
+///
+/// # Safety
+///
+/// `prog` must be created from [create_program()], compiled with [compile_program()], and
+/// not have been freed by [destroy_program()].
+pub unsafe fn get_lowered_name(
+    prog: sys::nvrtcProgram,
+    name_expression: &CStr,
+) -> Result<CString, NvrtcError> {
+    let mut lowered_name: *const c_char = std::ptr::null();
+    sys::nvrtcGetLoweredName(prog, name_expression.as_ptr(), &mut lowered_name as *mut _)
+        .result()?;
+    // The lowered name is owned by `prog`, so copy it out.
+    Ok(CString::from(CStr::from_ptr(lowered_name)))
+}
+
 /// Extract the ptx associated with `prog`. Call [compile_program()] before this.
 ///
 /// See [nvrtcGetPTX() docs](https://docs.nvidia.com/cuda/nvrtc/index.html#group__compilation_1gc9a66bbbd47c256f4a8955517b3965da)
@@ -121,6 +219,62 @@ pub unsafe fn get_ptx(prog: sys::nvrtcProgram) -> Result<Vec<c_char>, NvrtcError
     Ok(ptx_src)
 }
 
+/// Extract the cubin associated with `prog`. Call [compile_program()] with an `sm_XX`
+/// architecture before this, otherwise the result is empty.
+///
+/// See [nvrtcGetCUBIN() docs](https://docs.nvidia.com/cuda/nvrtc/index.html)
+/// and [nvrtcGetCUBINSize() docs](https://docs.nvidia.com/cuda/nvrtc/index.html).
+///
+/// # Safety
+///
+/// `prog` must be created from [create_program()] and not have been freed by [destroy_program()].
+pub unsafe fn get_cubin(prog: sys::nvrtcProgram) -> Result<Vec<u8>, NvrtcError> {
+    let mut size: usize = 0;
+    sys::nvrtcGetCUBINSize(prog, &mut size as *mut _).result()?;
+
+    let mut cubin: Vec<u8> = std::vec![0; size];
+    sys::nvrtcGetCUBIN(prog, cubin.as_mut_ptr() as *mut c_char).result()?;
+    Ok(cubin)
+}
+
+/// Extract the LTO-IR associated with `prog`. Call [compile_program()] with `-dlto` before
+/// this, otherwise the result is empty.
+///
+/// See [nvrtcGetLTOIR() docs](https://docs.nvidia.com/cuda/nvrtc/index.html)
+/// and [nvrtcGetLTOIRSize() docs](https://docs.nvidia.com/cuda/nvrtc/index.html).
+///
+/// # Safety
+///
+/// `prog` must be created from [create_program()] and not have been freed by [destroy_program()].
+#[cfg(not(any(
+    feature = "cuda-11040",
+    feature = "cuda-11050",
+    feature = "cuda-11060",
+    feature = "cuda-11070",
+    feature = "cuda-11080"
+)))]
+pub unsafe fn get_ltoir(prog: sys::nvrtcProgram) -> Result<Vec<u8>, NvrtcError> {
+    let mut size: usize = 0;
+    sys::nvrtcGetLTOIRSize(prog, &mut size as *mut _).result()?;
+
+    let mut ltoir: Vec<u8> = std::vec![0; size];
+    sys::nvrtcGetLTOIR(prog, ltoir.as_mut_ptr() as *mut c_char).result()?;
+    Ok(ltoir)
+}
+
+/// The architectures this nvrtc can compile for, as numbers like `75` for `sm_75`.
+///
+/// See [nvrtcGetSupportedArchs() docs](https://docs.nvidia.com/cuda/nvrtc/index.html)
+/// and [nvrtcGetNumSupportedArchs() docs](https://docs.nvidia.com/cuda/nvrtc/index.html).
+pub fn get_supported_archs() -> Result<Vec<c_int>, NvrtcError> {
+    let mut num: c_int = 0;
+    unsafe { sys::nvrtcGetNumSupportedArchs(&mut num as *mut _) }.result()?;
+
+    let mut archs: Vec<c_int> = std::vec![0; num as usize];
+    unsafe { sys::nvrtcGetSupportedArchs(archs.as_mut_ptr()) }.result()?;
+    Ok(archs)
+}
+
 /// Extract log from a compiled program.
 ///
 /// See [nvrtcGetProgramLog() docs](https://docs.nvidia.com/cuda/nvrtc/index.html#group__compilation_1g74c550e5cab81efbd59e4f72579edbd1)
@@ -174,6 +328,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compile_with_headers() {
+        const SRC: &CStr = c"#include \"scale.h\"
+        extern \"C\" __global__ void kernel(float *x) { x[0] *= SCALE; }";
+        let prog =
+            create_program_with_headers(SRC, None, &[(c"scale.h", c"#define SCALE 2.0f")]).unwrap();
+        unsafe { compile_program::<&str>(prog, &[]) }.unwrap();
+        unsafe { destroy_program(prog) }.unwrap();
+
+        let prog = create_program(SRC, None).unwrap();
+        assert!(unsafe { compile_program::<&str>(prog, &[]) }.is_err());
+        unsafe { destroy_program(prog) }.unwrap();
+    }
+
+    #[test]
+    fn test_lowered_name() {
+        let prog = create_program(
+            c"template <typename T> __global__ void kernel(T *out) { out[0] = T(1); }",
+            None,
+        )
+        .unwrap();
+        unsafe { add_name_expression(prog, c"kernel<float>") }.unwrap();
+        unsafe { compile_program::<&str>(prog, &[]) }.unwrap();
+        let lowered = unsafe { get_lowered_name(prog, c"kernel<float>") }.unwrap();
+        assert_eq!(lowered.to_str().unwrap(), "_Z6kernelIfEvPT_");
+        unsafe { destroy_program(prog) }.unwrap();
+    }
+
     #[test]
     fn test_get_ptx() {
         const SRC: &CStr =
@@ -1,35 +1,47 @@
 //! Safe abstractions around [crate::nvrtc::result] for compiling PTX files.
 //!
-//! Call [compile_ptx()] or [compile_ptx_with_opts()].
+//! Call [compile_ptx()] or [compile_ptx_with_opts()], or [compile_cubin()] to skip the PTX JIT.
 
-use super::{result, sys};
+use super::{result, sys, Diagnostic, PtxInfo, PtxParseError, Severity};
 
 use core::ffi::{c_char, CStr};
 use std::ffi::CString;
-use std::{borrow::ToOwned, path::PathBuf, string::String, vec::Vec};
+use std::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    path::PathBuf,
+    string::String,
+    vec::Vec,
+};
 
 /// An opaque structure representing a compiled PTX program
 /// output from [compile_ptx()] or [compile_ptx_with_opts()].
 ///
 /// Can also be created from a [Ptx::from_file] and [Ptx::from_src]
 #[derive(Debug, Clone)]
-pub struct Ptx(pub(crate) PtxKind);
+pub struct Ptx {
+    pub(crate) kind: PtxKind,
+    /// Maps each of [CompileOptions::name_expressions] to its mangled name.
+    pub(crate) lowered_names: BTreeMap<String, String>,
+    /// Warnings and remarks from compiling this.
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
 
 impl Ptx {
     /// Creates a Ptx from a pre-compiled .ptx file.
     pub fn from_file<P: Into<PathBuf>>(path: P) -> Self {
-        Self(PtxKind::File(path.into()))
+        PtxKind::File(path.into()).into()
     }
 
     /// Creates a Ptx from the source string of a pre-compiled .ptx
     /// file.
     pub fn from_src<S: Into<String>>(src: S) -> Self {
-        Self(PtxKind::Src(src.into()))
+        PtxKind::Src(src.into()).into()
     }
 
     /// Creates a Ptx from binary CUBIN data.
     pub fn from_binary(data: Vec<u8>) -> Self {
-        Self(PtxKind::Binary(data))
+        PtxKind::Binary(data).into()
     }
 
     /// Get the compiled source as a string.
@@ -39,7 +51,7 @@ impl Ptx {
     /// Panics if the Ptx was created from binary CUBIN data, as CUBIN cannot be
     /// converted to a string representation.
     pub fn to_src(&self) -> String {
-        match &self.0 {
+        match &self.kind {
             PtxKind::Image(bytes) => unsafe { CStr::from_ptr(bytes.as_ptr()) }
                 .to_str()
                 .expect("Unable to convert bytes to str.")
@@ -54,9 +66,32 @@ impl Ptx {
         }
     }
 
+    /// Parses the kernel signatures, variables and target of PTX text, see [PtxInfo]. Files
+    /// are read first. Cubins, and files that can't be read or are not text, are
+    /// [PtxParseError::NotText].
+    pub fn info(&self) -> Result<PtxInfo, PtxParseError> {
+        match &self.kind {
+            PtxKind::Image(image) => {
+                let bytes: Vec<u8> = image
+                    .iter()
+                    .take_while(|&&c| c != 0)
+                    .map(|&c| c as u8)
+                    .collect();
+                PtxInfo::parse(&String::from_utf8_lossy(&bytes))
+            }
+            PtxKind::Src(src) => PtxInfo::parse(src),
+            PtxKind::File(path) => match std::fs::read_to_string(path) {
+                // `cuModuleLoad()` also takes cubin and fatbin files.
+                Ok(src) if !src.starts_with("\x7fELF") => PtxInfo::parse(&src),
+                _ => Err(PtxParseError::NotText),
+            },
+            PtxKind::Binary(_) => Err(PtxParseError::NotText),
+        }
+    }
+
     /// If `self` is a compiled image (obtained using [compile_ptx] or [compile_ptx_with_opts]), get as bytes.
     pub fn as_bytes(&self) -> Option<&[u8]> {
-        match &self.0 {
+        match &self.kind {
             PtxKind::Image(bytes) => unsafe {
                 Some(std::slice::from_raw_parts(
                     bytes.as_ptr().cast(),
@@ -68,6 +103,38 @@ impl Ptx {
     }
 }
 
+impl Ptx {
+    /// The mangled name that a name expression from [CompileOptions::name_expressions]
+    /// was instantiated as. [crate::driver::CudaModule::load_function()] accepts the name
+    /// expression directly.
+    pub fn lowered_name(&self, name_expression: &str) -> Option<&str> {
+        self.lowered_names.get(name_expression).map(String::as_str)
+    }
+
+    /// The warnings and remarks nvrtc reported while compiling this. Empty if this wasn't
+    /// compiled in this process, e.g. if it came from a [crate::nvrtc::KernelCache].
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// All name expressions with their mangled names.
+    pub fn lowered_names(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lowered_names
+            .iter()
+            .map(|(expr, name)| (expr.as_str(), name.as_str()))
+    }
+}
+
+impl From<PtxKind> for Ptx {
+    fn from(kind: PtxKind) -> Self {
+        Self {
+            kind,
+            lowered_names: BTreeMap::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
 impl<S: Into<String>> From<S> for Ptx {
     fn from(value: S) -> Self {
         Self::from_src(value)
@@ -117,10 +184,95 @@ pub fn compile_ptx_with_opts<S: AsRef<str>>(
     src: S,
     opts: CompileOptions,
 ) -> Result<Ptx, CompileError> {
-    let prog = Program::create(src, opts.name.as_deref())?;
+    let prog = Program::create(src, opts.name.as_deref(), &opts.headers)?;
     prog.compile(opts)
 }
 
+/// Compiles `src` straight to a cubin (SASS) for the `sm_XX` architecture in `opts.arch`,
+/// which the driver loads without JIT compiling PTX. The cubin only runs on devices of that
+/// architecture, see [sm_arch()] to pick one for a device.
+///
+/// Example:
+/// ```ignore
+/// # use cudarc::nvrtc::*;
+/// let arch = sm_arch(ctx.compute_capability()?)?.unwrap();
+/// let opts = CompileOptions {
+///     arch: Some(arch),
+///     ..Default::default()
+/// };
+/// let cubin = compile_cubin("extern \"C\" __global__ void kernel() { }", opts).unwrap();
+/// let module = ctx.load_module(cubin)?;
+/// ```
+pub fn compile_cubin<S: AsRef<str>>(src: S, opts: CompileOptions) -> Result<Ptx, CompileError> {
+    let prog = Program::create(src, opts.name.as_deref(), &opts.headers)?;
+    prog.compile_cubin(opts)
+}
+
+/// Compiles `src` to LTO-IR for the architecture in `opts.arch`, to be linked with nvJitLink.
+/// [CompileOptions::dlto] is set if `-dlto` isn't already in the options.
+#[cfg(not(any(
+    feature = "cuda-11040",
+    feature = "cuda-11050",
+    feature = "cuda-11060",
+    feature = "cuda-11070",
+    feature = "cuda-11080"
+)))]
+pub fn compile_ltoir<S: AsRef<str>>(src: S, opts: CompileOptions) -> Result<Vec<u8>, CompileError> {
+    let prog = Program::create(src, opts.name.as_deref(), &opts.headers)?;
+    prog.compile_ltoir(opts)
+}
+
+/// The architectures this nvrtc can compile for, as numbers like `75` for `sm_75`.
+pub fn supported_archs() -> Result<Vec<i32>, result::NvrtcError> {
+    result::get_supported_archs()
+}
+
+/// Every `sm_XX` architecture [sm_arch()] knows the name of.
+const SM_ARCHS: &[(i32, &str)] = &[
+    (35, "sm_35"),
+    (37, "sm_37"),
+    (50, "sm_50"),
+    (52, "sm_52"),
+    (53, "sm_53"),
+    (60, "sm_60"),
+    (61, "sm_61"),
+    (62, "sm_62"),
+    (70, "sm_70"),
+    (72, "sm_72"),
+    (75, "sm_75"),
+    (80, "sm_80"),
+    (86, "sm_86"),
+    (87, "sm_87"),
+    (89, "sm_89"),
+    (90, "sm_90"),
+    (100, "sm_100"),
+    (101, "sm_101"),
+    (103, "sm_103"),
+    (110, "sm_110"),
+    (120, "sm_120"),
+    (121, "sm_121"),
+];
+
+/// Picks the `sm_XX` architecture to compile cubins for a device with the given compute
+/// capability, e.g. from [crate::driver::CudaContext::compute_capability()].
+///
+/// This is the newest architecture nvrtc supports with the same major version and a minor
+/// version no newer than the device's, since cubins are only compatible within a major
+/// version. Returns `None` if there is no such architecture, in which case compile to PTX
+/// instead.
+pub fn sm_arch(compute_capability: (i32, i32)) -> Result<Option<&'static str>, result::NvrtcError> {
+    Ok(pick_sm_arch(&supported_archs()?, compute_capability))
+}
+
+fn pick_sm_arch(supported: &[i32], (major, minor): (i32, i32)) -> Option<&'static str> {
+    SM_ARCHS
+        .iter()
+        .rev()
+        .filter(|(arch, _)| arch / 10 == major && arch % 10 <= minor)
+        .find(|(arch, _)| supported.contains(arch))
+        .map(|(_, name)| *name)
+}
+
 pub(crate) struct Program {
     prog: sys::nvrtcProgram,
 
@@ -128,40 +280,148 @@ pub(crate) struct Program {
     // dropped until after the nvrtcProgram.
     _src: CString,
     _name: Option<CString>,
+    _headers: Vec<(CString, CString)>,
 }
 
 impl Program {
-    pub(crate) fn create<S: AsRef<str>>(src: S, name: Option<&str>) -> Result<Self, CompileError> {
+    pub(crate) fn create<S: AsRef<str>>(
+        src: S,
+        name: Option<&str>,
+        headers: &HeaderSet,
+    ) -> Result<Self, CompileError> {
         let src = CString::new(src.as_ref().as_bytes())
             .expect("program code cannot contain null terminators");
         let name =
             name.map(|s| CString::new(s).expect("program name cannot contain null terminators"));
-        let prog =
-            result::create_program(&src, name.as_deref()).map_err(CompileError::CreationError)?;
+        let headers: Vec<(CString, CString)> = headers
+            .iter()
+            .map(|(name, contents)| {
+                (
+                    CString::new(name).expect("header name cannot contain null terminators"),
+                    CString::new(contents).expect("header cannot contain null terminators"),
+                )
+            })
+            .collect();
+        let header_refs: Vec<(&CStr, &CStr)> = headers
+            .iter()
+            .map(|(name, contents)| (name.as_c_str(), contents.as_c_str()))
+            .collect();
+        let prog = result::create_program_with_headers(&src, name.as_deref(), &header_refs)
+            .map_err(CompileError::CreationError)?;
         Ok(Self {
             prog,
             _src: src,
             _name: name,
+            _headers: headers,
         })
     }
 
     pub(crate) fn compile(self, opts: CompileOptions) -> Result<Ptx, CompileError> {
+        let (lowered_names, diagnostics) = self.compile_program(opts)?;
+        let image = unsafe { result::get_ptx(self.prog) }.map_err(CompileError::GetPtxError)?;
+        Ok(Ptx {
+            kind: PtxKind::Image(image),
+            lowered_names,
+            diagnostics,
+        })
+    }
+
+    pub(crate) fn compile_cubin(self, opts: CompileOptions) -> Result<Ptx, CompileError> {
+        let (lowered_names, diagnostics) = self.compile_program(opts)?;
+        let cubin = unsafe { result::get_cubin(self.prog) }.map_err(CompileError::GetCubinError)?;
+        if cubin.is_empty() {
+            // nvrtc only produces a cubin for real (`sm_XX`) architectures.
+            return Err(CompileError::GetCubinError(result::NvrtcError(
+                sys::nvrtcResult::NVRTC_ERROR_INVALID_OPTION,
+            )));
+        }
+        Ok(Ptx {
+            kind: PtxKind::Binary(cubin),
+            lowered_names,
+            diagnostics,
+        })
+    }
+
+    #[cfg(not(any(
+        feature = "cuda-11040",
+        feature = "cuda-11050",
+        feature = "cuda-11060",
+        feature = "cuda-11070",
+        feature = "cuda-11080"
+    )))]
+    pub(crate) fn compile_ltoir(self, mut opts: CompileOptions) -> Result<Vec<u8>, CompileError> {
+        if !opts
+            .options
+            .iter()
+            .any(|o| o == "-dlto" || o == "--dlink-time-opt")
+        {
+            opts.dlto = true;
+        }
+        self.compile_program(opts)?;
+        unsafe { result::get_ltoir(self.prog) }.map_err(CompileError::GetLtoIrError)
+    }
+
+    /// Compiles, returning the mangled name of each of `opts.name_expressions` and the
+    /// diagnostics from the log.
+    #[allow(clippy::type_complexity)]
+    fn compile_program(
+        &self,
+        mut opts: CompileOptions,
+    ) -> Result<(BTreeMap<String, String>, Vec<Diagnostic>), CompileError> {
+        opts.validate()?;
+        let mut warnings = opts.version_warnings();
+
+        let expressions: Vec<(String, CString)> = std::mem::take(&mut opts.name_expressions)
+            .into_iter()
+            .map(|expr| {
+                let c_expr = CString::new(expr.as_str())
+                    .expect("name expression cannot contain null terminators");
+                (expr, c_expr)
+            })
+            .collect();
+        for (expr, c_expr) in expressions.iter() {
+            unsafe { result::add_name_expression(self.prog, c_expr) }.map_err(|nvrtc| {
+                CompileError::NameExpressionError {
+                    nvrtc,
+                    expression: expr.clone(),
+                }
+            })?;
+        }
+
         let options = opts.build();
 
         unsafe { result::compile_program(self.prog, &options) }.map_err(|e| {
             let log_raw = unsafe { result::get_program_log(self.prog) }.unwrap();
             let log_ptr = log_raw.as_ptr();
             let log = unsafe { CStr::from_ptr(log_ptr) }.to_owned();
+            let mut diagnostics = warnings.clone();
+            diagnostics.extend(Diagnostic::parse_log(&log.to_string_lossy()));
             CompileError::CompileError {
                 nvrtc: e,
                 options,
                 log,
+                diagnostics,
             }
         })?;
 
-        let image = unsafe { result::get_ptx(self.prog) }.map_err(CompileError::GetPtxError)?;
+        let log =
+            unsafe { result::get_program_log(self.prog) }.map_err(CompileError::GetLogError)?;
+        let log = unsafe { CStr::from_ptr(log.as_ptr()) }.to_string_lossy();
+        warnings.extend(Diagnostic::parse_log(&log));
+        let diagnostics = warnings;
 
-        Ok(Ptx(PtxKind::Image(image)))
+        let mut lowered_names = BTreeMap::new();
+        for (expr, c_expr) in expressions {
+            let lowered =
+                unsafe { result::get_lowered_name(self.prog, &c_expr) }.map_err(|nvrtc| {
+                    CompileError::NameExpressionError {
+                        nvrtc,
+                        expression: expr.clone(),
+                    }
+                })?;
+            lowered_names.insert(expr, lowered.to_string_lossy().into_owned());
+        }
+        Ok((lowered_names, diagnostics))
     }
 }
 
@@ -185,6 +445,8 @@ pub enum CompileError {
         nvrtc: result::NvrtcError,
         options: Vec<String>,
         log: CString,
+        /// `log` parsed, see [CompileError::diagnostics()].
+        diagnostics: Vec<Diagnostic>,
     },
 
     /// Error happened during [result::get_program_log()]
@@ -193,28 +455,95 @@ pub enum CompileError {
     /// Error happened during [result::get_ptx()]
     GetPtxError(result::NvrtcError),
 
+    /// Error happened during [result::get_cubin()]
+    GetCubinError(result::NvrtcError),
+
+    /// Error happened during `result::get_ltoir()`
+    GetLtoIrError(result::NvrtcError),
+
+    /// Error happened during [result::add_name_expression()] or
+    /// [result::get_lowered_name()] for `expression`
+    NameExpressionError {
+        nvrtc: result::NvrtcError,
+        expression: String,
+    },
+
     /// Error happened during [result::destroy_program()]
     DestroyError(result::NvrtcError),
+
+    /// [CompileOptions::validate()] found two options that contradict each other.
+    ConflictingOptions {
+        option: &'static str,
+        conflicts_with: &'static str,
+    },
 }
 
 #[cfg(feature = "std")]
 impl std::fmt::Display for CompileError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            Self::CompileError {
+                nvrtc,
+                log,
+                diagnostics,
+                ..
+            } => {
+                write!(f, "nvrtc compilation failed ({:?})", nvrtc.0)?;
+                if diagnostics.is_empty() {
+                    write!(f, "\n{}", log.to_string_lossy().trim_end())
+                } else {
+                    diagnostics.iter().try_for_each(|d| write!(f, "\n{d}"))
+                }
+            }
+            Self::ConflictingOptions {
+                option,
+                conflicts_with,
+            } => write!(
+                f,
+                "CompileOptions::{option} conflicts with CompileOptions::{conflicts_with}"
+            ),
+            _ => write!(f, "{self:?}"),
+        }
     }
 }
 
 #[cfg(feature = "std")]
 impl std::error::Error for CompileError {}
 
+impl CompileError {
+    /// The errors and warnings nvrtc reported, if compilation failed.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Self::CompileError { diagnostics, .. } => diagnostics,
+            _ => &[],
+        }
+    }
+
+    /// The [crate::ErrorKind] of this error.
+    pub fn kind(&self) -> crate::ErrorKind {
+        match self {
+            Self::CompileError { .. } => crate::ErrorKind::Compilation,
+            Self::CreationError(e)
+            | Self::GetLogError(e)
+            | Self::GetPtxError(e)
+            | Self::GetCubinError(e)
+            | Self::GetLtoIrError(e)
+            | Self::DestroyError(e)
+            | Self::NameExpressionError { nvrtc: e, .. } => e.kind(),
+            Self::ConflictingOptions { .. } => crate::ErrorKind::InvalidValue,
+        }
+    }
+}
+
 /// Flags you can pass to the nvrtc compiler.
 /// See <https://docs.nvidia.com/cuda/nvrtc/index.html#group__options>
 /// for all available flags and documentation for what they do.
 ///
 /// All fields of this struct match one of the flags in the documentation.
-/// if a field is `None` it will not be passed to the compiler.
+/// if a field is `None`, `false` or empty it will not be passed to the compiler.
 ///
-/// All fields default to `None`.
+/// All fields default to `None`, `false` or empty. Options without a field can be passed
+/// through `options`.
 ///
 /// *NOTE*: not all flags are currently supported.
 ///
@@ -239,9 +568,253 @@ pub struct CompileOptions {
     pub include_paths: Vec<String>,
     pub arch: Option<&'static str>,
     pub name: Option<String>,
+    /// Compiles with up to this many threads inside nvrtc, `0` for as many as there are
+    /// CPUs. Requires CUDA 12.1 or newer.
+    pub split_compile: Option<usize>,
+    /// The C++ dialect. nvrtc defaults to C++17.
+    pub std: Option<CppStd>,
+    /// Generates debug information for device code, `-G`. This disables optimizations
+    /// unless `dopt` is set.
+    pub device_debug: bool,
+    /// Generates line number information for profilers, `-lineinfo`.
+    pub lineinfo: bool,
+    /// Device code optimizations, `-dopt`. nvrtc enables them unless `device_debug` is set.
+    pub dopt: Option<bool>,
+    /// Relocatable device code, `-rdc`, needed to call device functions of other programs
+    /// when linking with [crate::nvjitlink].
+    pub rdc: Option<bool>,
+    /// Generates LTO-IR instead of PTX, `-dlto`. `compile_ltoir()` sets this.
+    pub dlto: bool,
+    pub extra_device_vectorization: bool,
+    /// Treats functions without an execution space annotation as `__device__`,
+    /// `-default-device` (`--device-as-default-execution-space`).
+    pub default_device: bool,
+    /// Macros to define, as `-D<name>` or `-D<name>=<value>`.
+    pub defines: Vec<(String, Option<String>)>,
+    /// Macros to undefine, `-U<name>`.
+    pub undefines: Vec<String>,
+    /// Assumes kernel pointer parameters are `__restrict__`, `-restrict`.
+    pub restrict: bool,
+    /// Writes the time each compilation phase took as CSV to this file, `-time`. Requires
+    /// CUDA 12.1 or newer.
+    pub time: Option<String>,
+    /// Turns all warnings into errors, `-Werror`.
+    pub warnings_as_errors: bool,
+    /// C++ expressions naming template instantiations to compile, like
+    /// `my_kernel<float, 128>`. The compiled [Ptx] maps each to its mangled name, so they
+    /// can be passed to [crate::driver::CudaModule::load_function()].
+    ///
+    /// Generic code can build these from [crate::types::CudaTypeName], e.g.
+    /// `format!("my_kernel<{}>", T::NAME)`.
+    pub name_expressions: Vec<String>,
+    /// Headers that `#include` finds in memory, before searching `include_paths`.
+    pub headers: HeaderSet,
+}
+
+/// A C++ dialect for [CompileOptions::std].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CppStd {
+    Cpp03,
+    Cpp11,
+    Cpp14,
+    Cpp17,
+    /// Requires CUDA 12.0 or newer.
+    Cpp20,
+}
+
+impl CppStd {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cpp03 => "c++03",
+            Self::Cpp11 => "c++11",
+            Self::Cpp14 => "c++14",
+            Self::Cpp17 => "c++17",
+            Self::Cpp20 => "c++20",
+        }
+    }
+}
+
+/// A reusable set of in-memory headers, so kernels that `#include` them compile on
+/// machines without the headers on disk. Pass with [CompileOptions::headers].
+///
+/// Example:
+/// ```rust
+/// # use cudarc::nvrtc::*;
+/// let headers = HeaderSet::new().with("scale.h", "#define SCALE 2.0f");
+/// let opts = CompileOptions {
+///     headers,
+///     ..Default::default()
+/// };
+/// ```
+///
+/// Headers can be embedded into the binary with `include_str!`:
+/// ```ignore
+/// let headers = HeaderSet::from_static(&[
+///     ("common.cuh", include_str!("kernels/common.cuh")),
+///     ("math.cuh", include_str!("kernels/math.cuh")),
+/// ]);
+/// ```
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct HeaderSet {
+    headers: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+}
+
+impl HeaderSet {
+    /// An empty set.
+    pub const fn new() -> Self {
+        Self {
+            headers: Vec::new(),
+        }
+    }
+
+    /// A set of `(include name, contents)` pairs that are not copied, e.g. from `include_str!`.
+    pub fn from_static(headers: &[(&'static str, &'static str)]) -> Self {
+        let mut set = Self::new();
+        for &(name, contents) in headers {
+            set.insert(name, contents);
+        }
+        set
+    }
+
+    /// Adds a header that `#include "<name>"` resolves to, replacing any with the same name.
+    pub fn insert(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        contents: impl Into<Cow<'static, str>>,
+    ) {
+        let name = name.into();
+        let contents = contents.into();
+        match self.headers.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, c)) => *c = contents,
+            None => self.headers.push((name, contents)),
+        }
+    }
+
+    /// See [HeaderSet::insert()].
+    pub fn with(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        contents: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.insert(name, contents);
+        self
+    }
+
+    /// Adds all headers of `other`, replacing any with the same names.
+    pub fn extend_from(&mut self, other: &HeaderSet) {
+        for (name, contents) in other.headers.iter() {
+            self.insert(name.clone(), contents.clone());
+        }
+    }
+
+    /// The contents of the header with the given include name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, c)| c.as_ref())
+    }
+
+    /// Iterates over `(include name, contents)` pairs, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|(n, c)| (n.as_ref(), c.as_ref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
 }
 
+/// Options that older nvrtc versions don't know, and the version that added them.
+const MIN_NVRTC_VERSIONS: &[(&str, (i32, i32))] = &[
+    ("--std=c++20", (12, 0)),
+    ("--dopt", (11, 7)),
+    ("--split-compile", (12, 1)),
+    ("--time", (12, 1)),
+];
+
 impl CompileOptions {
+    /// Checks for options that contradict each other, which [compile_ptx_with_opts()] and
+    /// friends also do before compiling.
+    pub fn validate(&self) -> Result<(), CompileError> {
+        let conflict = |option, conflicts_with| {
+            Err(CompileError::ConflictingOptions {
+                option,
+                conflicts_with,
+            })
+        };
+        // `use_fast_math` only builds `--fmad=true`, so it isn't checked against
+        // `ftz`/`prec_*`/`fmad`, which have always compiled together with it.
+        if self
+            .defines
+            .iter()
+            .any(|(name, _)| self.undefines.contains(name))
+        {
+            return conflict("defines", "undefines");
+        }
+        Ok(())
+    }
+
+    /// The built options that the nvrtc of `version`, e.g. from [result::version()], doesn't
+    /// support. Compiling warns about these in [Ptx::diagnostics()] and
+    /// [CompileError::diagnostics()].
+    pub fn unsupported_options(&self, version: (i32, i32)) -> Vec<String> {
+        self.clone()
+            .build()
+            .into_iter()
+            .filter(|option| {
+                MIN_NVRTC_VERSIONS
+                    .iter()
+                    .any(|(prefix, min)| option.starts_with(prefix) && version < *min)
+            })
+            .collect()
+    }
+
+    /// Warnings for [CompileOptions::unsupported_options()] of the loaded nvrtc.
+    fn version_warnings(&self) -> Vec<Diagnostic> {
+        let Ok(version) = result::version() else {
+            return Vec::new();
+        };
+        self.unsupported_options(version)
+            .into_iter()
+            .map(|option| {
+                let (_, (major, minor)) = MIN_NVRTC_VERSIONS
+                    .iter()
+                    .find(|(prefix, _)| option.starts_with(prefix))
+                    .unwrap();
+                Diagnostic {
+                    file: None,
+                    line: None,
+                    column: None,
+                    severity: Severity::Warning,
+                    code: None,
+                    message: std::format!(
+                        "{option} requires nvrtc {major}.{minor} or newer, but this is {}.{}",
+                        version.0,
+                        version.1
+                    ),
+                    source_line: None,
+                }
+            })
+            .collect()
+    }
+
+    /// One copy of these options per architecture in `archs`, for multi-target builds, e.g.
+    /// with `CompileJobSet`. nvrtc compiles one architecture at a time.
+    pub fn for_archs(&self, archs: &[&'static str]) -> Vec<Self> {
+        archs
+            .iter()
+            .map(|&arch| Self {
+                arch: Some(arch),
+                ..self.clone()
+            })
+            .collect()
+    }
+
     pub(crate) fn build(self) -> Vec<String> {
         let mut options: Vec<String> = Vec::new();
 
@@ -277,6 +850,67 @@ impl CompileOptions {
             options.push(std::format!("--gpu-architecture={arch}"))
         }
 
+        if let Some(threads) = self.split_compile {
+            options.push(std::format!("--split-compile={threads}"));
+        }
+
+        if let Some(std) = self.std {
+            options.push(std::format!("--std={}", std.as_str()));
+        }
+
+        if self.device_debug {
+            options.push("--device-debug".into());
+        }
+
+        if self.lineinfo {
+            options.push("--generate-line-info".into());
+        }
+
+        if let Some(v) = self.dopt {
+            options.push(std::format!("--dopt={}", if v { "on" } else { "off" }));
+        }
+
+        if let Some(v) = self.rdc {
+            options.push(std::format!("--relocatable-device-code={v}"));
+        }
+
+        if self.dlto {
+            options.push("--dlink-time-opt".into());
+        }
+
+        if self.extra_device_vectorization {
+            options.push("--extra-device-vectorization".into());
+        }
+
+        if self.default_device {
+            options.push("--device-as-default-execution-space".into());
+        }
+
+        for (name, value) in self.defines {
+            match value {
+                Some(value) => options.push(std::format!("--define-macro={name}={value}")),
+                None => options.push(std::format!("--define-macro={name}")),
+            }
+        }
+
+        for name in self.undefines {
+            options.push(std::format!("--undefine-macro={name}"));
+        }
+
+        if self.restrict {
+            options.push("--restrict".into());
+        }
+
+        if let Some(file) = self.time {
+            options.push(std::format!("--time={file}"));
+        }
+
+        if self.warnings_as_errors {
+            options.push("--Werror=all-warnings".into());
+        }
+
+        // `name_expressions` and `headers` are not options, they are passed to `Program`.
+
         for option in self.options {
             options.push(option);
         }
@@ -288,6 +922,7 @@ impl CompileOptions {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nvrtc::Severity;
 
     #[test]
     fn test_compile_no_opts() {
@@ -301,6 +936,124 @@ mod tests {
         compile_ptx_with_opts(SRC, Default::default()).unwrap();
     }
 
+    #[test]
+    fn test_compile_cubin() {
+        const SRC: &str = "extern \"C\" __global__ void kernel(float *out) { out[0] = 1.0f; }";
+        let opts = CompileOptions {
+            arch: Some("sm_75"),
+            ..Default::default()
+        };
+        let cubin = compile_cubin(SRC, opts).unwrap();
+        assert!(matches!(&cubin.kind, PtxKind::Binary(data) if data.starts_with(b"\x7fELF")));
+
+        let opts = CompileOptions {
+            arch: Some("compute_75"),
+            ..Default::default()
+        };
+        assert!(matches!(
+            compile_cubin(SRC, opts),
+            Err(CompileError::GetCubinError(_))
+        ));
+    }
+
+    #[test]
+    fn test_compile_name_expressions() {
+        const SRC: &str = "template <typename T, int N> __global__ void fill(T *out) {
+            out[threadIdx.x] = T(N);
+        }";
+        let opts = CompileOptions {
+            name_expressions: std::vec!["fill<float, 128>".into(), "fill<int, 1>".into()],
+            ..Default::default()
+        };
+        let ptx = compile_ptx_with_opts(SRC, opts).unwrap();
+        assert_eq!(
+            ptx.lowered_name("fill<float, 128>"),
+            Some("_Z4fillIfLi128EEvPT_")
+        );
+        assert_eq!(ptx.lowered_name("fill<int, 1>"), Some("_Z4fillIiLi1EEvPT_"));
+        assert_eq!(ptx.lowered_names().count(), 2);
+        assert!(ptx.diagnostics().is_empty());
+
+        let opts = CompileOptions {
+            name_expressions: std::vec!["fill<float>".into()],
+            ..Default::default()
+        };
+        assert!(compile_ptx_with_opts(SRC, opts).is_err());
+    }
+
+    #[test]
+    fn test_compile_with_headers() {
+        const SRC: &str = "#include \"scale.h\"
+        extern \"C\" __global__ void kernel(float *x) { x[0] *= SCALE; }";
+        let opts = CompileOptions {
+            headers: HeaderSet::from_static(&[("scale.h", "#define SCALE 2.0f")]),
+            ..Default::default()
+        };
+        compile_ptx_with_opts(SRC, opts).unwrap();
+        assert!(compile_ptx(SRC).is_err());
+    }
+
+    #[test]
+    fn test_ptx_info_from_file() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/sin.ptx");
+        let info = Ptx::from_file(path).info().unwrap();
+        assert_eq!(info.entry("sin_kernel").unwrap().params.len(), 3);
+
+        let cubin =
+            std::env::temp_dir().join(std::format!("cudarc-ptx-info-{}.cubin", std::process::id()));
+        std::fs::write(&cubin, b"\x7fELF\x02\x01").unwrap();
+        assert_eq!(
+            Ptx::from_file(&cubin).info().unwrap_err(),
+            PtxParseError::NotText
+        );
+        std::fs::remove_file(&cubin).unwrap();
+        assert_eq!(
+            Ptx::from_file(&cubin).info().unwrap_err(),
+            PtxParseError::NotText
+        );
+    }
+
+    #[test]
+    fn test_header_set() {
+        let mut headers = HeaderSet::from_static(&[("a.h", "int a;"), ("b.h", "int b;")]);
+        headers.insert("a.h", std::format!("int {};", "c"));
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get("a.h"), Some("int c;"));
+
+        let mut more = HeaderSet::new().with("c.h", "int c;");
+        more.extend_from(&headers);
+        assert_eq!(
+            more.iter().collect::<Vec<_>>(),
+            [("c.h", "int c;"), ("a.h", "int c;"), ("b.h", "int b;")]
+        );
+        assert!(HeaderSet::new().is_empty());
+    }
+
+    #[test]
+    fn test_compile_diagnostics() {
+        let ptx = compile_ptx("extern \"C\" __global__ void kernel() { int unused; }").unwrap();
+        assert!(ptx
+            .diagnostics()
+            .iter()
+            .any(|d| d.severity == Severity::Warning && d.line == Some(1)));
+
+        let err = compile_ptx("extern \"C\" __global__ void kernel() { foo(); }").unwrap_err();
+        let diagnostic = &err.diagnostics()[0];
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.file.as_deref(), Some("default_program"));
+        assert!(diagnostic.message.contains("\"foo\""));
+    }
+
+    #[test]
+    fn test_pick_sm_arch() {
+        let supported = [75, 80, 86, 89, 90];
+        assert_eq!(pick_sm_arch(&supported, (8, 6)), Some("sm_86"));
+        assert_eq!(pick_sm_arch(&supported, (8, 7)), Some("sm_86"));
+        assert_eq!(pick_sm_arch(&supported, (8, 9)), Some("sm_89"));
+        assert_eq!(pick_sm_arch(&supported, (7, 0)), None);
+        assert_eq!(pick_sm_arch(&supported, (12, 0)), None);
+    }
+
     #[test]
     fn test_compile_options_build_none() {
         let opts: CompileOptions = Default::default();
@@ -325,4 +1078,238 @@ mod tests {
         };
         assert_eq!(&opts.build(), &["--prec-div=false", "--maxrregcount=60"]);
     }
+
+    #[test]
+    fn test_compile_options_build_each() {
+        let cases: std::vec::Vec<(CompileOptions, &[&str])> = std::vec![
+            (
+                CompileOptions {
+                    ftz: Some(false),
+                    ..Default::default()
+                },
+                &["--ftz=false"],
+            ),
+            (
+                CompileOptions {
+                    prec_sqrt: Some(true),
+                    ..Default::default()
+                },
+                &["--prec-sqrt=true"],
+            ),
+            (
+                CompileOptions {
+                    fmad: Some(false),
+                    ..Default::default()
+                },
+                &["--fmad=false"],
+            ),
+            (
+                CompileOptions {
+                    use_fast_math: Some(true),
+                    ..Default::default()
+                },
+                &["--fmad=true"],
+            ),
+            (
+                CompileOptions {
+                    use_fast_math: Some(false),
+                    ..Default::default()
+                },
+                &[],
+            ),
+            (
+                CompileOptions {
+                    include_paths: std::vec!["a".into(), "b".into()],
+                    ..Default::default()
+                },
+                &["--include-path=a", "--include-path=b"],
+            ),
+            (
+                CompileOptions {
+                    std: Some(CppStd::Cpp20),
+                    ..Default::default()
+                },
+                &["--std=c++20"],
+            ),
+            (
+                CompileOptions {
+                    device_debug: true,
+                    ..Default::default()
+                },
+                &["--device-debug"],
+            ),
+            (
+                CompileOptions {
+                    lineinfo: true,
+                    ..Default::default()
+                },
+                &["--generate-line-info"],
+            ),
+            (
+                CompileOptions {
+                    dopt: Some(true),
+                    ..Default::default()
+                },
+                &["--dopt=on"],
+            ),
+            (
+                CompileOptions {
+                    dopt: Some(false),
+                    ..Default::default()
+                },
+                &["--dopt=off"],
+            ),
+            (
+                CompileOptions {
+                    rdc: Some(true),
+                    ..Default::default()
+                },
+                &["--relocatable-device-code=true"],
+            ),
+            (
+                CompileOptions {
+                    dlto: true,
+                    ..Default::default()
+                },
+                &["--dlink-time-opt"],
+            ),
+            (
+                CompileOptions {
+                    extra_device_vectorization: true,
+                    ..Default::default()
+                },
+                &["--extra-device-vectorization"],
+            ),
+            (
+                CompileOptions {
+                    default_device: true,
+                    ..Default::default()
+                },
+                &["--device-as-default-execution-space"],
+            ),
+            (
+                CompileOptions {
+                    defines: std::vec![("A".into(), None), ("B".into(), Some("2".into()))],
+                    undefines: std::vec!["C".into()],
+                    ..Default::default()
+                },
+                &[
+                    "--define-macro=A",
+                    "--define-macro=B=2",
+                    "--undefine-macro=C"
+                ],
+            ),
+            (
+                CompileOptions {
+                    restrict: true,
+                    ..Default::default()
+                },
+                &["--restrict"],
+            ),
+            (
+                CompileOptions {
+                    time: Some("times.csv".into()),
+                    ..Default::default()
+                },
+                &["--time=times.csv"],
+            ),
+            (
+                CompileOptions {
+                    warnings_as_errors: true,
+                    ..Default::default()
+                },
+                &["--Werror=all-warnings"],
+            ),
+            (
+                CompileOptions {
+                    name: Some("kernel.cu".into()),
+                    name_expressions: std::vec!["f<int>".into()],
+                    headers: HeaderSet::new().with("a.h", ""),
+                    ..Default::default()
+                },
+                &[],
+            ),
+        ];
+        for (opts, expected) in cases {
+            assert_eq!(opts.clone().build(), expected, "{opts:?}");
+            assert!(opts.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_compile_options_validate() {
+        let conflict = |opts: CompileOptions| match opts.validate() {
+            Err(CompileError::ConflictingOptions {
+                option,
+                conflicts_with,
+            }) => (option, conflicts_with),
+            r => panic!("{r:?}"),
+        };
+        // Only `--fmad=true` is built for `use_fast_math`, so these compiled before.
+        assert!(CompileOptions {
+            use_fast_math: Some(true),
+            ftz: Some(false),
+            prec_sqrt: Some(true),
+            prec_div: Some(true),
+            ..Default::default()
+        }
+        .validate()
+        .is_ok());
+        assert_eq!(
+            conflict(CompileOptions {
+                defines: std::vec![("N".into(), Some("1".into()))],
+                undefines: std::vec!["N".into()],
+                ..Default::default()
+            }),
+            ("defines", "undefines")
+        );
+    }
+
+    #[test]
+    fn test_compile_options_unsupported() {
+        let opts = CompileOptions {
+            std: Some(CppStd::Cpp20),
+            split_compile: Some(4),
+            dopt: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            opts.unsupported_options((11, 8)),
+            ["--split-compile=4", "--std=c++20"]
+        );
+        assert_eq!(opts.unsupported_options((12, 0)), ["--split-compile=4"]);
+        assert!(opts.unsupported_options((12, 1)).is_empty());
+        assert_eq!(
+            opts.unsupported_options((11, 4)),
+            ["--split-compile=4", "--std=c++20", "--dopt=on"]
+        );
+    }
+
+    #[test]
+    fn test_compile_options_for_archs() {
+        let opts = CompileOptions {
+            arch: Some("sm_70"),
+            ftz: Some(true),
+            ..Default::default()
+        };
+        let per_arch = opts.for_archs(&["sm_80", "sm_90"]);
+        assert_eq!(per_arch.len(), 2);
+        assert_eq!(per_arch[0].arch, Some("sm_80"));
+        assert_eq!(per_arch[1].arch, Some("sm_90"));
+        assert_eq!(per_arch[1].ftz, Some(true));
+        assert!(opts.for_archs(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_compile_options_build_split_compile() {
+        let opts = CompileOptions {
+            arch: Some("sm_80"),
+            split_compile: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            &opts.build(),
+            &["--gpu-architecture=sm_80", "--split-compile=0"]
+        );
+    }
 }
@@ -13,6 +13,7 @@
 
 use super::sys;
 use crate::driver::sys::cudaError_enum;
+use crate::ErrorKind;
 use core::ffi::{c_uchar, c_void};
 use std::ffi::CStr;
 use std::mem::MaybeUninit;
@@ -89,6 +90,24 @@ impl std::fmt::Display for RuntimeError {
 #[cfg(feature = "std")]
 impl std::error::Error for RuntimeError {}
 
+impl RuntimeError {
+    /// The [ErrorKind] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.0 {
+            sys::cudaError_t::cudaErrorMemoryAllocation => ErrorKind::OutOfMemory,
+            sys::cudaError_t::cudaErrorInvalidValue => ErrorKind::InvalidValue,
+            sys::cudaError_t::cudaErrorNotSupported => ErrorKind::NotSupported,
+            sys::cudaError_t::cudaErrorInitializationError
+            | sys::cudaError_t::cudaErrorNoDevice => ErrorKind::NotInitialized,
+            sys::cudaError_t::cudaErrorInvalidResourceHandle => ErrorKind::InvalidHandle,
+            sys::cudaError_t::cudaErrorLaunchFailure
+            | sys::cudaError_t::cudaErrorIllegalAddress => ErrorKind::LaunchFailure,
+            sys::cudaError_t::cudaErrorInvalidPtx => ErrorKind::Compilation,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
 /// CUDA device flags.
 ///
 /// See [cudaSetDeviceFlags() docs](https://docs.nvidia.com/cuda/cuda-runtime-api/group__CUDART__DEVICE.html#group__CUDART__DEVICE_1g69e73c7dda3fc05306ae7c811a690fac)
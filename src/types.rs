@@ -4,11 +4,34 @@
 //! For example, `f32` in rust corresponds to `float` in a cuda
 //! kernel.
 
+use std::string::String;
+
 /// Maps a rust type to it's corresponding [CudaTypeName::NAME] in cuda c++ land.
+///
+/// With the `derive` feature, `#[derive(CudaTypeName)]` implements this for `#[repr(C)]`
+/// structs, along with a [CudaTypeName::definition()] that can be prepended to kernel source.
 pub trait CudaTypeName {
     const NAME: &'static str;
+
+    /// Writes the cuda c++ definition of this type, if it needs one. Builtin types write nothing.
+    fn write_definition(_f: &mut dyn core::fmt::Write) -> core::fmt::Result {
+        Ok(())
+    }
+
+    /// The cuda c++ definition of this type, followed by `static_assert`s that its size and
+    /// alignment (and those of its fields) match the rust type.
+    ///
+    /// Definitions of nested struct fields are not included, and must be prepended first.
+    fn definition() -> String {
+        let mut definition = String::new();
+        Self::write_definition(&mut definition).unwrap();
+        definition
+    }
 }
 
+#[cfg(feature = "derive")]
+pub use cudarc_derive::CudaTypeName;
+
 macro_rules! cuda_type {
     ($RustTy:ty, $CudaTy:expr) => {
         impl CudaTypeName for $RustTy {
@@ -44,3 +67,129 @@ cuda_type!(float4::F4E2M1, "__nv_fp4_e2m1");
 cuda_type!(float4::F4E2M1x2, "__nv_fp4x2_e2m1");
 #[cfg(feature = "f4")]
 cuda_type!(float4::E8M0, "__nv_fp8_e8m0");
+
+/// The derives reject structs whose layout may not match c++, and fields that don't implement
+/// the derived trait:
+///
+/// ```compile_fail
+/// #[derive(Clone, Copy, cudarc::driver::DeviceRepr)]
+/// struct NotReprC {
+///     x: f32,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[derive(Clone, Copy, cudarc::driver::DeviceRepr)]
+/// #[repr(C, packed)]
+/// struct Packed {
+///     x: u8,
+///     y: f32,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[derive(cudarc::types::CudaTypeName)]
+/// #[repr(C)]
+/// #[repr(packed(2))]
+/// struct Packed {
+///     x: u8,
+///     y: f32,
+/// }
+/// ```
+///
+/// ```compile_fail,E0277
+/// #[derive(Clone, Copy, cudarc::driver::DeviceRepr)]
+/// #[repr(C)]
+/// struct HostPointer {
+///     x: &'static f32,
+/// }
+/// ```
+///
+/// ```compile_fail,E0277
+/// #[derive(Clone, Copy, cudarc::driver::ValidAsZeroBits)]
+/// #[repr(C)]
+/// struct NonNull {
+///     x: core::num::NonZeroU32,
+/// }
+/// ```
+///
+/// While this compiles:
+///
+/// ```
+/// #[derive(Clone, Copy, cudarc::driver::DeviceRepr, cudarc::driver::ValidAsZeroBits)]
+/// #[derive(cudarc::types::CudaTypeName)]
+/// #[repr(C)]
+/// struct Particle {
+///     pos: [f32; 3],
+///     mass: f64,
+/// }
+/// ```
+#[cfg(all(doctest, feature = "derive", feature = "driver"))]
+pub struct DeriveCompileFailTests;
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(CudaTypeName)]
+    #[repr(C)]
+    struct Particle {
+        pos: [f32; 3],
+        mass: f64,
+        grid: [[u8; 2]; 4],
+    }
+
+    #[allow(dead_code)]
+    #[derive(CudaTypeName)]
+    #[repr(C)]
+    struct Pair(u32, i16);
+
+    #[test]
+    fn test_derive_cuda_type_name() {
+        assert_eq!(Particle::NAME, "Particle");
+        assert_eq!(f32::definition(), "");
+        assert_eq!(
+            Particle::definition(),
+            "struct Particle {
+    float pos[3];
+    double mass;
+    unsigned char grid[4][2];
+};
+static_assert(sizeof(Particle) == 32, \"Particle has a different size in rust\");
+static_assert(alignof(Particle) == 8, \"Particle has a different alignment in rust\");
+static_assert(sizeof(Particle::pos) == 12, \"Particle::pos has a different size in rust\");
+static_assert(alignof(decltype(Particle::pos)) == 4, \"Particle::pos has a different alignment in rust\");
+static_assert(sizeof(Particle::mass) == 8, \"Particle::mass has a different size in rust\");
+static_assert(alignof(decltype(Particle::mass)) == 8, \"Particle::mass has a different alignment in rust\");
+static_assert(sizeof(Particle::grid) == 8, \"Particle::grid has a different size in rust\");
+static_assert(alignof(decltype(Particle::grid)) == 1, \"Particle::grid has a different alignment in rust\");
+"
+        );
+    }
+
+    #[test]
+    fn test_derive_cuda_type_name_tuple_struct() {
+        let definition = Pair::definition();
+        assert!(definition.starts_with("struct Pair {\n    unsigned int _0;\n    short _1;\n};\n"));
+        assert!(definition.contains("static_assert(sizeof(Pair) == 8,"));
+    }
+
+    #[cfg(feature = "driver")]
+    #[test]
+    fn test_derive_device_repr() {
+        use crate::driver::{DeviceRepr, ValidAsZeroBits};
+
+        #[allow(dead_code)]
+        #[derive(Clone, Copy, DeviceRepr, ValidAsZeroBits)]
+        #[repr(C)]
+        struct Params<T> {
+            scale: T,
+            offsets: [u32; 4],
+        }
+
+        fn is_device_repr<T: DeviceRepr + ValidAsZeroBits>() {}
+        is_device_repr::<Params<f32>>();
+        is_device_repr::<Params<Params<u8>>>();
+    }
+}